@@ -0,0 +1,78 @@
+//! Compares re-parsing a whole workflow against re-parsing one of its
+//! jobs or steps, as an editor would after a small edit.
+
+use std::{fmt::Write, hint::black_box, str::FromStr};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use github_actions_models::workflow::Workflow;
+
+const JOBS: usize = 200;
+const STEPS: usize = 10;
+
+const JOB: &str = "\
+runs-on: ubuntu-latest
+needs: [job-0]
+steps:
+  - uses: actions/checkout@v4
+    with:
+      persist-credentials: false
+  - name: build
+    run: make -j${{ matrix.jobs }}
+    env:
+      TOKEN: ${{ secrets.TOKEN }}
+";
+
+const STEP: &str = "\
+name: build
+run: make -j${{ matrix.jobs }}
+env:
+  TOKEN: ${{ secrets.TOKEN }}
+";
+
+/// A workflow with `JOBS` jobs of `STEPS` steps each.
+fn large_workflow() -> String {
+    let mut workflow = "on: [push, pull_request]\njobs:\n".to_string();
+    for job in 0..JOBS {
+        writeln!(
+            workflow,
+            "  job-{job}:\n    runs-on: ubuntu-latest\n    steps:"
+        )
+        .unwrap();
+        for step in 0..STEPS {
+            writeln!(
+                workflow,
+                "      - name: step {step}\n        run: echo ${{{{ matrix.os }}}} {step}"
+            )
+            .unwrap();
+        }
+    }
+    workflow
+}
+
+fn reparse(c: &mut Criterion) {
+    let contents = large_workflow();
+    let mut workflow = Workflow::from_str(&contents).unwrap();
+
+    let mut group = c.benchmark_group("reparse");
+    group.bench_function("full", |b| {
+        b.iter(|| Workflow::from_str(black_box(&contents)).unwrap())
+    });
+    group.bench_function("job", |b| {
+        b.iter(|| {
+            workflow
+                .reparse_job(black_box("job-100"), black_box(JOB))
+                .unwrap()
+        })
+    });
+    group.bench_function("step", |b| {
+        b.iter(|| {
+            workflow
+                .reparse_step(black_box("job-150"), black_box(5), black_box(STEP))
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, reparse);
+criterion_main!(benches);
@@ -0,0 +1,190 @@
+//! Check that a repository's `dependabot.yml` covers the dependencies its
+//! workflows actually have.
+//!
+//! Workflows that use actions or reusable workflows from other repositories
+//! need a `github-actions` update entry, and workflows that use Docker
+//! images (via `docker://` steps, job containers, or services) need a
+//! `docker` update entry.
+//!
+//! ```console
+//! $ cargo run --example check-dependabot-coverage -- path/to/repo
+//! ```
+
+use std::{env, fs, path::Path, process::ExitCode};
+
+use github_actions_models::{
+    common::{expr::LoE, Uses},
+    dependabot::v2::{Dependabot, PackageEcosystem},
+    workflow::{job, Job, Workflow},
+};
+
+/// Returns the ecosystems that `workflow`'s dependencies belong to.
+fn workflow_ecosystems(workflow: &Workflow) -> Vec<PackageEcosystem> {
+    let mut actions = false;
+    let mut docker = false;
+
+    for job in workflow.jobs.values() {
+        let job = match job {
+            Job::ReusableWorkflowCallJob(job) => {
+                actions |= matches!(job.uses, Uses::Repository(_));
+                continue;
+            }
+            Job::NormalJob(job) => job,
+        };
+
+        for step in &job.steps {
+            if let job::StepBody::Uses { uses, .. } = &step.body {
+                actions |= matches!(uses, Uses::Repository(_));
+                docker |= matches!(uses, Uses::Docker(_));
+            }
+        }
+
+        for container in job.container.iter().chain(job.services.values()) {
+            if let LoE::Literal(container) = container {
+                docker |= matches!(container.image_uses(), Ok(Some(_)));
+            }
+        }
+    }
+
+    let mut ecosystems = vec![];
+    if actions {
+        ecosystems.push(PackageEcosystem::GithubActions);
+    }
+    if docker {
+        ecosystems.push(PackageEcosystem::Docker);
+    }
+    ecosystems
+}
+
+/// Returns the ecosystems needed by `workflows` that `dependabot` has
+/// no update entry for.
+fn missing_ecosystems(workflows: &[Workflow], dependabot: &Dependabot) -> Vec<PackageEcosystem> {
+    let mut missing = vec![];
+    for ecosystem in workflows.iter().flat_map(workflow_ecosystems) {
+        let covered = dependabot
+            .updates
+            .iter()
+            .any(|update| update.package_ecosystem == ecosystem);
+        if !covered && !missing.contains(&ecosystem) {
+            missing.push(ecosystem);
+        }
+    }
+    missing
+}
+
+fn main() -> ExitCode {
+    let Some(repo) = env::args_os().nth(1) else {
+        eprintln!("usage: check-dependabot-coverage <repo>");
+        return ExitCode::FAILURE;
+    };
+    let github = Path::new(&repo).join(".github");
+
+    let Some(dependabot) = ["dependabot.yml", "dependabot.yaml"]
+        .into_iter()
+        .find_map(|name| fs::read(github.join(name)).ok())
+    else {
+        eprintln!("no dependabot.yml in {}", github.display());
+        return ExitCode::FAILURE;
+    };
+    let dependabot = match Dependabot::from_slice(&dependabot) {
+        Ok(dependabot) => dependabot,
+        Err(e) => {
+            eprintln!("invalid dependabot.yml: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut workflows = vec![];
+    for entry in fs::read_dir(github.join("workflows")).into_iter().flatten() {
+        let Ok(path) = entry.map(|entry| entry.path()) else {
+            continue;
+        };
+        match fs::read(&path).map(|contents| Workflow::from_slice(&contents)) {
+            Ok(Ok(workflow)) => workflows.push(workflow),
+            Ok(Err(e)) => eprintln!("skipping {}: {e}", path.display()),
+            Err(e) => eprintln!("couldn't read {}: {e}", path.display()),
+        }
+    }
+
+    let missing = missing_ecosystems(&workflows, &dependabot);
+    for ecosystem in &missing {
+        println!("missing dependabot coverage for {ecosystem}");
+    }
+
+    if missing.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use github_actions_models::{
+        dependabot::v2::{Dependabot, PackageEcosystem},
+        workflow::Workflow,
+    };
+
+    use super::missing_ecosystems;
+
+    #[test]
+    fn test_missing_ecosystems() {
+        let workflows: [Workflow; 2] = [
+            "
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: ./.github/actions/local
+"
+            .parse()
+            .unwrap(),
+            "
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    services:
+      db:
+        image: postgres:16
+    steps:
+      - run: make test
+"
+            .parse()
+            .unwrap(),
+        ];
+
+        let dependabot: Dependabot = "
+version: 2
+updates:
+  - package-ecosystem: cargo
+    directory: /
+    schedule:
+      interval: weekly
+"
+        .parse()
+        .unwrap();
+        assert_eq!(
+            missing_ecosystems(&workflows, &dependabot),
+            [PackageEcosystem::GithubActions, PackageEcosystem::Docker]
+        );
+
+        let dependabot: Dependabot = "
+version: 2
+updates:
+  - package-ecosystem: github-actions
+    directory: /
+    schedule:
+      interval: weekly
+  - package-ecosystem: docker
+    directory: /
+    schedule:
+      interval: weekly
+"
+        .parse()
+        .unwrap();
+        assert!(missing_ecosystems(&workflows, &dependabot).is_empty());
+    }
+}
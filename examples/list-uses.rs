@@ -0,0 +1,182 @@
+//! Print an inventory of every action, reusable workflow, and Docker image
+//! used by the workflows and actions under a directory, along with whether
+//! each one is pinned.
+//!
+//! ```console
+//! $ cargo run --example list-uses -- path/to/repo
+//! ```
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use github_actions_models::{action::Action, common::Uses, workflow::Workflow};
+
+/// How a single `uses:` clause is pinned.
+#[derive(Debug, PartialEq)]
+enum Pinning {
+    /// Pinned to a commit SHA or image digest.
+    Pinned,
+    /// Pinned to a mutable ref or tag, or not at all.
+    Unpinned,
+    /// A local action or workflow, which is pinned by definition.
+    Local,
+}
+
+fn pinning(uses: &Uses) -> Pinning {
+    match uses {
+        Uses::Local(_) => Pinning::Local,
+        Uses::Repository(uses) => match &uses.git_ref {
+            Some(git_ref)
+                if git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Pinning::Pinned
+            }
+            _ => Pinning::Unpinned,
+        },
+        Uses::Docker(uses) if uses.is_digest_pinned() => Pinning::Pinned,
+        Uses::Docker(_) => Pinning::Unpinned,
+    }
+}
+
+/// Returns every `uses:` clause in a composite action, in document order.
+fn action_uses(action: &Action) -> Vec<&Uses> {
+    action
+        .steps()
+        .iter()
+        .filter_map(|step| step.uses())
+        .collect()
+}
+
+/// Collect every workflow and action definition under `dir`.
+fn find_definitions(dir: &Path, found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_definitions(&path, found)?;
+        } else if matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("yml" | "yaml")
+        ) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns each `uses:` clause in the definition at `path`, or `None` if
+/// it's neither a workflow nor an action.
+fn definition_uses(path: &Path, contents: &[u8]) -> Option<Vec<(String, Pinning)>> {
+    let is_action = matches!(
+        path.file_name().and_then(|name| name.to_str()),
+        Some("action.yml" | "action.yaml")
+    );
+
+    let uses = if is_action {
+        action_uses(&Action::from_slice(contents).ok()?)
+            .into_iter()
+            .map(|uses| (uses.to_string(), pinning(uses)))
+            .collect()
+    } else {
+        Workflow::from_slice(contents)
+            .ok()?
+            .uses()
+            .map(|(uses, _)| (uses.to_string(), pinning(uses)))
+            .collect()
+    };
+
+    Some(uses)
+}
+
+fn main() -> ExitCode {
+    let Some(dir) = env::args_os().nth(1) else {
+        eprintln!("usage: list-uses <dir>");
+        return ExitCode::FAILURE;
+    };
+
+    let mut paths = vec![];
+    if let Err(e) = find_definitions(Path::new(&dir), &mut paths) {
+        eprintln!("couldn't walk {}: {e}", Path::new(&dir).display());
+        return ExitCode::FAILURE;
+    }
+    paths.sort();
+
+    for path in paths {
+        let Ok(contents) = fs::read(&path) else {
+            eprintln!("couldn't read {}", path.display());
+            continue;
+        };
+        let Some(uses) = definition_uses(&path, &contents) else {
+            continue;
+        };
+
+        for (uses, pinning) in uses {
+            let pinning = match pinning {
+                Pinning::Pinned => "pinned",
+                Pinning::Unpinned => "unpinned",
+                Pinning::Local => "local",
+            };
+            println!("{pinning:<8} {uses}  ({})", path.display());
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{definition_uses, Pinning};
+
+    #[test]
+    fn test_definition_uses() {
+        let workflow = br#"
+on: push
+jobs:
+  call:
+    uses: octo-org/example/.github/workflows/ci.yml@main
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@8f4b7f84864484a7bf31766abe9204da3cbe65b3
+      - uses: ./.github/actions/setup
+      - uses: docker://alpine:3.8
+      - run: make test
+"#;
+        assert_eq!(
+            definition_uses(Path::new(".github/workflows/ci.yml"), workflow).unwrap(),
+            [
+                (
+                    "octo-org/example/.github/workflows/ci.yml@main".into(),
+                    Pinning::Unpinned
+                ),
+                (
+                    "actions/checkout@8f4b7f84864484a7bf31766abe9204da3cbe65b3".into(),
+                    Pinning::Pinned
+                ),
+                ("./.github/actions/setup".into(), Pinning::Local),
+                ("docker://alpine:3.8".into(), Pinning::Unpinned),
+            ]
+        );
+
+        let action = br#"
+name: setup
+description: set things up
+runs:
+  using: composite
+  steps:
+    - uses: docker://alpine@sha256:abcd
+    - run: echo hi
+      shell: bash
+"#;
+        assert_eq!(
+            definition_uses(Path::new(".github/actions/setup/action.yml"), action).unwrap(),
+            [("docker://alpine@sha256:abcd".into(), Pinning::Pinned)]
+        );
+
+        assert!(definition_uses(Path::new("config.yml"), b"foo: bar").is_none());
+    }
+}
@@ -0,0 +1,66 @@
+//! Print a workflow's job dependency graph as a Mermaid flowchart.
+//!
+//! ```console
+//! $ cargo run --example workflow-graph -- .github/workflows/ci.yml
+//! ```
+
+use std::{env, fs, process::ExitCode};
+
+use github_actions_models::workflow::Workflow;
+
+/// Render the workflow in `contents` as a Mermaid flowchart.
+fn graph(contents: &[u8]) -> Result<String, github_actions_models::Error> {
+    Ok(Workflow::from_slice(contents)?.to_mermaid())
+}
+
+fn main() -> ExitCode {
+    let Some(path) = env::args_os().nth(1) else {
+        eprintln!("usage: workflow-graph <workflow.yml>");
+        return ExitCode::FAILURE;
+    };
+
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("couldn't read {}: {e}", path.to_string_lossy());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match graph(&contents) {
+        Ok(graph) => {
+            print!("{graph}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("invalid workflow: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::graph;
+
+    #[test]
+    fn test_graph() {
+        let workflow = b"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps: []
+  test:
+    needs: build
+    runs-on: ubuntu-latest
+    steps: []
+";
+        assert_eq!(
+            graph(workflow).unwrap(),
+            "graph TD\n    job_build[\"build\"]\n    job_test[\"test\"]\n    job_build --> job_test\n"
+        );
+
+        assert!(graph(b"jobs: {}").is_err());
+    }
+}
@@ -7,16 +7,22 @@
 //! [Metadata syntax for GitHub Actions]: https://docs.github.com/en/actions/creating-actions/metadata-syntax-for-github-actions
 //! [JSON Schema definition for GitHub Actions]: https://json.schemastore.org/github-action.json
 
+use std::{fmt::Display, path::Path, str::FromStr};
+
 use indexmap::IndexMap;
-use serde::Deserialize;
+use serde::{de, Deserialize};
+use serde_yaml::{Mapping, Value};
 
 use crate::common::{
+    diagnostic::Diagnostic,
     expr::{BoE, LoE},
-    Env, If, Uses,
+    from_buffered, Env, IdentifierKind, If, Shell, UnknownKey, Uses,
 };
 
+pub mod visit;
+
 /// A GitHub Actions action definition.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Action {
     pub name: String,
@@ -27,46 +33,282 @@ pub struct Action {
     #[serde(default)]
     pub outputs: IndexMap<String, Output>,
     pub runs: Runs,
+    pub branding: Option<Branding>,
+
+    /// Any other top-level keys.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl FromStr for Action {
+    type Err = crate::Error;
+
+    /// Parse an action definition from a YAML string.
+    ///
+    /// ```
+    /// use github_actions_models::action::Action;
+    ///
+    /// let action: Action = "
+    /// name: hello
+    /// runs:
+    ///   using: node20
+    ///   main: index.js
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// assert_eq!(action.name, "hello");
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::common::from_yaml(s)
+    }
+}
+
+impl Action {
+    /// Returns whether this is a composite action.
+    pub fn is_composite(&self) -> bool {
+        matches!(self.runs, Runs::Composite(_))
+    }
+
+    /// Returns whether this is a JavaScript action.
+    pub fn is_javascript(&self) -> bool {
+        matches!(self.runs, Runs::JavaScript(_))
+    }
+
+    /// Returns whether this is a Docker action.
+    pub fn is_docker(&self) -> bool {
+        matches!(self.runs, Runs::Docker(_))
+    }
+
+    /// Returns this action's inputs that may need to be supplied by
+    /// callers: those marked `required: true`, and those that don't say
+    /// either way but have no `default`.
+    ///
+    /// This is broader than [`Input::is_required`], since GitHub doesn't
+    /// enforce `required` and an input without a default is empty unless
+    /// supplied.
+    pub fn required_inputs(&self) -> impl Iterator<Item = (&str, &Input)> {
+        self.inputs
+            .iter()
+            .filter(|(_, input)| match input.required {
+                Some(required) => required,
+                None => input.default.is_none(),
+            })
+            .map(|(name, input)| (name.as_str(), input))
+    }
+
+    /// Returns this action's steps, in definition order.
+    ///
+    /// Only composite actions have steps; for other actions, this is empty.
+    pub fn steps(&self) -> &[Step] {
+        match &self.runs {
+            Runs::Composite(composite) => &composite.steps,
+            Runs::JavaScript(_) | Runs::Docker(_) => &[],
+        }
+    }
+
+    /// Check that each input name, output name, and composite step `id`
+    /// in this action is a valid identifier (see
+    /// [`is_identifier`](crate::common::is_identifier)), returning an
+    /// [`INVALID_IDENTIFIER`](crate::common::diagnostic::INVALID_IDENTIFIER)
+    /// diagnostic for each one that isn't.
+    ///
+    /// Results are in declaration order: inputs, then outputs, then steps.
+    pub fn validate_identifiers(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = crate::common::invalid_identifiers(
+            IdentifierKind::Input,
+            self.inputs.keys().map(|name| Some(name.as_str())),
+            None,
+        );
+        diagnostics.extend(crate::common::invalid_identifiers(
+            IdentifierKind::Output,
+            self.outputs.keys().map(|name| Some(name.as_str())),
+            None,
+        ));
+        diagnostics.extend(crate::common::invalid_identifiers(
+            IdentifierKind::Step,
+            self.steps().iter().map(|step| step.id.as_deref()),
+            None,
+        ));
+        diagnostics
+    }
+
+    /// Parse an action definition from raw YAML bytes.
+    ///
+    /// The bytes are decoded as UTF-8 (with or without a BOM), or as
+    /// UTF-16LE/BE if they begin with the corresponding BOM. Invalid
+    /// sequences produce [`crate::Error::Encoding`] rather than being replaced.
+    pub fn from_slice(contents: &[u8]) -> Result<Self, crate::Error> {
+        crate::common::from_yaml_slice(contents)
+    }
+
+    /// Parse an action definition from a YAML string, like [`FromStr`],
+    /// but also reject keys that GitHub doesn't accept.
+    ///
+    /// The first of [`Self::unknown_keys`], if any, produces a
+    /// [`crate::Error::UnknownKey`] with its path and location.
+    pub fn from_str_strict(s: &str) -> Result<Self, crate::Error> {
+        crate::common::strict::from_yaml_strict(s, Self::unknown_keys)
+    }
+
+    /// Load a action definition from the file at `path`.
+    ///
+    /// The file's contents are decoded as with [`Self::from_slice`].
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, crate::LoadError> {
+        crate::common::from_path(path.as_ref())
+    }
+
+    /// Returns every key that this action definition's models didn't
+    /// recognize, such as `input` in place of `inputs`.
+    ///
+    /// Unlike [`Self::from_str_strict`], this doesn't reject the action,
+    /// and returns all such keys rather than the first. Keys are collected
+    /// from the top level, each input and output, `runs:` and its steps,
+    /// and `branding:`, in that order.
+    pub fn unknown_keys(&self) -> Vec<UnknownKey<'_>> {
+        use crate::common::{strict::child, unknown_keys};
+
+        let mut unknown: Vec<_> = unknown_keys("", &self.extra).collect();
+        for (name, input) in &self.inputs {
+            unknown.extend(unknown_keys(&child("inputs", name), &input.extra));
+        }
+        for (name, output) in &self.outputs {
+            unknown.extend(unknown_keys(&child("outputs", name), &output.extra));
+        }
+        match &self.runs {
+            Runs::JavaScript(javascript) => unknown.extend(unknown_keys("runs", &javascript.extra)),
+            Runs::Composite(composite) => {
+                unknown.extend(unknown_keys("runs", &composite.extra));
+                for (index, step) in composite.steps.iter().enumerate() {
+                    unknown.extend(unknown_keys(&format!("runs.steps[{index}]"), &step.extra));
+                }
+            }
+            Runs::Docker(docker) => unknown.extend(unknown_keys("runs", &docker.extra)),
+        }
+        if let Some(branding) = &self.branding {
+            unknown.extend(unknown_keys("branding", &branding.extra));
+        }
+
+        unknown
+    }
 }
 
 /// An action input.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Input {
     pub description: String,
     pub required: Option<bool>,
     pub default: Option<String>,
+    /// A warning to show callers that pass this input.
+    #[serde(rename = "deprecationMessage")]
+    pub deprecation_message: Option<String>,
+
+    /// Any other keys in this input.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl Input {
+    /// Returns whether callers must supply this input, i.e. it's marked
+    /// `required: true` and has no `default` to fall back on.
+    pub fn is_required(&self) -> bool {
+        self.required == Some(true) && self.default.is_none()
+    }
 }
 
 /// An action output.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Output {
     pub description: String,
     // NOTE: not optional for composite actions, but this is not worth modeling.
     pub value: Option<String>,
+
+    /// Any other keys in this output.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+/// An action's `branding`, used to render it in the GitHub Marketplace.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Branding {
+    /// The name of a [Feather](https://feathericons.com/) icon.
+    pub icon: Option<String>,
+    pub color: Option<BrandingColor>,
+
+    /// Any other keys in this branding.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+/// The background color of an action's branding badge.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BrandingColor {
+    White,
+    Yellow,
+    Blue,
+    Green,
+    Orange,
+    Red,
+    Purple,
+    GrayDark,
 }
 
 /// An action `runs` definition.
 ///
 /// A `runs` definition can be either a JavaScript action, a "composite" action
 /// (made up of several constituent actions), or a Docker action.
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case", untagged)]
+#[derive(Clone)]
 pub enum Runs {
     JavaScript(JavaScript),
     Composite(Composite),
     Docker(Docker),
 }
 
+impl<'de> Deserialize<'de> for Runs {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // NOTE: `using:` fully determines the kind of action, so we dispatch
+        // on it rather than trying each variant with `#[serde(untagged)]`.
+        // This way, errors within the selected variant (like a malformed
+        // composite step) are reported directly.
+        let runs = Mapping::deserialize(deserializer)?;
+
+        let Some(using) = runs.get("using") else {
+            return Err(de::Error::missing_field("using"));
+        };
+        let Some(using) = using.as_str() else {
+            return Err(de::Error::custom("invalid `using`: expected a string"));
+        };
+
+        match using {
+            "composite" => from_buffered(Value::Mapping(runs)).map(Self::Composite),
+            "docker" => from_buffered(Value::Mapping(runs)).map(Self::Docker),
+            "node12" | "node16" | "node20" | "node24" => {
+                from_buffered(Value::Mapping(runs)).map(Self::JavaScript)
+            }
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "unknown `using` value `{using}`, expected one of: \
+                     `composite`, `docker`, `node12`, `node16`, `node20`, `node24`"
+                )))
+            }
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
 /// A `runs` definition for a JavaScript action.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct JavaScript {
-    /// The Node runtime to use for this action. This is one of:
-    ///
-    /// `"node12" | "node16" | "node20"`
-    pub using: String,
+    /// The Node runtime to use for this action.
+    pub using: NodeRuntime,
 
     /// The action's entrypoint, as a JavaScript file.
     pub main: String,
@@ -86,22 +328,61 @@ pub struct JavaScript {
     ///
     /// If not present, defaults to `always()`
     pub post_if: Option<If>,
+
+    /// Any other keys in this `runs:`.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+/// A Node runtime for a JavaScript action.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRuntime {
+    /// `node12`
+    Node12,
+    /// `node16`
+    Node16,
+    /// `node20`
+    Node20,
+    /// `node24`
+    Node24,
+}
+
+impl NodeRuntime {
+    /// Returns whether this runtime is deprecated by GitHub Actions.
+    pub fn is_deprecated(&self) -> bool {
+        matches!(self, Self::Node12 | Self::Node16)
+    }
+}
+
+impl Display for NodeRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Node12 => write!(f, "node12"),
+            Self::Node16 => write!(f, "node16"),
+            Self::Node20 => write!(f, "node20"),
+            Self::Node24 => write!(f, "node24"),
+        }
+    }
 }
 
 /// A `runs` definition for a composite action.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Composite {
-    /// Invariant: `"composite"`
     pub using: String,
     /// The individual steps that make up this composite action.
     pub steps: Vec<Step>,
+
+    /// Any other keys in this `runs:`.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// An individual composite action step.
 ///
-/// This is similar, but not identical to [`crate::workflow::job::Step`].
-#[derive(Deserialize)]
+/// This is similar, but not identical to `workflow::job::Step`.
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Step {
     /// An optional ID for this composite step.
@@ -121,11 +402,54 @@ pub struct Step {
     /// The `run:` or `uses:` body for this composite step.
     #[serde(flatten)]
     pub body: StepBody,
+
+    /// Any keys that are in neither this composite step nor its body.
+    #[serde(flatten, deserialize_with = "crate::common::step_extra::<StepBody, _>")]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl Step {
+    /// Returns the action used by this composite step, if it's a `uses:` step.
+    pub fn uses(&self) -> Option<&Uses> {
+        match &self.body {
+            StepBody::Uses { uses, .. } => Some(uses),
+            StepBody::Run { .. } => None,
+        }
+    }
+
+    /// Returns the command run by this composite step, if it's a `run:` step.
+    pub fn run(&self) -> Option<&str> {
+        match &self.body {
+            StepBody::Uses { .. } => None,
+            StepBody::Run { run, .. } => Some(run),
+        }
+    }
+
+    /// Returns the inputs passed to this composite step's action, if it's
+    /// a `uses:` step.
+    pub fn with(&self) -> Option<&Env> {
+        match &self.body {
+            StepBody::Uses { with, .. } => Some(with),
+            StepBody::Run { .. } => None,
+        }
+    }
+
+    /// Returns this composite step's environment mapping, which is empty
+    /// if unset.
+    pub fn env(&self) -> &LoE<Env> {
+        match &self.body {
+            StepBody::Uses { env, .. } | StepBody::Run { env, .. } => env,
+        }
+    }
 }
 
 /// The body of a composite action step.
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case", untagged)]
+#[derive(Clone, Deserialize)]
+#[serde(
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case",
+    remote = "Self"
+)]
 pub enum StepBody {
     /// A step that uses another GitHub Action.
     Uses {
@@ -136,6 +460,10 @@ pub enum StepBody {
         /// Any inputs to the action being used.
         #[serde(default)]
         with: Env,
+
+        /// An optional environment mapping for this step.
+        #[serde(default)]
+        env: LoE<Env>,
     },
     /// A step that runs a command in a shell.
     Run {
@@ -143,22 +471,39 @@ pub enum StepBody {
         run: String,
 
         /// The shell to run in.
-        shell: String,
+        shell: Shell,
 
         /// An optional environment mapping for this step.
         #[serde(default)]
         env: LoE<Env>,
 
-        /// An optional working directory to run [`RunShell::run`] from.
+        /// An optional working directory to run [`StepBody::Run::run`] from.
         working_directory: Option<String>,
     },
 }
 
+impl crate::common::StepBodyVariants for StepBody {
+    fn deserialize_variant<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Self::deserialize(de)
+    }
+}
+
+impl<'de> Deserialize<'de> for StepBody {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        crate::common::step_body(deserializer)
+    }
+}
+
 /// A `runs` definition for a Docker action.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Docker {
-    /// Invariant: `"docker"`
     pub using: String,
 
     /// The Docker image to use.
@@ -172,6 +517,10 @@ pub struct Docker {
     /// default entrypoint.
     pub entrypoint: Option<String>,
 
+    /// Arguments to pass to the Docker entrypoint.
+    #[serde(default)]
+    pub args: Vec<String>,
+
     /// An optional "pre" entrypoint to run, before [`Docker::entrypoint`].
     pub pre_entrypoint: Option<String>,
 
@@ -188,4 +537,8 @@ pub struct Docker {
     ///
     /// If not present, defaults to `always()`
     pub post_if: Option<If>,
+
+    /// Any other keys in this `runs:`.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
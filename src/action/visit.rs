@@ -0,0 +1,111 @@
+//! Pre-order traversal of action definitions.
+
+use crate::common::{expr, expr::LoE, Env, EnvValue, If, Uses};
+
+use super::{Action, Step, StepBody};
+
+/// A visitor over the parts of an action definition, driven by
+/// [`walk_action`].
+///
+/// Every method does nothing by default, so implementations only need to
+/// override the ones they care about.
+pub trait ActionVisitor<'a> {
+    /// Called once, before anything else.
+    fn visit_action(&mut self, _action: &'a Action) {}
+
+    /// Called for each composite step, before anything within it.
+    fn visit_step(&mut self, _index: usize, _step: &'a Step) {}
+
+    /// Called for a composite step's `uses:`.
+    fn visit_uses(&mut self, _index: usize, _uses: &'a Uses) {}
+
+    /// Called for a composite step's `run:` script.
+    fn visit_run(&mut self, _index: usize, _run: &'a str) {}
+
+    /// Called for each expression's body, e.g. `inputs.version` for
+    /// `${{ inputs.version }}`, along with the index of the composite
+    /// step containing it (if any).
+    ///
+    /// Like in workflows, `if:` conditions are expressions even without
+    /// `${{ }}`.
+    fn visit_expression(&mut self, _step_index: Option<usize>, _expression: &'a str) {}
+}
+
+/// Walk `action` in pre-order, calling `visitor` for each part of it.
+///
+/// Expressions in input defaults and output values are visited first,
+/// followed by each composite step. Each step is visited before its
+/// `uses:` or `run:`, followed by its expressions.
+pub fn walk_action<'a>(action: &'a Action, visitor: &mut impl ActionVisitor<'a>) {
+    visitor.visit_action(action);
+
+    let defaults = action.inputs.values().filter_map(|i| i.default.as_deref());
+    let values = action.outputs.values().filter_map(|o| o.value.as_deref());
+    for expression in defaults.chain(values).flat_map(interpolations) {
+        visitor.visit_expression(None, expression);
+    }
+
+    for (index, step) in action.steps().iter().enumerate() {
+        visitor.visit_step(index, step);
+        match &step.body {
+            StepBody::Uses { uses, .. } => visitor.visit_uses(index, uses),
+            StepBody::Run { run, .. } => visitor.visit_run(index, run),
+        }
+
+        for expression in step_expressions(step) {
+            visitor.visit_expression(Some(index), expression);
+        }
+    }
+}
+
+/// Returns the body of each expression interpolated into `s`.
+fn interpolations(s: &str) -> impl Iterator<Item = &str> {
+    expr::interpolations(s).into_iter().map(|(_, body)| body)
+}
+
+/// Returns the body of each expression in `step`, in the order its fields
+/// are declared.
+fn step_expressions(step: &Step) -> Vec<&str> {
+    fn strings(env: &Env) -> impl Iterator<Item = &str> {
+        env.values().filter_map(|value| match value {
+            EnvValue::String(value) => Some(value.as_str()),
+            EnvValue::Number(_) | EnvValue::Boolean(_) => None,
+        })
+    }
+
+    fn env_expressions(env: &LoE<Env>) -> Vec<&str> {
+        match env {
+            LoE::Expr(expr) => vec![expr.as_bare()],
+            LoE::Literal(env) => strings(env).flat_map(interpolations).collect(),
+        }
+    }
+
+    let mut expressions = vec![];
+
+    if let Some(cond @ If::Expr(_)) = &step.r#if {
+        expressions.push(cond.normalized());
+    }
+    expressions.extend(step.name.iter().flat_map(|name| interpolations(name)));
+    if let LoE::Expr(expr) = &step.continue_on_error {
+        expressions.push(expr.as_bare());
+    }
+
+    match &step.body {
+        StepBody::Uses { with, env, .. } => {
+            expressions.extend(strings(with).flat_map(interpolations));
+            expressions.extend(env_expressions(env));
+        }
+        StepBody::Run {
+            run,
+            env,
+            working_directory,
+            ..
+        } => {
+            expressions.extend(interpolations(run));
+            expressions.extend(env_expressions(env));
+            expressions.extend(working_directory.iter().flat_map(|dir| interpolations(dir)));
+        }
+    }
+
+    expressions
+}
@@ -1,17 +1,33 @@
 //! Shared models and utilities.
 
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+use std::{borrow::Cow, path::Path};
 use std::{
     fmt::{self, Display},
     str::FromStr,
 };
 
 use indexmap::IndexMap;
-use serde::{de, Deserialize, Deserializer, Serialize};
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml::Value;
+#[cfg(any(feature = "workflow", feature = "action"))]
+use {
+    serde::de,
+    serde_yaml::{
+        value::{Tag, TaggedValue},
+        Mapping,
+    },
+};
 
+pub mod diagnostic;
 pub mod expr;
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+pub(crate) mod strict;
 
 /// `permissions` for a workflow, job, or step.
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Debug, PartialEq, Serialize)]
 #[serde(rename_all = "kebab-case", untagged)]
 pub enum Permissions {
     /// Base, i.e. blanket permissions.
@@ -23,6 +39,38 @@ pub enum Permissions {
     Explicit(IndexMap<String, Permission>),
 }
 
+impl Permissions {
+    /// Returns whether these permissions grant write access to the given
+    /// `scope`, e.g. `contents` or `id-token`.
+    ///
+    /// Scopes that aren't listed in [`Permissions::Explicit`] have no access.
+    /// For [`BasePermission::Default`], the answer depends on the repository
+    /// or organization's default `GITHUB_TOKEN` permissions, which aren't
+    /// visible here; this method conservatively returns `false`.
+    pub fn has_write_access_to(&self, scope: &str) -> bool {
+        match self {
+            Self::Base(BasePermission::WriteAll) => true,
+            Self::Base(BasePermission::ReadAll | BasePermission::Default) => false,
+            Self::Explicit(perms) => matches!(perms.get(scope), Some(Permission::Write)),
+        }
+    }
+
+    /// Returns whether these permissions grant read access to the given
+    /// `scope`, e.g. `contents` or `id-token`.
+    ///
+    /// Write access implies read access. Like [`Permissions::has_write_access_to`],
+    /// this returns `false` for [`BasePermission::Default`].
+    pub fn has_read_access_to(&self, scope: &str) -> bool {
+        match self {
+            Self::Base(BasePermission::ReadAll | BasePermission::WriteAll) => true,
+            Self::Base(BasePermission::Default) => false,
+            Self::Explicit(perms) => {
+                matches!(perms.get(scope), Some(Permission::Read | Permission::Write))
+            }
+        }
+    }
+}
+
 impl Default for Permissions {
     fn default() -> Self {
         Self::Base(BasePermission::Default)
@@ -31,7 +79,7 @@ impl Default for Permissions {
 
 /// "Base" permissions, where all individual permissions are configured
 /// with a blanket setting.
-#[derive(Deserialize, Default, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Default, Debug, PartialEq, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum BasePermission {
     /// Whatever default permissions come from the workflow's `GITHUB_TOKEN`.
@@ -44,7 +92,7 @@ pub enum BasePermission {
 }
 
 /// A singular permission setting.
-#[derive(Deserialize, Default, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Default, Debug, PartialEq, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum Permission {
     /// Read access.
@@ -64,7 +112,7 @@ pub type Env = IndexMap<String, EnvValue>;
 /// Environment variable values are always strings, but GitHub Actions
 /// allows users to configure them as various native YAML types before
 /// internal stringification.
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(untagged)]
 pub enum EnvValue {
     // Missing values are empty strings.
@@ -84,10 +132,483 @@ impl Display for EnvValue {
     }
 }
 
+impl EnvValue {
+    /// Returns this value as GitHub Actions stringifies it, e.g. when
+    /// exposing it as an environment variable.
+    ///
+    /// This is identical to this value's [`Display`] representation.
+    pub fn coerce_to_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns this value as a boolean, if it's either a boolean or a
+    /// string spelling `true` or `false` (case-insensitively).
+    pub fn try_as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            Self::String(s) if s.eq_ignore_ascii_case("true") => Some(true),
+            Self::String(s) if s.eq_ignore_ascii_case("false") => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns this value as a number, if it's either a number or a
+    /// string containing a finite number (ignoring surrounding whitespace).
+    pub fn try_as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::String(s) => s.trim().parse().ok().filter(|n: &f64| n.is_finite()),
+            Self::Boolean(_) => None,
+        }
+    }
+}
+
+/// Returns whether `value` is its type's default, for skipping
+/// defaulted fields during serialization.
+#[cfg(feature = "workflow")]
+pub(crate) fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Returns whether `name` is a valid identifier in GitHub Actions
+/// expressions, i.e. starts with a letter or `_` and contains only
+/// alphanumeric characters, `-`, or `_`.
+///
+/// Names that aren't identifiers (like `foo.bar`) can still be used
+/// as keys in some places, but can't be referenced with the
+/// `context.name` property syntax.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The kind of thing named by an identifier passed to
+/// [`invalid_identifiers`].
+#[cfg(any(feature = "workflow", feature = "action"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum IdentifierKind {
+    /// A workflow job ID.
+    #[cfg(feature = "workflow")]
+    Job,
+    /// A workflow or composite action step's `id`.
+    Step,
+    /// An action input's name.
+    #[cfg(feature = "action")]
+    Input,
+    /// An action output's name.
+    #[cfg(feature = "action")]
+    Output,
+}
+
+#[cfg(any(feature = "workflow", feature = "action"))]
+impl Display for IdentifierKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "workflow")]
+            IdentifierKind::Job => write!(f, "job ID"),
+            IdentifierKind::Step => write!(f, "step ID"),
+            #[cfg(feature = "action")]
+            IdentifierKind::Input => write!(f, "input name"),
+            #[cfg(feature = "action")]
+            IdentifierKind::Output => write!(f, "output name"),
+        }
+    }
+}
+
+/// Returns an [`INVALID_IDENTIFIER`](diagnostic::INVALID_IDENTIFIER)
+/// diagnostic for each of `ids` that isn't an identifier (see
+/// [`is_identifier`]).
+///
+/// `ids` are siblings in declaration order; `None` entries (e.g. steps
+/// without an `id`) are skipped, but still count towards positions.
+/// For workflow steps, `job_id` is the ID of the job containing them.
+#[cfg(any(feature = "workflow", feature = "action"))]
+pub(crate) fn invalid_identifiers<'a>(
+    kind: IdentifierKind,
+    ids: impl IntoIterator<Item = Option<&'a str>>,
+    job_id: Option<&str>,
+) -> Vec<diagnostic::Diagnostic> {
+    ids.into_iter()
+        .enumerate()
+        .filter_map(|(index, id)| Some((index, id?)))
+        .filter(|(_, id)| !is_identifier(id))
+        .map(|(index, id)| {
+            let index = index.to_string();
+            let path = match (kind, job_id) {
+                #[cfg(feature = "workflow")]
+                (IdentifierKind::Job, _) => vec!["jobs", id],
+                (IdentifierKind::Step, Some(job_id)) => {
+                    vec!["jobs", job_id, "steps", &index, "id"]
+                }
+                (IdentifierKind::Step, None) => vec!["runs", "steps", &index, "id"],
+                #[cfg(feature = "action")]
+                (IdentifierKind::Input, _) => vec!["inputs", id],
+                #[cfg(feature = "action")]
+                (IdentifierKind::Output, _) => vec!["outputs", id],
+            };
+
+            diagnostic::Diagnostic::new(
+                diagnostic::INVALID_IDENTIFIER,
+                path,
+                format!(
+                    "{kind} `{id}` must start with a letter or `_` and contain only \
+                     alphanumeric characters, `-`, or `_`"
+                ),
+            )
+        })
+        .collect()
+}
+
+/// A key that a model didn't recognize, as captured in one of its `extra`
+/// fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownKey<'a> {
+    /// The path to the key, e.g. `jobs.test.time-out`.
+    pub path: String,
+    /// The key itself, e.g. `time-out`.
+    pub key: &'a str,
+    /// The key's value.
+    pub value: &'a Value,
+}
+
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+impl UnknownKey<'_> {
+    /// Returns the 1-based line and column of this key in `contents`, the
+    /// document that it was captured from, if it can be found.
+    ///
+    /// The models don't keep locations, so this re-reads `contents`.
+    pub fn location(&self, contents: &str) -> Option<(usize, usize)> {
+        strict::locate(contents, &self.path)
+    }
+}
+
+/// Returns an [`UnknownKey`] for each entry of `extra`, which was captured
+/// from the mapping at `path`.
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+pub(crate) fn unknown_keys<'a>(
+    path: &str,
+    extra: &'a IndexMap<String, Value>,
+) -> impl Iterator<Item = UnknownKey<'a>> + 'a {
+    let path = path.to_string();
+    extra.iter().map(move |(key, value)| UnknownKey {
+        path: strict::child(&path, key),
+        key,
+        value,
+    })
+}
+
+/// Returns the candidate closest to `name`, if any is close enough to
+/// plausibly be what was meant (e.g. `build` for `biuld`).
+///
+/// Closeness is the optimal string alignment distance (edits, with
+/// adjacent transpositions counting as one edit), compared ASCII
+/// case-insensitively. Ties go to the earliest candidate.
+#[cfg(feature = "workflow")]
+pub(crate) fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    fn distance(a: &[u8], b: &[u8]) -> usize {
+        // `rows[i][j]` is the distance between `a[..i]` and `b[..j]`.
+        let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[0] = i;
+        }
+        rows[0] = (0..=b.len()).collect();
+
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                let cost = usize::from(a[i - 1] != b[j - 1]);
+                let mut best = (rows[i - 1][j] + 1)
+                    .min(rows[i][j - 1] + 1)
+                    .min(rows[i - 1][j - 1] + cost);
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    best = best.min(rows[i - 2][j - 2] + 1);
+                }
+                rows[i][j] = best;
+            }
+        }
+
+        rows[a.len()][b.len()]
+    }
+
+    let name = name.to_ascii_lowercase();
+    // Allow roughly one edit per three characters, and always at least one.
+    let threshold = (name.len() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let distance = distance(name.as_bytes(), candidate.to_ascii_lowercase().as_bytes());
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Find each expression within `value`, an untyped value (like a matrix
+/// entry or an unknown key) found at `key`.
+///
+/// Each item is the path to the expression's key, e.g. `include[0].os`
+/// for `key` `include`, along with the expression's body. `if:` values
+/// are expressions even without `${{ }}`.
+#[cfg(feature = "workflow")]
+pub(crate) fn find_expressions(value: &Value, key: &str) -> Vec<(String, String)> {
+    fn find(value: &Value, key: String, found: &mut Vec<(String, String)>) {
+        match value {
+            Value::String(value) => {
+                let mut expressions = expr::interpolations(value)
+                    .into_iter()
+                    .map(|(_, body)| body)
+                    .collect::<Vec<_>>();
+                if expressions.is_empty() && key == "if" && !value.trim().is_empty() {
+                    expressions.push(value.trim());
+                }
+
+                found.extend(
+                    expressions
+                        .into_iter()
+                        .map(|expression| (key.clone(), expression.into())),
+                );
+            }
+            Value::Sequence(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    find(value, format!("{key}[{index}]"), found);
+                }
+            }
+            Value::Mapping(mapping) => {
+                for (child_key, value) in mapping {
+                    if let Some(child_key) = child_key.as_str() {
+                        find(value, format!("{key}.{child_key}"), found);
+                    }
+                }
+            }
+            Value::Tagged(tagged) => find(&tagged.value, key, found),
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
+
+    let mut found = vec![];
+    find(value, key.into(), &mut found);
+    found
+}
+
+/// Load a top-level model from the YAML file at `path`, as with
+/// [`from_yaml_slice`].
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+pub(crate) fn from_path<T>(path: &Path) -> Result<T, crate::LoadError>
+where
+    T: DeserializeOwned,
+{
+    let contents = std::fs::read(path).map_err(|source| crate::LoadError::Io {
+        path: path.into(),
+        source,
+    })?;
+
+    from_yaml_slice(&contents).map_err(|source| crate::LoadError::Parse {
+        path: path.into(),
+        source,
+    })
+}
+
+/// Load a top-level model (workflow, action, etc.) from a YAML document.
+///
+/// This is the shared implementation behind each model's `FromStr`.
+/// Beyond plain deserialization, it strips a leading UTF-8 byte order mark,
+/// which is otherwise interpreted as part of the first key.
+/// CRLF line endings are handled by the underlying YAML parser, and
+/// inputs containing multiple YAML documents are rejected.
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+pub(crate) fn from_yaml<T>(contents: &str) -> Result<T, crate::Error>
+where
+    T: DeserializeOwned,
+{
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+
+    #[cfg(feature = "error-paths")]
+    {
+        BUFFERED_PATH.with(|buffered| buffered.take());
+        serde_path_to_error::deserialize(serde_yaml::Deserializer::from_str(contents)).map_err(
+            |e| {
+                let mut path = e.path().to_string();
+                if let Some(buffered) = BUFFERED_PATH.with(|buffered| buffered.take()) {
+                    path = join_path(&path, &buffered);
+                }
+
+                match path.as_str() {
+                    "." => crate::Error::Yaml(e.into_inner()),
+                    _ => crate::Error::Field {
+                        path,
+                        source: e.into_inner(),
+                    },
+                }
+            },
+        )
+    }
+
+    #[cfg(not(feature = "error-paths"))]
+    Ok(serde_yaml::from_str(contents)?)
+}
+
+#[cfg(all(
+    feature = "error-paths",
+    any(feature = "workflow", feature = "action", feature = "dependabot")
+))]
+thread_local! {
+    /// The path to the offending field within the outermost value that
+    /// failed in [`from_buffered_with`], relative to that value.
+    static BUFFERED_PATH: std::cell::Cell<Option<String>> = const { std::cell::Cell::new(None) };
+}
+
+/// Join a path to a buffered value with a path within it, where `.` is
+/// the empty path.
+#[cfg(all(
+    feature = "error-paths",
+    any(feature = "workflow", feature = "action", feature = "dependabot")
+))]
+fn join_path(outer: &str, inner: &str) -> String {
+    match (outer, inner) {
+        (".", inner) => inner.into(),
+        (outer, ".") => outer.into(),
+        (outer, inner) if inner.starts_with('[') => format!("{outer}{inner}"),
+        (outer, inner) => format!("{outer}.{inner}"),
+    }
+}
+
+/// The deserializer that [`from_buffered_with`] passes a buffered value to.
+#[cfg(all(feature = "error-paths", any(feature = "workflow", feature = "action")))]
+pub(crate) type BufferedDeserializer<'a, 'b> = serde_path_to_error::Deserializer<'a, 'b, Value>;
+
+/// The deserializer that [`from_buffered_with`] passes a buffered value to.
+#[cfg(all(
+    not(feature = "error-paths"),
+    any(feature = "workflow", feature = "action")
+))]
+pub(crate) type BufferedDeserializer<'a, 'b> = Value;
+
+/// Deserialize a model from a value that was buffered by a custom
+/// `Deserialize` implementation, e.g. to pick an enum variant.
+///
+/// Buffering hides the path within the value from the outer
+/// deserializer, so with the `error-paths` feature this records it for
+/// [`from_yaml`] to report. When buffered values are nested (like a step's
+/// body within a job), each level's path is joined onto the paths recorded
+/// within it.
+#[cfg(any(feature = "workflow", feature = "action"))]
+pub(crate) fn from_buffered<T>(value: Value) -> Result<T, serde_yaml::Error>
+where
+    T: DeserializeOwned,
+{
+    from_buffered_with(value, |de| T::deserialize(de))
+}
+
+/// Like [`from_buffered`], but with an explicit `deserialize` function,
+/// e.g. the derived `Deserialize` of a `remote = "Self"` type.
+///
+/// `deserialize` has to be passed as a closure (e.g.
+/// `|de| Self::deserialize(de)`), since it must accept the `error-paths`
+/// feature's deserializer for any lifetimes.
+#[cfg(any(feature = "workflow", feature = "action"))]
+pub(crate) fn from_buffered_with<T>(
+    value: Value,
+    deserialize: impl for<'a, 'b> FnOnce(BufferedDeserializer<'a, 'b>) -> Result<T, serde_yaml::Error>,
+) -> Result<T, serde_yaml::Error> {
+    #[cfg(feature = "error-paths")]
+    {
+        use serde_path_to_error::Segment;
+
+        // A tagged value was re-tagged to pick an enum variant (see
+        // `step_body`), so its variant isn't part of the document's path.
+        let retagged = matches!(value, Value::Tagged(_));
+
+        let mut track = serde_path_to_error::Track::new();
+        let result = deserialize(serde_path_to_error::Deserializer::new(value, &mut track));
+
+        // NOTE: An inner failure is only relevant if it made this value fail;
+        // otherwise, it was recovered from (e.g. by an untagged enum).
+        let inner = BUFFERED_PATH.with(|buffered| buffered.take());
+        if result.is_err() {
+            let path = track.path();
+            let mut segments = path.iter().peekable();
+            if retagged {
+                segments.next_if(|segment| matches!(segment, Segment::Enum { .. }));
+            }
+            let path = segments.fold(".".to_string(), |path, segment| {
+                join_path(&path, &segment.to_string())
+            });
+            let path = match inner {
+                Some(inner) => join_path(&path, &inner),
+                None => path,
+            };
+            BUFFERED_PATH.with(|buffered| buffered.set(Some(path)));
+        }
+
+        result
+    }
+
+    #[cfg(not(feature = "error-paths"))]
+    deserialize(value)
+}
+
+/// Deserialize a model from raw YAML bytes.
+///
+/// UTF-8 input (with or without a BOM) is decoded directly; UTF-16 input
+/// is only recognized by its BOM.
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+pub(crate) fn from_yaml_slice<T>(contents: &[u8]) -> Result<T, crate::Error>
+where
+    T: DeserializeOwned,
+{
+    from_yaml(&decode(contents)?)
+}
+
+#[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+fn decode(contents: &[u8]) -> Result<Cow<'_, str>, crate::Error> {
+    let (encoding, from_units): (_, fn([u8; 2]) -> u16) = match contents {
+        [0xff, 0xfe, ..] => ("UTF-16LE", u16::from_le_bytes),
+        [0xfe, 0xff, ..] => ("UTF-16BE", u16::from_be_bytes),
+        _ => {
+            return std::str::from_utf8(contents)
+                .map(Cow::Borrowed)
+                .map_err(|e| crate::Error::Encoding {
+                    encoding: "UTF-8",
+                    offset: e.valid_up_to(),
+                });
+        }
+    };
+
+    let body = &contents[2..];
+    let units = body.chunks_exact(2).map(|c| from_units([c[0], c[1]]));
+
+    let mut decoded = String::with_capacity(body.len() / 2);
+    let mut offset = 2;
+    for c in char::decode_utf16(units) {
+        let Ok(c) = c else {
+            return Err(crate::Error::Encoding { encoding, offset });
+        };
+        decoded.push(c);
+        offset += c.len_utf16() * 2;
+    }
+
+    if !body.len().is_multiple_of(2) {
+        return Err(crate::Error::Encoding {
+            encoding,
+            offset: contents.len() - 1,
+        });
+    }
+
+    Ok(Cow::Owned(decoded))
+}
+
 /// A "scalar or vector" type, for places in GitHub Actions where a
 /// key can have either a scalar value or an array of values.
 ///
 /// This only appears internally, as an intermediate type for `scalar_or_vector`.
+#[cfg(any(feature = "workflow", feature = "dependabot"))]
 #[derive(Debug, Deserialize, PartialEq)]
 #[serde(untagged)]
 enum SoV<T> {
@@ -95,6 +616,7 @@ enum SoV<T> {
     Many(Vec<T>),
 }
 
+#[cfg(any(feature = "workflow", feature = "dependabot"))]
 impl<T> From<SoV<T>> for Vec<T> {
     fn from(val: SoV<T>) -> Vec<T> {
         match val {
@@ -104,6 +626,7 @@ impl<T> From<SoV<T>> for Vec<T> {
     }
 }
 
+#[cfg(any(feature = "workflow", feature = "dependabot"))]
 pub(crate) fn scalar_or_vector<'de, D, T>(de: D) -> Result<Vec<T>, D::Error>
 where
     D: Deserializer<'de>,
@@ -115,6 +638,7 @@ where
 /// A bool or string. This is useful for cases where GitHub Actions contextually
 /// reinterprets a YAML boolean as a string, e.g. `run: true` really means
 /// `run: 'true'`.
+#[cfg(feature = "workflow")]
 #[derive(Debug, Deserialize, PartialEq)]
 #[serde(untagged)]
 enum BoS {
@@ -122,6 +646,7 @@ enum BoS {
     String(String),
 }
 
+#[cfg(feature = "workflow")]
 impl From<BoS> for String {
     fn from(value: BoS) -> Self {
         match value {
@@ -134,7 +659,7 @@ impl From<BoS> for String {
 /// An `if:` condition in a job or action definition.
 ///
 /// These are either booleans or bare (i.e. non-curly) expressions.
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum If {
     Bool(bool),
@@ -143,6 +668,54 @@ pub enum If {
     Expr(String),
 }
 
+impl If {
+    /// Returns this condition as a string: `true` or `false` for boolean
+    /// conditions, or the expression itself.
+    pub fn as_str(&self) -> &str {
+        match self {
+            If::Bool(true) => "true",
+            If::Bool(false) => "false",
+            If::Expr(expr) => expr,
+        }
+    }
+
+    /// Returns whether this condition always passes, i.e. is `true` or
+    /// `always()`.
+    ///
+    /// Expressions are compared after trimming whitespace and any `${{ }}`
+    /// delimiters, case-insensitively.
+    pub fn is_always_true(&self) -> bool {
+        match self {
+            If::Bool(b) => *b,
+            If::Expr(_) => {
+                let cond = self.normalized();
+                cond.eq_ignore_ascii_case("always()") || cond.eq_ignore_ascii_case("true")
+            }
+        }
+    }
+
+    /// Returns whether this condition is equivalent to GitHub's default
+    /// `success()` condition, i.e. is `true` or `success()`.
+    ///
+    /// Like [`If::is_always_true`], expressions are compared loosely.
+    pub fn is_success_condition(&self) -> bool {
+        match self {
+            If::Bool(b) => *b,
+            If::Expr(_) => self.normalized().eq_ignore_ascii_case("success()"),
+        }
+    }
+
+    /// Returns this condition's expression body, without surrounding
+    /// whitespace or `${{ }}` delimiters.
+    pub(crate) fn normalized(&self) -> &str {
+        let cond = self.as_str().trim();
+        cond.strip_prefix("${{")
+            .and_then(|cond| cond.strip_suffix("}}"))
+            .map_or(cond, str::trim)
+    }
+}
+
+#[cfg(feature = "workflow")]
 pub(crate) fn bool_is_string<'de, D>(de: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
@@ -160,8 +733,8 @@ where
 }
 
 // TODO: Bother with enum variants here?
-#[derive(Debug, PartialEq)]
-pub struct UsesError(String);
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsesError(pub(crate) String);
 
 impl fmt::Display for UsesError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -169,7 +742,7 @@ impl fmt::Display for UsesError {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Uses {
     /// A local `uses:` clause, e.g. `uses: ./foo/bar`.
     Local(LocalUses),
@@ -195,8 +768,27 @@ impl FromStr for Uses {
     }
 }
 
+impl Display for Uses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Uses::Local(uses) => uses.fmt(f),
+            Uses::Repository(uses) => uses.fmt(f),
+            Uses::Docker(uses) => uses.fmt(f),
+        }
+    }
+}
+
+impl Serialize for Uses {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
 /// A `uses: ./some/path` clause.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct LocalUses {
     pub path: String,
     pub git_ref: Option<String>,
@@ -219,7 +811,7 @@ impl FromStr for LocalUses {
 
         // TODO: Overly conservative? `uses: ./foo/bar@` might be valid if
         // `./foo/bar@/action.yml` exists.
-        if git_ref.map_or(false, |git_ref| git_ref.is_empty()) {
+        if git_ref.is_some_and(|git_ref| git_ref.is_empty()) {
             return Err(UsesError(format!(
                 "local uses is missing git ref after '@': {uses}"
             )));
@@ -232,8 +824,18 @@ impl FromStr for LocalUses {
     }
 }
 
+impl Display for LocalUses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some(git_ref) = &self.git_ref {
+            write!(f, "@{git_ref}")?;
+        }
+        Ok(())
+    }
+}
+
 /// A `uses: some/repo` clause.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RepositoryUses {
     /// The repo user or org.
     pub owner: String,
@@ -245,6 +847,29 @@ pub struct RepositoryUses {
     pub git_ref: Option<String>,
 }
 
+impl RepositoryUses {
+    /// Returns the `owner/repo` slug, without any subpath or ref.
+    pub fn full_slug(&self) -> String {
+        format!("{}/{}", self.owner, self.repo)
+    }
+
+    /// Returns a copy of this clause, pinned to `git_ref` instead.
+    pub fn with_ref(&self, git_ref: &str) -> RepositoryUses {
+        RepositoryUses {
+            git_ref: Some(git_ref.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this clause, without any ref.
+    pub fn without_ref(&self) -> RepositoryUses {
+        RepositoryUses {
+            git_ref: None,
+            ..self.clone()
+        }
+    }
+}
+
 impl FromStr for RepositoryUses {
     type Err = UsesError;
 
@@ -277,8 +902,21 @@ impl FromStr for RepositoryUses {
     }
 }
 
+impl Display for RepositoryUses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.owner, self.repo)?;
+        if let Some(subpath) = &self.subpath {
+            write!(f, "/{subpath}")?;
+        }
+        if let Some(git_ref) = &self.git_ref {
+            write!(f, "@{git_ref}")?;
+        }
+        Ok(())
+    }
+}
+
 /// A `uses: docker://some-image` clause.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct DockerUses {
     /// The registry this image is on, if present.
     pub registry: Option<String>,
@@ -295,6 +933,41 @@ impl DockerUses {
         // https://stackoverflow.com/a/42116190
         registry == "localhost" || registry.contains('.') || registry.contains(':')
     }
+
+    /// Returns whether this image is pinned by digest, e.g.
+    /// `alpine@sha256:...`.
+    pub fn is_digest_pinned(&self) -> bool {
+        self.hash.is_some()
+    }
+
+    /// Returns whether this image floats on the `latest` tag, either
+    /// explicitly or by having no tag at all.
+    ///
+    /// Digest-pinned images never float, even if they're also tagged
+    /// `latest`.
+    pub fn is_latest_tag(&self) -> bool {
+        !self.is_digest_pinned() && matches!(self.tag.as_deref(), None | Some("latest"))
+    }
+
+    /// Returns the image reference, i.e. `[registry/]image[:tag][@hash]`,
+    /// without the `docker://` prefix.
+    pub fn full_image_ref(&self) -> String {
+        let mut image_ref = String::new();
+        if let Some(registry) = &self.registry {
+            image_ref.push_str(registry);
+            image_ref.push('/');
+        }
+        image_ref.push_str(&self.image);
+        if let Some(tag) = &self.tag {
+            image_ref.push(':');
+            image_ref.push_str(tag);
+        }
+        if let Some(hash) = &self.hash {
+            image_ref.push('@');
+            image_ref.push_str(hash);
+        }
+        image_ref
+    }
 }
 
 impl FromStr for DockerUses {
@@ -341,16 +1014,413 @@ impl FromStr for DockerUses {
     }
 }
 
+impl Display for DockerUses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "docker://{}", self.full_image_ref())
+    }
+}
+
+/// A shell for `run:` steps, as given by `shell:` or `defaults.run.shell`.
+///
+/// See the [workflow syntax for `shell`] for the builtin shells.
+///
+/// [workflow syntax for `shell`]: https://docs.github.com/en/actions/writing-workflows/workflow-syntax-for-github-actions#jobsjob_idstepsshell
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Shell {
+    /// `bash`
+    Bash,
+    /// `sh`
+    Sh,
+    /// `pwsh`
+    Pwsh,
+    /// `powershell`
+    Powershell,
+    /// `cmd`
+    Cmd,
+    /// `python`
+    Python,
+    /// Any other shell, normally a custom command template like
+    /// `perl {0}`.
+    Custom(String),
+}
+
+impl Shell {
+    /// Returns whether this is one of GitHub's builtin shells.
+    pub fn is_builtin(&self) -> bool {
+        !matches!(self, Shell::Custom(_))
+    }
+
+    /// Parse this custom shell's command template, e.g.
+    /// `deno run --allow-all {0}`.
+    ///
+    /// The template is split into words like a POSIX shell would, with
+    /// single quotes, double quotes, and backslash escapes. Exactly one
+    /// argument must contain the `{0}` script path placeholder, which
+    /// GitHub requires for custom shells.
+    ///
+    /// Returns an error for builtin shells.
+    pub fn custom_template(&self) -> Result<ShellTemplate, ShellTemplateError> {
+        let Shell::Custom(template) = self else {
+            return Err(ShellTemplateError(format!("`{self}` is a builtin shell")));
+        };
+
+        let mut words = shell_words(template)?.into_iter();
+        let Some(program) = words.next() else {
+            return Err(ShellTemplateError("empty shell template".into()));
+        };
+        if program.contains("{0}") {
+            return Err(ShellTemplateError(format!(
+                "`{{0}}` placeholder can't be the program: {template}"
+            )));
+        }
+        let args = words.collect::<Vec<_>>();
+
+        let mut positions = args
+            .iter()
+            .enumerate()
+            .filter(|(_, arg)| arg.contains("{0}"))
+            .map(|(idx, _)| idx);
+        let (Some(script_position), None) = (positions.next(), positions.next()) else {
+            return Err(ShellTemplateError(format!(
+                "expected exactly one `{{0}}` placeholder: {template}"
+            )));
+        };
+
+        Ok(ShellTemplate {
+            program,
+            args,
+            script_position,
+        })
+    }
+}
+
+/// A parsed custom shell command template, as returned by
+/// [`Shell::custom_template`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShellTemplate {
+    /// The program to run, e.g. `deno`.
+    pub program: String,
+    /// The program's arguments, e.g. `["run", "--allow-all", "{0}"]`.
+    pub args: Vec<String>,
+    /// The index into `args` of the argument containing the `{0}`
+    /// placeholder.
+    pub script_position: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShellTemplateError(String);
+
+impl fmt::Display for ShellTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed shell template: {}", self.0)
+    }
+}
+
+/// Split `s` into words, honoring single quotes, double quotes, and
+/// backslash escapes.
+fn shell_words(s: &str) -> Result<Vec<String>, ShellTemplateError> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\\' => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| ShellTemplateError(format!("trailing backslash: {s}")))?;
+                word.get_or_insert_default().push(escaped);
+            }
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(ShellTemplateError(format!("unterminated quote: {s}"))),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => {
+                                return Err(ShellTemplateError(format!("unterminated quote: {s}")))
+                            }
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(ShellTemplateError(format!("unterminated quote: {s}"))),
+                    }
+                }
+            }
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+impl FromStr for Shell {
+    type Err = std::convert::Infallible;
+
+    /// Parse a shell. Unknown shells become [`Shell::Custom`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "bash" => Shell::Bash,
+            "sh" => Shell::Sh,
+            "pwsh" => Shell::Pwsh,
+            "powershell" => Shell::Powershell,
+            "cmd" => Shell::Cmd,
+            "python" => Shell::Python,
+            _ => Shell::Custom(s.into()),
+        })
+    }
+}
+
+impl Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shell::Bash => write!(f, "bash"),
+            Shell::Sh => write!(f, "sh"),
+            Shell::Pwsh => write!(f, "pwsh"),
+            Shell::Powershell => write!(f, "powershell"),
+            Shell::Cmd => write!(f, "cmd"),
+            Shell::Python => write!(f, "python"),
+            Shell::Custom(shell) => write!(f, "{shell}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Shell {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Ok(shell) = String::deserialize(de)?.parse();
+        Ok(shell)
+    }
+}
+
+impl Serialize for Shell {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// A step body, i.e. an enum with `uses` and `run` struct variants.
+///
+/// Step bodies implement `Deserialize` with [`step_body`], which picks the
+/// variant; `deserialize_variant` is the body's derived `Deserialize` (from
+/// `remote = "Self"`), which deserializes it.
+#[cfg(any(feature = "workflow", feature = "action"))]
+pub(crate) trait StepBodyVariants: Sized {
+    fn deserialize_variant<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Deserialize a step body, i.e. either a `uses:` or a `run:` step.
+///
+/// Rather than trying each variant in turn (like `#[serde(untagged)]`),
+/// this dispatches on whichever of `uses:` or `run:` is present, so that
+/// errors within the body are reported directly instead of being collapsed
+/// into an opaque untagged enum error.
+#[cfg(any(feature = "workflow", feature = "action"))]
+pub(crate) fn step_body<'de, D, T>(de: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: StepBodyVariants,
+{
+    let body = Mapping::deserialize(de)?;
+
+    let tag = match (body.contains_key("uses"), body.contains_key("run")) {
+        (true, false) => "uses",
+        (false, true) => "run",
+        (true, true) => {
+            return Err(de::Error::custom(
+                "step cannot contain both `uses:` and `run:`",
+            ))
+        }
+        (false, false) => {
+            // Keys that belong to a body (like `with:`) are only misplaced
+            // without `uses:` or `run:`, so they aren't listed.
+            let body_fields = step_body_fields::<T>();
+            let keys = body
+                .keys()
+                .filter_map(|key| match key {
+                    Value::String(key) if body_fields.contains(&key.as_str()) => None,
+                    Value::String(key) => Some(format!("`{key}`")),
+                    _ => Some("<non-string key>".into()),
+                })
+                .collect::<Vec<_>>();
+
+            return Err(de::Error::custom(if keys.is_empty() {
+                "step must contain either `uses:` or `run:`".into()
+            } else {
+                format!(
+                    "step must contain either `uses:` or `run:`, found unrecognized keys: {}",
+                    keys.join(", ")
+                )
+            }));
+        }
+    };
+
+    // NOTE: The body is re-wrapped as a YAML-tagged enum value (i.e.
+    // `!uses {...}` or `!run {...}`), so that the derived `Deserialize`
+    // picks the variant we chose.
+    let body = Value::Tagged(Box::new(TaggedValue {
+        tag: Tag::new(tag),
+        value: Value::Mapping(body),
+    }));
+    from_buffered_with(body, |de| T::deserialize_variant(de)).map_err(de::Error::custom)
+}
+
+/// Deserialize a step's unknown keys, for a step whose body is a `T`.
+///
+/// A flattened step body sees every key that the step itself doesn't
+/// consume, so this drops the keys that belong to the body.
+#[cfg(any(feature = "workflow", feature = "action"))]
+pub(crate) fn step_extra<'de, T, D>(de: D) -> Result<IndexMap<String, Value>, D::Error>
+where
+    T: StepBodyVariants,
+    D: Deserializer<'de>,
+{
+    let body_fields = step_body_fields::<T>();
+
+    let mut extra = IndexMap::<String, Value>::deserialize(de)?;
+    extra.retain(|key, _| !body_fields.contains(&key.as_str()));
+    Ok(extra)
+}
+
+/// Returns the keys of each of `T`'s variants, as declared by its derived
+/// `Deserialize`.
+#[cfg(any(feature = "workflow", feature = "action"))]
+fn step_body_fields<T: StepBodyVariants>() -> Vec<&'static str> {
+    let mut fields = vec![];
+    for variant in ["uses", "run"] {
+        let mut variant_fields: &'static [&'static str] = &[];
+        // NOTE: The probe always fails, once it's seen the variant's fields.
+        let _ = T::deserialize_variant(FieldsProbe {
+            variant,
+            fields: &mut variant_fields,
+        });
+        fields.extend(variant_fields);
+    }
+    fields
+}
+
+/// A deserializer that selects `variant` from an enum, and then records
+/// the fields of that struct variant.
+#[cfg(any(feature = "workflow", feature = "action"))]
+struct FieldsProbe<'a> {
+    variant: &'static str,
+    fields: &'a mut &'static [&'static str],
+}
+
+#[cfg(any(feature = "workflow", feature = "action"))]
+impl<'de> Deserializer<'de> for FieldsProbe<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(de::Error::custom("expected an enum"))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_enum(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(any(feature = "workflow", feature = "action"))]
+impl<'de, 'a> de::EnumAccess<'de> for FieldsProbe<'a> {
+    type Error = de::value::Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(de::value::StrDeserializer::new(self.variant))?;
+        Ok((variant, self))
+    }
+}
+
+#[cfg(any(feature = "workflow", feature = "action"))]
+impl<'de> de::VariantAccess<'de> for FieldsProbe<'_> {
+    type Error = de::value::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Err(de::Error::custom("expected a struct variant"))
+    }
+
+    fn newtype_variant_seed<T>(self, _seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        Err(de::Error::custom("expected a struct variant"))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(de::Error::custom("expected a struct variant"))
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.fields = fields;
+        Err(de::Error::custom("probed"))
+    }
+}
+
 /// Deserialize an ordinary step `uses:`.
+#[cfg(any(feature = "workflow", feature = "action"))]
 pub(crate) fn step_uses<'de, D>(de: D) -> Result<Uses, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let uses = <&str>::deserialize(de)?;
-    Uses::from_str(uses).map_err(de::Error::custom)
+    let uses = <Cow<'de, str>>::deserialize(de)?;
+    Uses::from_str(&uses).map_err(de::Error::custom)
 }
 
 /// Deserialize a reusable workflow step `uses:`
+#[cfg(feature = "workflow")]
 pub(crate) fn reusable_step_uses<'de, D>(de: D) -> Result<Uses, D::Error>
 where
     D: Deserializer<'de>,
@@ -374,14 +1444,44 @@ where
 #[cfg(test)]
 mod tests {
     use indexmap::IndexMap;
-    use serde::Deserialize;
 
-    use crate::common::{BasePermission, Env, EnvValue, Permission};
+    use crate::common::{BasePermission, Env, EnvValue, If, Permission};
 
     use super::{
-        reusable_step_uses, DockerUses, LocalUses, Permissions, RepositoryUses, Uses, UsesError,
+        is_identifier, DockerUses, LocalUses, Permissions, RepositoryUses, Shell, ShellTemplate,
+        Uses, UsesError,
     };
 
+    #[test]
+    #[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+    fn test_decode() {
+        use super::decode;
+
+        assert_eq!(decode(b"on: push").unwrap(), "on: push");
+        assert_eq!(decode(b"\xef\xbb\xbfon: push").unwrap(), "\u{feff}on: push");
+        assert_eq!(decode(b"\xff\xfeo\0n\0").unwrap(), "on");
+        assert_eq!(decode(b"\xfe\xff\0o\0n").unwrap(), "on");
+        // A UTF-16 BOM with nothing after it is just empty.
+        assert_eq!(decode(b"\xff\xfe").unwrap(), "");
+
+        for (input, expected_encoding, expected_offset) in [
+            (&b"on: \xffpush"[..], "UTF-8", 4),
+            (b"on: \xe2\x82", "UTF-8", 4),
+            // Unpaired high surrogate.
+            (b"\xff\xfeo\0\x00\xd8n\0", "UTF-16LE", 4),
+            // Unpaired low surrogate.
+            (b"\xfe\xff\0o\xdc\x00", "UTF-16BE", 4),
+            // Trailing odd byte.
+            (b"\xff\xfeo\0n", "UTF-16LE", 4),
+        ] {
+            let Err(crate::Error::Encoding { encoding, offset }) = decode(input) else {
+                panic!("expected {input:?} to fail");
+            };
+            assert_eq!(encoding, expected_encoding);
+            assert_eq!(offset, expected_offset);
+        }
+    }
+
     #[test]
     fn test_permissions() {
         assert_eq!(
@@ -399,6 +1499,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_permissions_access() {
+        let default = Permissions::default();
+        assert!(!default.has_read_access_to("contents"));
+        assert!(!default.has_write_access_to("contents"));
+
+        let read_all = Permissions::Base(BasePermission::ReadAll);
+        assert!(read_all.has_read_access_to("contents"));
+        assert!(!read_all.has_write_access_to("contents"));
+
+        let write_all = Permissions::Base(BasePermission::WriteAll);
+        assert!(write_all.has_read_access_to("id-token"));
+        assert!(write_all.has_write_access_to("id-token"));
+
+        let explicit = serde_yaml::from_str::<Permissions>(
+            "contents: read\npull-requests: write\nid-token: none",
+        )
+        .unwrap();
+        assert!(explicit.has_read_access_to("contents"));
+        assert!(!explicit.has_write_access_to("contents"));
+        assert!(explicit.has_read_access_to("pull-requests"));
+        assert!(explicit.has_write_access_to("pull-requests"));
+        assert!(!explicit.has_read_access_to("id-token"));
+        assert!(!explicit.has_write_access_to("id-token"));
+        // Unlisted scopes have no access.
+        assert!(!explicit.has_read_access_to("packages"));
+        assert!(!explicit.has_write_access_to("packages"));
+
+        // An empty mapping (`permissions: {}`) disables everything.
+        let empty = serde_yaml::from_str::<Permissions>("{}").unwrap();
+        assert!(!empty.has_read_access_to("contents"));
+    }
+
+    #[test]
+    #[cfg(any(feature = "workflow", feature = "action", feature = "dependabot"))]
+    fn test_from_yaml_bom() {
+        let env: Env = super::from_yaml("\u{feff}foo: bar\r\nbaz: quux\r\n").unwrap();
+        assert_eq!(env["foo"], EnvValue::String("bar".into()));
+        assert_eq!(env["baz"], EnvValue::String("quux".into()));
+
+        assert!(super::from_yaml::<Env>("foo: bar\n---\nbaz: quux").is_err());
+    }
+
+    #[test]
+    fn test_env_value_coercions() {
+        let vectors = [
+            (EnvValue::String("TRUE".into()), "TRUE", Some(true), None),
+            (EnvValue::String("false".into()), "false", Some(false), None),
+            (EnvValue::String("yes".into()), "yes", None, None),
+            (EnvValue::String("".into()), "", None, None),
+            (EnvValue::String(" 1.5 ".into()), " 1.5 ", None, Some(1.5)),
+            (EnvValue::String("-3".into()), "-3", None, Some(-3.0)),
+            (EnvValue::String("inf".into()), "inf", None, None),
+            (EnvValue::String("NaN".into()), "NaN", None, None),
+            (EnvValue::Number(1.0), "1", None, Some(1.0)),
+            (EnvValue::Number(0.25), "0.25", None, Some(0.25)),
+            (EnvValue::Boolean(true), "true", Some(true), None),
+            (EnvValue::Boolean(false), "false", Some(false), None),
+        ];
+
+        for (value, string, bool, f64) in vectors {
+            assert_eq!(value.coerce_to_string(), string);
+            assert_eq!(value.coerce_to_string(), value.to_string());
+            assert_eq!(value.try_as_bool(), bool, "{value:?}");
+            assert_eq!(value.try_as_f64(), f64, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_if() {
+        let vectors = [
+            (If::Bool(true), "true", true, true),
+            (If::Bool(false), "false", false, false),
+            (If::Expr("always()".into()), "always()", true, false),
+            (If::Expr(" Always() ".into()), " Always() ", true, false),
+            (
+                If::Expr("${{ always() }}".into()),
+                "${{ always() }}",
+                true,
+                false,
+            ),
+            (If::Expr("TRUE".into()), "TRUE", true, false),
+            (If::Expr("success()".into()), "success()", false, true),
+            (
+                If::Expr("${{success()}}".into()),
+                "${{success()}}",
+                false,
+                true,
+            ),
+            (If::Expr("failure()".into()), "failure()", false, false),
+            (
+                If::Expr("always() && github.ref == 'refs/heads/main'".into()),
+                "always() && github.ref == 'refs/heads/main'",
+                false,
+                false,
+            ),
+        ];
+
+        for (cond, as_str, always, success) in vectors {
+            assert_eq!(cond.as_str(), as_str);
+            assert_eq!(cond.is_always_true(), always, "{cond:?}");
+            assert_eq!(cond.is_success_condition(), success, "{cond:?}");
+        }
+    }
+
     #[test]
     fn test_env_empty_value() {
         let env = "foo:";
@@ -576,12 +1781,35 @@ mod tests {
         ];
 
         for (input, expected) in vectors {
+            if let Ok(Uses::Docker(docker)) = &expected {
+                // An empty tag is dropped, like a missing one.
+                let image_ref = input.strip_prefix("docker://").unwrap();
+                assert_eq!(docker.full_image_ref(), image_ref.trim_end_matches(':'));
+                assert_eq!(docker.is_digest_pinned(), image_ref.contains('@'));
+                assert_eq!(
+                    docker.is_latest_tag(),
+                    docker.tag.is_none() && docker.hash.is_none()
+                );
+            }
+
             assert_eq!(input.parse(), expected);
         }
+
+        let docker = "ubuntu:latest".parse::<DockerUses>().unwrap();
+        assert!(docker.is_latest_tag());
+        let docker = "ubuntu:latest@sha256:abcd".parse::<DockerUses>().unwrap();
+        assert!(docker.is_digest_pinned());
+        assert!(!docker.is_latest_tag());
+        assert_eq!(docker.full_image_ref(), "ubuntu:latest@sha256:abcd");
     }
 
     #[test]
+    #[cfg(feature = "workflow")]
     fn test_uses_deser_reusable() {
+        use serde::Deserialize;
+
+        use super::reusable_step_uses;
+
         let vectors = [
             // Valid, as expected.
             (
@@ -642,4 +1870,139 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_is_identifier() {
+        for ident in ["foo", "_foo", "foo-bar", "foo_bar2", "F"] {
+            assert!(is_identifier(ident), "{ident}");
+        }
+        for non_ident in ["", "foo.bar", "foo bar", "2foo", "-foo", "foo!"] {
+            assert!(!is_identifier(non_ident), "{non_ident}");
+        }
+    }
+
+    #[test]
+    fn test_shell() {
+        for (raw, shell) in [
+            ("bash", Shell::Bash),
+            ("sh", Shell::Sh),
+            ("pwsh", Shell::Pwsh),
+            ("powershell", Shell::Powershell),
+            ("cmd", Shell::Cmd),
+            ("python", Shell::Python),
+        ] {
+            let parsed = serde_yaml::from_str::<Shell>(raw).unwrap();
+            assert_eq!(parsed, shell);
+            assert!(parsed.is_builtin());
+            assert_eq!(parsed.to_string(), raw);
+            assert_eq!(serde_yaml::to_string(&parsed).unwrap(), format!("{raw}\n"));
+        }
+
+        let custom = serde_yaml::from_str::<Shell>("perl {0}").unwrap();
+        assert_eq!(custom, Shell::Custom("perl {0}".into()));
+        assert!(!custom.is_builtin());
+        assert_eq!(serde_yaml::to_string(&custom).unwrap(), "perl {0}\n");
+
+        // Builtins are case-sensitive.
+        assert_eq!(
+            "Bash".parse::<Shell>().unwrap(),
+            Shell::Custom("Bash".into())
+        );
+    }
+
+    #[test]
+    fn test_repository_uses_refs() {
+        let uses = "actions/aws/ec2@v4".parse::<RepositoryUses>().unwrap();
+        assert_eq!(uses.full_slug(), "actions/aws");
+
+        let sha = "8f4b7f84864484a7bf31766abe9204da3cbe65b3";
+        let pinned = uses.with_ref(sha);
+        assert_eq!(pinned.to_string(), format!("actions/aws/ec2@{sha}"));
+        assert_eq!(
+            pinned.to_string().parse::<Uses>().unwrap(),
+            Uses::Repository(RepositoryUses {
+                owner: "actions".into(),
+                repo: "aws".into(),
+                subpath: Some("ec2".into()),
+                git_ref: Some(sha.into()),
+            })
+        );
+
+        let unpinned = uses.without_ref();
+        assert_eq!(unpinned.to_string(), "actions/aws/ec2");
+        assert_eq!(
+            unpinned.to_string().parse::<Uses>().unwrap(),
+            Uses::Repository(unpinned.clone())
+        );
+        assert_eq!(unpinned.with_ref("v4"), uses);
+
+        // The original is untouched.
+        assert_eq!(uses.git_ref.as_deref(), Some("v4"));
+    }
+
+    #[test]
+    fn test_shell_custom_template() {
+        let template = |shell: &str| Shell::Custom(shell.into()).custom_template();
+
+        assert_eq!(
+            template("perl {0}").unwrap(),
+            ShellTemplate {
+                program: "perl".into(),
+                args: vec!["{0}".into()],
+                script_position: 0,
+            }
+        );
+
+        let deno = template("deno run --allow-all {0}").unwrap();
+        assert_eq!(deno.program, "deno");
+        assert_eq!(deno.args, ["run", "--allow-all", "{0}"]);
+        assert_eq!(deno.script_position, 2);
+
+        // Quoted arguments and embedded placeholders.
+        let quoted =
+            template(r#"bash --noprofile -c 'set -e; source "$1"' "my shell" --file={0} -v"#)
+                .unwrap();
+        assert_eq!(quoted.program, "bash");
+        assert_eq!(
+            quoted.args,
+            [
+                "--noprofile",
+                "-c",
+                r#"set -e; source "$1""#,
+                "my shell",
+                "--file={0}",
+                "-v"
+            ]
+        );
+        assert_eq!(quoted.script_position, 4);
+
+        let escaped = template(r#"my\ tool "a \"b\" \c" {0}"#).unwrap();
+        assert_eq!(escaped.program, "my tool");
+        assert_eq!(escaped.args, [r#"a "b" \c"#, "{0}"]);
+
+        // Errors.
+        for (shell, err) in [
+            ("perl", "expected exactly one `{0}` placeholder: perl"),
+            (
+                "python {0} {0}",
+                "expected exactly one `{0}` placeholder: python {0} {0}",
+            ),
+            (
+                "{0} --flag",
+                "`{0}` placeholder can't be the program: {0} --flag",
+            ),
+            ("", "empty shell template"),
+            ("perl '{0}", "unterminated quote: perl '{0}"),
+            ("perl {0} \\", "trailing backslash: perl {0} \\"),
+        ] {
+            assert_eq!(
+                template(shell).unwrap_err().to_string(),
+                format!("malformed shell template: {err}")
+            );
+        }
+        assert_eq!(
+            Shell::Bash.custom_template().unwrap_err().to_string(),
+            "malformed shell template: `bash` is a builtin shell"
+        );
+    }
 }
@@ -0,0 +1,254 @@
+//! Machine-readable diagnostics produced by validation APIs.
+//!
+//! Every diagnostic has a stable [`DiagnosticCode`], all of which are
+//! listed in [`CODES`]. Downstream tools can use this registry to build
+//! configuration or suppression interfaces.
+
+use std::{fmt, ops::Range};
+
+/// How serious a [`Diagnostic`] is.
+///
+/// Variants are ordered from least to most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Informational; nothing is wrong.
+    Note,
+    /// Valid to GitHub, but probably a mistake.
+    Warning,
+    /// Rejected by GitHub, or certain to fail at runtime.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Note => write!(f, "note"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A registered diagnostic kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DiagnosticCode {
+    /// The stable code, e.g. `W101-unreachable-output-name`.
+    pub code: &'static str,
+    /// The severity of every diagnostic with this code.
+    pub severity: Severity,
+    /// A short, human-readable summary of what this code means.
+    pub summary: &'static str,
+}
+
+/// A job or `workflow_call` output whose name can't be referenced from
+/// expressions, e.g. `foo.bar`.
+pub const UNREACHABLE_OUTPUT_NAME: DiagnosticCode = DiagnosticCode {
+    code: "W101-unreachable-output-name",
+    severity: Severity::Warning,
+    summary: "output name cannot be referenced from expressions",
+};
+
+/// A reference to a `needs` job output whose name can't be referenced
+/// from expressions.
+pub const UNREACHABLE_OUTPUT_REFERENCE: DiagnosticCode = DiagnosticCode {
+    code: "W102-unreachable-output-reference",
+    severity: Severity::Warning,
+    summary: "reference to an output whose name cannot be referenced from expressions",
+};
+
+/// A reference to a `needs` job output that isn't defined.
+pub const UNDEFINED_OUTPUT_REFERENCE: DiagnosticCode = DiagnosticCode {
+    code: "W103-undefined-output-reference",
+    severity: Severity::Warning,
+    summary: "reference to an undefined job output",
+};
+
+/// A secret passed to a reusable workflow via `with:` rather than `secrets:`.
+pub const SECRET_IN_WITH: DiagnosticCode = DiagnosticCode {
+    code: "W104-secret-in-with",
+    severity: Severity::Warning,
+    summary: "secret passed to a reusable workflow as an input",
+};
+
+/// A non-secret value passed to a reusable workflow via `secrets:`.
+pub const NON_SECRET_IN_SECRETS: DiagnosticCode = DiagnosticCode {
+    code: "W105-non-secret-in-secrets",
+    severity: Severity::Warning,
+    summary: "non-secret value passed to a reusable workflow as a secret",
+};
+
+/// An event that's listed more than once in a workflow's `on:`.
+pub const DUPLICATE_EVENT: DiagnosticCode = DiagnosticCode {
+    code: "W106-duplicate-event",
+    severity: Severity::Warning,
+    summary: "event is listed more than once",
+};
+
+/// A `needs` entry that doesn't name a job in the workflow.
+pub const UNKNOWN_NEED: DiagnosticCode = DiagnosticCode {
+    code: "E101-unknown-need",
+    severity: Severity::Error,
+    summary: "job needs a job that doesn't exist",
+};
+
+/// A job that lists itself in its own `needs`.
+pub const SELF_NEED: DiagnosticCode = DiagnosticCode {
+    code: "E102-self-need",
+    severity: Severity::Error,
+    summary: "job needs itself",
+};
+
+/// A job ID, step ID, or action input or output name that isn't an
+/// identifier.
+pub const INVALID_IDENTIFIER: DiagnosticCode = DiagnosticCode {
+    code: "E103-invalid-identifier",
+    severity: Severity::Error,
+    summary: "ID or name is not a valid identifier",
+};
+
+/// A matrix dimension named `include` or `exclude`.
+pub const RESERVED_MATRIX_DIMENSION: DiagnosticCode = DiagnosticCode {
+    code: "E104-reserved-matrix-dimension",
+    severity: Severity::Error,
+    summary: "matrix dimension uses a reserved name",
+};
+
+/// A matrix dimension with no values.
+pub const EMPTY_MATRIX_DIMENSION: DiagnosticCode = DiagnosticCode {
+    code: "E105-empty-matrix-dimension",
+    severity: Severity::Error,
+    summary: "matrix dimension has no values",
+};
+
+/// A matrix `exclude` entry with a key that isn't a dimension.
+pub const UNKNOWN_MATRIX_EXCLUDE_KEY: DiagnosticCode = DiagnosticCode {
+    code: "E106-unknown-matrix-exclude-key",
+    severity: Severity::Error,
+    summary: "matrix exclude key is not a dimension",
+};
+
+/// A Dependabot update that uses a registry that isn't configured.
+pub const UNKNOWN_REGISTRY: DiagnosticCode = DiagnosticCode {
+    code: "E107-unknown-registry",
+    severity: Severity::Error,
+    summary: "update uses a registry that isn't configured",
+};
+
+/// A step `id` that an earlier step in the same job already uses.
+pub const DUPLICATE_STEP_ID: DiagnosticCode = DiagnosticCode {
+    code: "E108-duplicate-step-id",
+    severity: Severity::Error,
+    summary: "step ID is used by more than one step",
+};
+
+/// Every registered diagnostic code.
+pub const CODES: &[DiagnosticCode] = &[
+    UNREACHABLE_OUTPUT_NAME,
+    UNREACHABLE_OUTPUT_REFERENCE,
+    UNDEFINED_OUTPUT_REFERENCE,
+    SECRET_IN_WITH,
+    NON_SECRET_IN_SECRETS,
+    DUPLICATE_EVENT,
+    UNKNOWN_NEED,
+    SELF_NEED,
+    INVALID_IDENTIFIER,
+    RESERVED_MATRIX_DIMENSION,
+    EMPTY_MATRIX_DIMENSION,
+    UNKNOWN_MATRIX_EXCLUDE_KEY,
+    UNKNOWN_REGISTRY,
+    DUPLICATE_STEP_ID,
+];
+
+/// A single problem found by a validation API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The registered code for this diagnostic.
+    pub code: DiagnosticCode,
+    /// The structural path to the offending value, e.g.
+    /// `["jobs", "build", "outputs", "foo.bar"]`.
+    pub path: Vec<String>,
+    /// The byte span of the offending value within its source, if known.
+    pub span: Option<Range<usize>>,
+    /// A human-readable description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic, without a span.
+    pub fn new<P, S>(code: DiagnosticCode, path: P, message: impl Into<String>) -> Self
+    where
+        P: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            code,
+            path: path.into_iter().map(Into::into).collect(),
+            span: None,
+            message: message.into(),
+        }
+    }
+
+    /// Returns this diagnostic's severity.
+    pub fn severity(&self) -> Severity {
+        self.code.severity
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.severity(),
+            self.code.code,
+            self.message
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{Diagnostic, Severity, CODES, UNREACHABLE_OUTPUT_NAME};
+
+    #[test]
+    fn test_codes_unique() {
+        let mut seen = HashSet::new();
+        for code in CODES {
+            assert!(seen.insert(code.code), "duplicate code {}", code.code);
+        }
+
+        let mut prefixes = HashSet::new();
+        for code in CODES {
+            let (prefix, _) = code.code.split_once('-').unwrap();
+            assert!(prefixes.insert(prefix), "duplicate prefix {prefix}");
+
+            let expected = match code.severity {
+                Severity::Note => 'N',
+                Severity::Warning => 'W',
+                Severity::Error => 'E',
+            };
+            assert!(
+                prefix.starts_with(expected),
+                "{} has wrong prefix",
+                code.code
+            );
+        }
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = Diagnostic::new(
+            UNREACHABLE_OUTPUT_NAME,
+            ["jobs", "build", "outputs", "foo.bar"],
+            "job `build` output name `foo.bar` cannot be referenced from expressions",
+        );
+        assert_eq!(diagnostic.severity(), Severity::Warning);
+        assert_eq!(
+            diagnostic.to_string(),
+            "warning[W101-unreachable-output-name]: \
+             job `build` output name `foo.bar` cannot be referenced from expressions"
+        );
+    }
+}
@@ -1,9 +1,11 @@
 //! GitHub Actions expression parsing and handling.
 
+use std::ops::Range;
+
 use serde::{Deserialize, Serialize};
 
 /// An explicit GitHub Actions expression, fenced by `${{ <expr> }}`.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct ExplicitExpr(String);
 
 impl ExplicitExpr {
@@ -20,6 +22,13 @@ impl ExplicitExpr {
             return None;
         }
 
+        // Reject templates that merely start and end with expressions,
+        // e.g. `${{ a }}-${{ b }}`.
+        if !matches!(interpolations(trimmed).as_slice(), [(span, _)] if span.len() == trimmed.len())
+        {
+            return None;
+        }
+
         Some(ExplicitExpr(expr))
     }
 
@@ -47,6 +56,44 @@ impl ExplicitExpr {
             .map(|e| e.trim())
             .expect("invariant violated: ExplicitExpr must be an expression")
     }
+
+    /// Returns whether this expression reads from the given context or
+    /// one of its properties, e.g. `github.event` for
+    /// `${{ github.event.issue.title }}`.
+    ///
+    /// A context reference matches if it [starts with](ContextPath::starts_with)
+    /// `context_prefix`, segment by segment, so e.g. `github.event_name`
+    /// doesn't match `github.event`. References within string literals
+    /// are ignored.
+    ///
+    /// This is a heuristic: it can't see through e.g. `fromJSON` or
+    /// `env` values that were themselves populated from other contexts.
+    pub fn references_context(&self, context_prefix: &str) -> bool {
+        let Some(prefix) = ContextPath::parse(context_prefix) else {
+            return false;
+        };
+
+        context_paths(self.as_bare())
+            .into_iter()
+            .filter_map(ContextPath::parse)
+            .any(|path| path.starts_with(&prefix))
+    }
+
+    /// Returns whether this expression reads from the `secrets` context.
+    pub fn references_secrets(&self) -> bool {
+        self.references_context("secrets")
+    }
+
+    /// Returns whether this expression reads from `github.event`, i.e. the
+    /// triggering event's payload.
+    pub fn references_github_event(&self) -> bool {
+        self.references_context("github.event")
+    }
+
+    /// Returns whether this expression reads from the `env` context.
+    pub fn references_env(&self) -> bool {
+        self.references_context("env")
+    }
 }
 
 impl<'de> Deserialize<'de> for ExplicitExpr {
@@ -69,7 +116,7 @@ impl<'de> Deserialize<'de> for ExplicitExpr {
 /// A "literal or expr" type, for places in GitHub Actions where a
 /// key can either have a literal value (array, object, etc.) or an
 /// expression string.
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum LoE<T> {
     // Observe that `Expr` comes first, since `LoE<String>` should always
@@ -88,12 +135,552 @@ where
     }
 }
 
+impl<T> From<T> for LoE<T> {
+    /// Wraps `value` as a literal.
+    ///
+    /// This never classifies `value`: `LoE::<String>::from("${{ foo }}")`
+    /// is a literal, unlike deserializing the same string.
+    fn from(value: T) -> Self {
+        Self::Literal(value)
+    }
+}
+
+/// Implements `From<ExplicitExpr>` for `LoE<T>` for each given `T`.
+///
+/// A blanket impl would overlap with `From<T>` when `T` is `ExplicitExpr`,
+/// so these are spelled out for each literal type that the models use.
+/// `LoE<ExplicitExpr>::from(expr)` is therefore always a literal.
+macro_rules! impl_from_expr {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl From<$crate::common::expr::ExplicitExpr> for $crate::common::expr::LoE<$ty> {
+                fn from(expr: $crate::common::expr::ExplicitExpr) -> Self {
+                    Self::Expr(expr)
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "workflow")]
+pub(crate) use impl_from_expr;
+
+impl_from_expr!(String, bool, u64, super::Env);
+
+impl LoE<String> {
+    /// Returns the underlying string, exactly as written, whether it's
+    /// a literal or an expression.
+    pub fn as_str(&self) -> &str {
+        match self {
+            LoE::Expr(expr) => expr.as_raw(),
+            LoE::Literal(value) => value,
+        }
+    }
+}
+
+/// Classify `value` as an expression or a literal string, like
+/// deserializing a `LoE<String>` does.
+#[cfg(feature = "workflow")]
+pub(crate) fn string_loe(value: String) -> LoE<String> {
+    match ExplicitExpr::from_curly(value.as_str()) {
+        Some(expr) => LoE::Expr(expr),
+        None => LoE::Literal(value),
+    }
+}
+
 /// A convenience alias for a `bool` literal or an actions expression.
 pub type BoE = LoE<bool>;
 
+/// Find each `${{ ... }}` expression interpolated into `s`.
+///
+/// Each item is the byte span of the entire `${{ ... }}` fragment within `s`,
+/// along with the expression's trimmed body. Unterminated fragments are
+/// ignored.
+pub fn interpolations(s: &str) -> Vec<(Range<usize>, &str)> {
+    let mut interpolations = vec![];
+    let mut cursor = 0;
+
+    while let Some(start) = s[cursor..].find("${{").map(|idx| cursor + idx) {
+        let body_start = start + "${{".len();
+        let Some(body_end) = find_expr_end(&s[body_start..]).map(|idx| body_start + idx) else {
+            break;
+        };
+        let end = body_end + "}}".len();
+
+        interpolations.push((start..end, s[body_start..body_end].trim()));
+        cursor = end;
+    }
+
+    interpolations
+}
+
+/// A piece of a string that may have expressions interpolated into it,
+/// as returned by [`template_parts`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemplatePart<'a> {
+    /// Literal text.
+    Literal(&'a str),
+    /// An interpolated expression's trimmed body, e.g. `matrix.os`
+    /// for `${{ matrix.os }}`.
+    Expr(&'a str),
+}
+
+/// Split `s` into its literal text and interpolated expressions, in order.
+///
+/// Empty literal text between or around expressions is omitted.
+pub fn template_parts(s: &str) -> Vec<TemplatePart<'_>> {
+    let mut parts = vec![];
+    let mut cursor = 0;
+
+    for (span, body) in interpolations(s) {
+        if span.start > cursor {
+            parts.push(TemplatePart::Literal(&s[cursor..span.start]));
+        }
+        parts.push(TemplatePart::Expr(body));
+        cursor = span.end;
+    }
+
+    if cursor < s.len() {
+        parts.push(TemplatePart::Literal(&s[cursor..]));
+    }
+
+    parts
+}
+
+/// Find the `}}` that closes an expression body, skipping over any
+/// `}}` that appear within string literals.
+fn find_expr_end(body: &str) -> Option<usize> {
+    let mut in_string = false;
+    let mut chars = body.char_indices().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            // NOTE: Expression strings escape `'` by doubling it, which
+            // this handles naturally by toggling out and back in.
+            '\'' => in_string = !in_string,
+            '}' if !in_string && chars.peek().is_some_and(|(_, c)| *c == '}') => return Some(idx),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// A single token within an expression body, as produced by [`tokens`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    /// A context reference, e.g. `github.event.issue.title`.
+    Context(&'a str),
+    /// The contents of a string literal, without its surrounding quotes.
+    String(&'a str),
+    /// Anything else: operators, punctuation, function names, and
+    /// non-string literals.
+    Other(&'a str),
+}
+
+/// Split an expression body into [`Token`]s, skipping whitespace.
+fn tokens(expr: &str) -> Vec<Token<'_>> {
+    let is_ident_start = |c: u8| c.is_ascii_alphabetic() || c == b'_';
+    let is_ident = |c: u8| c.is_ascii_alphanumeric() || c == b'_' || c == b'-';
+
+    let bytes = expr.as_bytes();
+    let mut tokens = vec![];
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        let c = bytes[idx];
+        let start = idx;
+
+        if c == b'\'' {
+            // NOTE: Doubled `''` escapes end up as two adjacent literals,
+            // which is fine for our purposes.
+            idx += 1;
+            while idx < bytes.len() && bytes[idx] != b'\'' {
+                idx += 1;
+            }
+            tokens.push(Token::String(&expr[start + 1..idx.min(bytes.len())]));
+            idx += 1;
+        } else if c.is_ascii_digit() {
+            // Numeric literals, including e.g. `0xff` and `1.5`.
+            while idx < bytes.len() && (is_ident(bytes[idx]) || bytes[idx] == b'.') {
+                idx += 1;
+            }
+            tokens.push(Token::Other(&expr[start..idx]));
+        } else if is_ident_start(c) {
+            while idx < bytes.len() && is_ident(bytes[idx]) {
+                idx += 1;
+            }
+
+            // Consume any property accesses, indices, and `.*` filters.
+            loop {
+                match bytes.get(idx) {
+                    Some(b'.')
+                        if bytes
+                            .get(idx + 1)
+                            .is_some_and(|c| is_ident_start(*c) || *c == b'*') =>
+                    {
+                        idx += 2;
+                        while idx < bytes.len() && is_ident(bytes[idx]) {
+                            idx += 1;
+                        }
+                    }
+                    Some(b'[') => {
+                        let mut in_string = false;
+                        idx += 1;
+                        while idx < bytes.len() && (in_string || bytes[idx] != b']') {
+                            if bytes[idx] == b'\'' {
+                                in_string = !in_string;
+                            }
+                            idx += 1;
+                        }
+                        idx += 1;
+                    }
+                    _ => break,
+                }
+            }
+
+            let ident = &expr[start..idx.min(bytes.len())];
+            let is_call = expr[idx.min(bytes.len())..].trim_start().starts_with('(');
+            let is_literal = matches!(ident, "true" | "false" | "null");
+            if is_call || is_literal {
+                tokens.push(Token::Other(ident));
+            } else {
+                tokens.push(Token::Context(ident));
+            }
+        } else if c.is_ascii_whitespace() {
+            idx += 1;
+        } else {
+            // Operators are at most two characters, e.g. `==` or `&&`.
+            idx += match (c, bytes.get(idx + 1)) {
+                (b'=' | b'!' | b'<' | b'>', Some(b'='))
+                | (b'&', Some(b'&'))
+                | (b'|', Some(b'|')) => 2,
+                _ => expr[idx..].chars().next().map_or(1, char::len_utf8),
+            };
+            tokens.push(Token::Other(&expr[start..idx]));
+        }
+    }
+
+    tokens
+}
+
+/// Extract the context references (e.g. `github.event.pull_request.title`
+/// or `matrix['os']`) from an expression body, in order of appearance.
+///
+/// Function names and literals are not included, but references within
+/// function arguments are.
+pub fn context_paths(expr: &str) -> Vec<&str> {
+    tokens(expr)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Context(path) => Some(path),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A direct `==` or `!=` comparison between a context and a string
+/// literal, e.g. `github.ref == 'refs/heads/main'`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Comparison<'a> {
+    /// The context being compared, e.g. `github.ref`.
+    pub context: &'a str,
+    /// Whether this is an `==` (rather than `!=`) comparison.
+    pub equal: bool,
+    /// The string literal being compared against, without quotes.
+    ///
+    /// Note that GitHub compares strings case-insensitively.
+    pub literal: &'a str,
+}
+
+/// Extract each direct context-to-string-literal comparison from an
+/// expression body, in either operand order.
+///
+/// Comparisons against function results or other contexts aren't
+/// included.
+pub fn comparisons(expr: &str) -> Vec<Comparison<'_>> {
+    tokens(expr)
+        .windows(3)
+        .filter_map(|window| {
+            let equal = match window[1] {
+                Token::Other("==") => true,
+                Token::Other("!=") => false,
+                _ => return None,
+            };
+            match (window[0], window[2]) {
+                (Token::Context(context), Token::String(literal))
+                | (Token::String(literal), Token::Context(context)) => Some(Comparison {
+                    context,
+                    equal,
+                    literal,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// How far a context's value can be trusted, e.g. when interpolated
+/// into a shell script.
+///
+/// Variants are ordered from most to least trustworthy, so the overall
+/// trust of several contexts is their maximum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ContextTrust {
+    /// The value is fixed by GitHub or the workflow itself, e.g. `github.sha`
+    /// or `runner.os`.
+    Trusted,
+    /// The value's provenance isn't known, e.g. `inputs.*` or
+    /// `steps.*.outputs.*`.
+    Unknown,
+    /// The value can be controlled by an external attacker, e.g.
+    /// `github.event.pull_request.title`.
+    Untrusted,
+}
+
+/// Contexts whose values are fixed by GitHub or the workflow.
+///
+/// Each entry also covers any path beneath it.
+const TRUSTED_CONTEXTS: &[&str] = &[
+    "github.action_path",
+    "github.api_url",
+    "github.event_name",
+    "github.graphql_url",
+    "github.job",
+    "github.ref_type",
+    "github.repository",
+    "github.repository_id",
+    "github.repository_owner",
+    "github.repository_owner_id",
+    "github.retention_days",
+    "github.run_attempt",
+    "github.run_id",
+    "github.run_number",
+    "github.server_url",
+    "github.sha",
+    "github.workflow",
+    "github.workspace",
+    "job",
+    // NOTE: Matrix values are usually static, but can be computed from
+    // other contexts (e.g. `fromJSON(inputs.matrix)`).
+    "matrix",
+    "runner",
+    "secrets",
+    "strategy",
+];
+
+/// Contexts whose values can be controlled by an external attacker,
+/// e.g. by opening a pull request or issue.
+///
+/// `*` matches any single property or index. Each entry also covers any
+/// path beneath it, and any path that contains it (e.g. all of
+/// `github.event`).
+const UNTRUSTED_CONTEXTS: &[&str] = &[
+    "github.event.comment.body",
+    "github.event.commits.*.author.email",
+    "github.event.commits.*.author.name",
+    "github.event.commits.*.message",
+    "github.event.discussion.body",
+    "github.event.discussion.title",
+    "github.event.head_commit.author.email",
+    "github.event.head_commit.author.name",
+    "github.event.head_commit.message",
+    "github.event.issue.body",
+    "github.event.issue.title",
+    "github.event.pages.*.page_name",
+    "github.event.pull_request.body",
+    "github.event.pull_request.head.label",
+    "github.event.pull_request.head.ref",
+    "github.event.pull_request.head.repo.default_branch",
+    "github.event.pull_request.title",
+    "github.event.review.body",
+    "github.event.review_comment.body",
+    "github.event.workflow_run.head_branch",
+    "github.event.workflow_run.head_commit.author.email",
+    "github.event.workflow_run.head_commit.author.name",
+    "github.event.workflow_run.head_commit.message",
+    "github.event.workflow_run.pull_requests.*.head.ref",
+    "github.head_ref",
+];
+
+impl ContextTrust {
+    /// Classify a context reference, as returned by [`context_paths`].
+    pub fn of(context: &str) -> Self {
+        let Some(path) = ContextPath::parse(context) else {
+            return Self::Unknown;
+        };
+        let pattern = |pattern: &str| {
+            ContextPath::parse(pattern).expect("invariant violated: invalid context pattern")
+        };
+
+        let untrusted = UNTRUSTED_CONTEXTS
+            .iter()
+            .map(|p| pattern(p))
+            .any(|pattern| {
+                // Either a prefix of the other: both `github.event` and
+                // `github.event.issue.title.length` contain attacker-controlled data.
+                path.starts_with(&pattern) || pattern.starts_with(&path)
+            });
+        if untrusted {
+            return Self::Untrusted;
+        }
+
+        let trusted = TRUSTED_CONTEXTS
+            .iter()
+            .any(|p| path.starts_with(&pattern(p)));
+        if trusted {
+            Self::Trusted
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// A single segment of a [`ContextPath`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Segment {
+    /// A property access, e.g. `event` in `github.event`.
+    Ident(String),
+    /// A string index, e.g. `title` in `github.event['title']`.
+    Key(String),
+    /// A numeric index, e.g. `0` in `github.event.commits[0]`.
+    Index(u64),
+    /// An object filter, e.g. `*` in `github.event.commits.*.message`.
+    ///
+    /// Indices that are themselves expressions, e.g. `matrix[inputs.key]`,
+    /// are also represented as wildcards, since they could be anything.
+    Wildcard,
+}
+
+impl Segment {
+    /// Returns whether this segment matches `other`, treating
+    /// [`Segment::Wildcard`] on either side as matching anything.
+    ///
+    /// Property and string index names are compared ASCII
+    /// case-insensitively, like GitHub's context lookups, so
+    /// `github.event.issue` matches `github.event['issue']`.
+    fn matches(&self, other: &Segment) -> bool {
+        match (self, other) {
+            (Segment::Wildcard, _) | (_, Segment::Wildcard) => true,
+            (Segment::Ident(a) | Segment::Key(a), Segment::Ident(b) | Segment::Key(b)) => {
+                a.eq_ignore_ascii_case(b)
+            }
+            (Segment::Index(a), Segment::Index(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// A context reference split into its segments, e.g. `github`, `event`,
+/// `commits`, `0`, `message` for `github.event.commits[0].message`.
+///
+/// The [`Display`](std::fmt::Display) form is canonical: properties are
+/// dotted, string and numeric indices are bracketed, and object filters
+/// are written as `.*`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ContextPath(Vec<Segment>);
+
+impl ContextPath {
+    /// Parse a context reference, as returned by [`context_paths`].
+    ///
+    /// Returns `None` if `path` isn't a well-formed context reference.
+    pub fn parse(path: &str) -> Option<Self> {
+        let is_ident_start = |c: char| c.is_ascii_alphabetic() || c == '_';
+        let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+        let ident = |rest: &str| -> Option<usize> {
+            rest.starts_with(is_ident_start)
+                .then(|| rest.find(|c| !is_ident(c)).unwrap_or(rest.len()))
+        };
+
+        let path = path.trim();
+        let len = ident(path)?;
+        let mut segments = vec![Segment::Ident(path[..len].into())];
+        let mut rest = &path[len..];
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix(".*") {
+                segments.push(Segment::Wildcard);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let len = ident(after)?;
+                segments.push(Segment::Ident(after[..len].into()));
+                rest = &after[len..];
+            } else if let Some(after) = rest.strip_prefix('[').map(str::trim_start) {
+                let Some(after) = after.strip_prefix('\'') else {
+                    let end = after.find(']')?;
+                    segments.push(match after[..end].trim().parse() {
+                        Ok(index) => Segment::Index(index),
+                        Err(_) => Segment::Wildcard,
+                    });
+                    rest = &after[end + 1..];
+                    continue;
+                };
+
+                // Find the closing quote, skipping doubled `''` escapes.
+                let mut key = String::new();
+                let mut chars = after.char_indices();
+                let end = loop {
+                    match chars.next()? {
+                        (idx, '\'') if after[idx + 1..].starts_with('\'') => {
+                            key.push('\'');
+                            chars.next();
+                        }
+                        (idx, '\'') => break idx,
+                        (_, c) => key.push(c),
+                    }
+                };
+                rest = after[end + 1..].trim_start().strip_prefix(']')?;
+                segments.push(Segment::Key(key));
+            } else {
+                return None;
+            }
+        }
+
+        Some(Self(segments))
+    }
+
+    /// Returns this path's segments.
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    /// Returns whether this path is `prefix` or a path beneath it, with
+    /// segments compared as described in [`Segment`]'s variants.
+    ///
+    /// Wildcards on either side match any single segment, so
+    /// `github.event.commits[0].message` starts with
+    /// `github.event.commits.*`, and vice versa.
+    pub fn starts_with(&self, prefix: &ContextPath) -> bool {
+        self.0.len() >= prefix.0.len()
+            && prefix
+                .0
+                .iter()
+                .zip(&self.0)
+                .all(|(prefix, segment)| prefix.matches(segment))
+    }
+}
+
+impl std::fmt::Display for ContextPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Ident(ident) if idx == 0 => write!(f, "{ident}")?,
+                Segment::Ident(ident) => write!(f, ".{ident}")?,
+                Segment::Key(key) => write!(f, "['{}']", key.replace('\'', "''"))?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+                Segment::Wildcard => write!(f, ".*")?,
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ExplicitExpr, LoE};
+    use super::{
+        comparisons, context_paths, interpolations, template_parts, Comparison, ContextPath,
+        ContextTrust, ExplicitExpr, LoE, Segment, TemplatePart,
+    };
 
     #[test]
     fn test_expr_invalid() {
@@ -101,6 +688,7 @@ mod tests {
             "not an expression",
             "${{ missing end ",
             "missing beginning }}",
+            "${{ a }}-${{ b }}",
         ];
 
         for case in cases {
@@ -136,5 +724,316 @@ mod tests {
             serde_yaml::from_str::<LoE<String>>(invalid).unwrap(),
             LoE::Literal("${{ invalid ".to_string())
         );
+
+        // Templates that start and end with expressions are strings.
+        let template = "\"${{ github.workflow }}-${{ github.ref }}\"";
+        let template = serde_yaml::from_str::<LoE<String>>(template).unwrap();
+        assert_eq!(
+            template,
+            LoE::Literal("${{ github.workflow }}-${{ github.ref }}".to_string())
+        );
+        assert_eq!(
+            template.as_str(),
+            "${{ github.workflow }}-${{ github.ref }}"
+        );
+
+        let expr = serde_yaml::from_str::<LoE<String>>("\" ${{ expr }}\"").unwrap();
+        assert_eq!(expr.as_str(), " ${{ expr }}");
+    }
+
+    #[test]
+    fn test_loe_from() {
+        assert_eq!(LoE::from(true), LoE::Literal(true));
+        assert_eq!(
+            LoE::<String>::from("${{ foo }}".to_string()),
+            LoE::Literal("${{ foo }}".to_string())
+        );
+
+        let expr = ExplicitExpr::from_curly("${{ foo }}").unwrap();
+        assert_eq!(LoE::<bool>::from(expr.clone()), LoE::Expr(expr.clone()));
+        assert_eq!(LoE::<u64>::from(expr.clone()), LoE::Expr(expr.clone()));
+
+        // `From<T>` wins when the literal type is itself an expression.
+        assert_eq!(LoE::<ExplicitExpr>::from(expr.clone()), LoE::Literal(expr));
+    }
+
+    #[test]
+    fn test_interpolations() {
+        let script = "echo ${{ matrix.os }} && echo \"${{github.event.issue.title}}\" ${{ '}}' }}";
+        let found = interpolations(script);
+        assert_eq!(
+            found.iter().map(|(_, expr)| *expr).collect::<Vec<_>>(),
+            ["matrix.os", "github.event.issue.title", "'}}'"]
+        );
+        for (span, expr) in &found {
+            assert!(script[span.clone()].starts_with("${{"));
+            assert!(script[span.clone()].ends_with("}}"));
+            assert!(script[span.clone()].contains(expr));
+        }
+        assert_eq!(found[0].0, 5..21);
+
+        assert!(interpolations("no expressions").is_empty());
+        assert!(interpolations("unterminated ${{ foo").is_empty());
+    }
+
+    #[test]
+    fn test_template_parts() {
+        use TemplatePart::*;
+
+        let vectors: &[(&str, &[TemplatePart])] = &[
+            ("", &[]),
+            ("ubuntu-latest", &[Literal("ubuntu-latest")]),
+            ("${{ matrix.os }}", &[Expr("matrix.os")]),
+            (
+                "ubuntu-${{ matrix.version }}-arm",
+                &[Literal("ubuntu-"), Expr("matrix.version"), Literal("-arm")],
+            ),
+            ("${{ a }}${{ b }}", &[Expr("a"), Expr("b")]),
+            ("unterminated ${{ a", &[Literal("unterminated ${{ a")]),
+        ];
+
+        for (s, expected) in vectors {
+            assert_eq!(&template_parts(s), expected, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_context_paths() {
+        let vectors: &[(&str, &[&str])] = &[
+            ("github.event.issue.title", &["github.event.issue.title"]),
+            (
+                "contains(github.event.pull_request.labels.*.name, 'bug')",
+                &["github.event.pull_request.labels.*.name"],
+            ),
+            (
+                "github.event['pull_request']['title'] || 'fallback'",
+                &["github.event['pull_request']['title']"],
+            ),
+            ("toJSON(matrix) != null && true", &["matrix"]),
+            (
+                "steps.my-step.outputs.result == 1.5 && needs.a.result",
+                &["steps.my-step.outputs.result", "needs.a.result"],
+            ),
+            ("'github.sha' == format('{0}', 0xff)", &[]),
+        ];
+
+        for (expr, expected) in vectors {
+            assert_eq!(&context_paths(expr), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_context_path_segments() {
+        use Segment::*;
+
+        let ident = |s: &str| Ident(s.into());
+        let key = |s: &str| Key(s.into());
+
+        let vectors: &[(&str, &[Segment], &str)] = &[
+            (
+                "github.event.issue.title",
+                &[
+                    ident("github"),
+                    ident("event"),
+                    ident("issue"),
+                    ident("title"),
+                ],
+                "github.event.issue.title",
+            ),
+            (
+                "github.event.commits[0].message",
+                &[
+                    ident("github"),
+                    ident("event"),
+                    ident("commits"),
+                    Index(0),
+                    ident("message"),
+                ],
+                "github.event.commits[0].message",
+            ),
+            (
+                "github.event['pull_request'][ 'title' ]",
+                &[
+                    ident("github"),
+                    ident("event"),
+                    key("pull_request"),
+                    key("title"),
+                ],
+                "github.event['pull_request']['title']",
+            ),
+            (
+                "github.event.commits.*.message",
+                &[
+                    ident("github"),
+                    ident("event"),
+                    ident("commits"),
+                    Wildcard,
+                    ident("message"),
+                ],
+                "github.event.commits.*.message",
+            ),
+            (
+                "steps.my-step.outputs['it''s']",
+                &[
+                    ident("steps"),
+                    ident("my-step"),
+                    ident("outputs"),
+                    key("it's"),
+                ],
+                "steps.my-step.outputs['it''s']",
+            ),
+            (
+                "matrix[inputs.key]",
+                &[ident("matrix"), Wildcard],
+                "matrix.*",
+            ),
+        ];
+
+        for (path, segments, display) in vectors {
+            let Some(parsed) = ContextPath::parse(path) else {
+                assert!(segments.is_empty(), "{path} should parse");
+                continue;
+            };
+            assert_eq!(parsed.segments(), *segments, "{path}");
+            assert_eq!(parsed.to_string(), *display, "{path}");
+            // The canonical form is stable.
+            assert_eq!(ContextPath::parse(display).unwrap().to_string(), *display);
+        }
+
+        for invalid in [
+            "",
+            "1abc",
+            "github.",
+            "github..event",
+            "github['event'",
+            "a b",
+        ] {
+            assert!(ContextPath::parse(invalid).is_none(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_context_path_starts_with() {
+        let path = |path: &str| ContextPath::parse(path).unwrap();
+
+        // A wildcard table entry matches concrete paths of every form.
+        let pattern = path("github.event.commits.*.message");
+        for concrete in [
+            "github.event.commits[0].message",
+            "github.event.commits[12].message.length",
+            "github.event['commits'][1]['message']",
+            "GitHub.Event.Commits.*.Message",
+        ] {
+            assert!(path(concrete).starts_with(&pattern), "{concrete}");
+        }
+        assert!(!path("github.event.commits[0].author").starts_with(&pattern));
+        assert!(!path("github.event.commits").starts_with(&pattern));
+        assert!(path("github.event.*.body").starts_with(&path("github.event.issue")));
+        assert!(!path("github.event_name").starts_with(&path("github.event")));
+        assert!(!path("a[0]").starts_with(&path("a.b")));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let cmp = |context, equal, literal| Comparison {
+            context,
+            equal,
+            literal,
+        };
+
+        let vectors: &[(&str, &[Comparison])] = &[
+            (
+                "github.ref == 'refs/heads/main'",
+                &[cmp("github.ref", true, "refs/heads/main")],
+            ),
+            (
+                "'refs/heads/main'==github.ref && github.event_name != 'pull_request'",
+                &[
+                    cmp("github.ref", true, "refs/heads/main"),
+                    cmp("github.event_name", false, "pull_request"),
+                ],
+            ),
+            ("github.ref <= 'refs/heads/main'", &[]),
+            ("startsWith(github.ref, 'refs/tags/') == 'true'", &[]),
+            ("github.ref == github.base_ref", &[]),
+            ("'é' == 'é'", &[]),
+        ];
+
+        for (expr, expected) in vectors {
+            assert_eq!(&comparisons(expr), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_references_context() {
+        let expr = |expr: &str| ExplicitExpr::from_curly(expr).unwrap();
+
+        let title = expr("${{ github.event.pull_request.title }}");
+        assert!(title.references_github_event());
+        assert!(title.references_context("github"));
+        assert!(title.references_context("github.event.pull_request"));
+        assert!(!title.references_context("github.event.pull"));
+        assert!(!title.references_secrets());
+        assert!(!title.references_env());
+
+        let token = expr("${{ secrets.GITHUB_TOKEN || env.FALLBACK_TOKEN }}");
+        assert!(token.references_secrets());
+        assert!(token.references_env());
+        assert!(!token.references_github_event());
+
+        assert!(expr("${{ secrets['MY_SECRET'] }}").references_secrets());
+        assert!(expr("${{ toJSON(secrets) }}").references_secrets());
+        assert!(expr("${{ GitHub.Event.Issue.Body }}").references_github_event());
+        assert!(
+            expr("${{ contains(github.event.head_commit.message, '[skip ci]') }}")
+                .references_github_event()
+        );
+
+        // Not the context itself, or only mentioned in a string.
+        assert!(!expr("${{ github.event_name }}").references_github_event());
+        assert!(!expr("${{ inputs.secrets }}").references_secrets());
+        assert!(!expr("${{ steps.environment.outputs.env }}").references_env());
+        assert!(!expr("${{ format('secrets.{0}', inputs.name) }}").references_secrets());
+        assert!(!expr("${{ matrix.env }}").references_env());
+    }
+
+    #[test]
+    fn test_context_trust() {
+        for context in [
+            "github.event.pull_request.title",
+            "github.event['pull_request']['title']",
+            "github.event.commits[0].message",
+            "github.event.commits.*.message",
+            "GITHUB.HEAD_REF",
+            "github.event",
+            "github",
+        ] {
+            assert_eq!(
+                ContextTrust::of(context),
+                ContextTrust::Untrusted,
+                "{context}"
+            );
+        }
+
+        for context in ["matrix.os", "runner.os", "github.sha", "secrets.TOKEN"] {
+            assert_eq!(
+                ContextTrust::of(context),
+                ContextTrust::Trusted,
+                "{context}"
+            );
+        }
+
+        for context in [
+            "inputs.tag",
+            "steps.x.outputs.y",
+            "env.FOO",
+            "github.event.number",
+        ] {
+            assert_eq!(
+                ContextTrust::of(context),
+                ContextTrust::Unknown,
+                "{context}"
+            );
+        }
     }
 }
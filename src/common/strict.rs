@@ -0,0 +1,256 @@
+//! Unknown key checks, for strict parsing.
+//!
+//! The models capture keys they don't recognize in their `extra` fields,
+//! so strict parsing is ordinary parsing followed by a check that nothing
+//! was captured.
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess};
+
+use super::UnknownKey;
+
+/// Parse `contents` as a top-level model, and then reject it if
+/// `unknown_keys` finds an unknown key in it.
+pub(crate) fn from_yaml_strict<T>(
+    contents: &str,
+    unknown_keys: fn(&T) -> Vec<UnknownKey<'_>>,
+) -> Result<T, crate::Error>
+where
+    T: DeserializeOwned,
+{
+    let model = super::from_yaml(contents)?;
+
+    let first = unknown_keys(&model).into_iter().next();
+    if let Some(unknown) = first {
+        return Err(crate::Error::UnknownKey {
+            location: unknown.location(contents),
+            path: unknown.path,
+        });
+    }
+
+    Ok(model)
+}
+
+/// Returns the path to `key` within the mapping at `path`.
+pub(crate) fn child(path: &str, key: &str) -> String {
+    match path {
+        "" => key.into(),
+        path => format!("{path}.{key}"),
+    }
+}
+
+/// A step in a path, as produced by [`child`] and `[index]` suffixes.
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+fn segments(path: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    for part in path.split('.') {
+        let (key, indices) = part.split_once('[').unwrap_or((part, ""));
+        segments.push(Segment::Key(key));
+        segments.extend(
+            indices
+                .split(['[', ']'])
+                .filter_map(|index| index.parse().ok())
+                .map(Segment::Index),
+        );
+    }
+    segments
+}
+
+/// Returns the 1-based line and column of the key at `path` in
+/// `contents`, if it can be found.
+///
+/// The models don't keep locations, so this re-reads `contents` along
+/// `path`. It's only needed once an unknown key has already been found.
+pub(super) fn locate(contents: &str, path: &str) -> Option<(usize, usize)> {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let segments = segments(path);
+
+    // NOTE: Finding the key fails deserialization on purpose, since the
+    // error is what carries the location.
+    let err = Locate(&segments)
+        .deserialize(serde_yaml::Deserializer::from_str(contents))
+        .err()?;
+    let location = err.location()?;
+    // NOTE: The message is prefixed with the path, e.g. `jobs.test: ...`.
+    (err.to_string().contains(FOUND)).then(|| (location.line(), location.column()))
+}
+
+/// The message that [`Locate`] fails with, once it finds the key.
+const FOUND: &str = "located unknown key";
+
+/// Walks a document along a path, and fails at its last segment.
+struct Locate<'a>(&'a [Segment<'a>]);
+
+impl<'de> DeserializeSeed<'de> for Locate<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, de: D) -> Result<(), D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for Locate<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let [Segment::Key(key), rest @ ..] = self.0 else {
+            return Ok(());
+        };
+
+        while let Some(found) = map.next_key_seed(LocateKey {
+            key,
+            last: rest.is_empty(),
+        })? {
+            match found {
+                true => map.next_value_seed(Locate(rest))?,
+                false => map.next_value::<IgnoredAny>().map(|_| ())?,
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let [Segment::Index(index), rest @ ..] = self.0 else {
+            return Ok(());
+        };
+
+        let mut current = 0;
+        while current < *index && seq.next_element::<IgnoredAny>()?.is_some() {
+            current += 1;
+        }
+        seq.next_element_seed(Locate(rest))?;
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(())
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Deserializes a mapping key, and fails if it's `key` and the last
+/// segment of the path; otherwise, returns whether it's `key`.
+struct LocateKey<'a> {
+    key: &'a str,
+    last: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for LocateKey<'_> {
+    type Value = bool;
+
+    fn deserialize<D>(self, de: D) -> Result<bool, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for LocateKey<'_> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a mapping key")
+    }
+
+    fn visit_str<E>(self, key: &str) -> Result<bool, E>
+    where
+        E: de::Error,
+    {
+        match (key == self.key, self.last) {
+            (true, true) => Err(E::custom(FOUND)),
+            (found, _) => Ok(found),
+        }
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_unit<E>(self) -> Result<bool, E> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{locate, segments, Segment};
+
+    #[test]
+    fn test_segments() {
+        assert_eq!(
+            segments("jobs.test.steps[1].time-out"),
+            [
+                Segment::Key("jobs"),
+                Segment::Key("test"),
+                Segment::Key("steps"),
+                Segment::Index(1),
+                Segment::Key("time-out"),
+            ]
+        );
+        assert_eq!(segments("foo"), [Segment::Key("foo")]);
+    }
+
+    #[test]
+    fn test_locate() {
+        let contents = "on: push\njobs:\n  test:\n    steps:\n      - run: a\n      - run: b\n        time-out: 1\n";
+        assert_eq!(
+            locate(contents, "jobs.test.steps[1].time-out"),
+            Some((7, 9))
+        );
+        assert_eq!(locate(contents, "on"), Some((1, 1)));
+        // Flow mappings and keys that don't exist.
+        assert_eq!(locate("a: {b: 1, c: 2}", "a.c"), Some((1, 11)));
+        assert_eq!(locate(contents, "jobs.test.steps[2].run"), None);
+        assert_eq!(locate(contents, "jobs.nope"), None);
+    }
+}
@@ -0,0 +1,513 @@
+//! Compatibility shims for upgrading across breaking model changes.
+//!
+//! Each submodule is named after the release whose models it describes,
+//! and contains minimal copies of that release's public types along with
+//! conversions into the current ones. Types whose shape hasn't changed
+//! since that release are re-exported as-is.
+//!
+//! These shims exist to ease incremental upgrades, and will be removed
+//! in a future breaking release.
+
+/// Models from the 0.22 release.
+///
+/// Most conversions from these models are lossless, since the current
+/// models are richer; the exception is [`v0_22::Trigger`], whose conversion is
+/// fallible. Where the current models have fields that 0.22 didn't,
+/// conversions use the same defaults as deserialization.
+pub mod v0_22 {
+    use std::fmt;
+
+    use serde::Deserialize;
+    use serde_yaml::Value;
+
+    use crate::common::{
+        expr::{self, BoE, LoE},
+        Env, If, Shell,
+    };
+    use crate::workflow::{event, job};
+
+    // These are unchanged since 0.22.
+    pub use crate::common::{Permissions, Uses};
+
+    // These accept everything that 0.22 did, and more.
+    pub use crate::workflow::event::{
+        BareEvent, BranchFilters, GenericEvent, PathFilters, Push, WorkflowCall, WorkflowDispatch,
+        WorkflowRun,
+    };
+
+    /// A 0.22 workflow trigger.
+    ///
+    /// Converting this into a current [`Trigger`](crate::workflow::Trigger)
+    /// fails if a `pull_request` or `pull_request_target` body lists an
+    /// activity type that the current models don't know (see
+    /// [`PullRequest`]). Otherwise, the conversion is lossless.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum Trigger {
+        BareEvent(BareEvent),
+        BareEvents(Vec<BareEvent>),
+        Events(Box<Events>),
+    }
+
+    impl TryFrom<Trigger> for crate::workflow::Trigger {
+        type Error = UnknownActivityType;
+
+        fn try_from(trigger: Trigger) -> Result<Self, Self::Error> {
+            Ok(match trigger {
+                Trigger::BareEvent(event) => Self::BareEvent(event),
+                Trigger::BareEvents(events) => Self::BareEvents(events),
+                Trigger::Events(events) => Self::Events(Box::new((*events).try_into()?)),
+            })
+        }
+    }
+
+    /// 0.22 workflow event triggers, with bodies.
+    ///
+    /// 0.22 didn't model bodies for `create`, `delete`, `deployment`,
+    /// `deployment_protection_rule`, `deployment_status`, `fork`, `gollum`,
+    /// `page_build`, `public` or `status`, so these are always missing
+    /// after conversion. A `repository_dispatch` body was a
+    /// [`GenericEvent`], and converts into an
+    /// [`event::RepositoryDispatch`] without `event-types`.
+    #[derive(Default, Deserialize)]
+    #[serde(default, rename_all = "snake_case")]
+    pub struct Events {
+        pub branch_protection_rule: event::OptionalBody<GenericEvent>,
+        pub check_run: event::OptionalBody<GenericEvent>,
+        pub check_suite: event::OptionalBody<GenericEvent>,
+        pub discussion: event::OptionalBody<GenericEvent>,
+        pub discussion_comment: event::OptionalBody<GenericEvent>,
+        pub issue_comment: event::OptionalBody<GenericEvent>,
+        pub issues: event::OptionalBody<GenericEvent>,
+        pub label: event::OptionalBody<GenericEvent>,
+        pub merge_group: event::OptionalBody<GenericEvent>,
+        pub milestone: event::OptionalBody<GenericEvent>,
+        pub project: event::OptionalBody<GenericEvent>,
+        pub project_card: event::OptionalBody<GenericEvent>,
+        pub project_column: event::OptionalBody<GenericEvent>,
+        pub pull_request: event::OptionalBody<PullRequest>,
+        pub pull_request_comment: event::OptionalBody<GenericEvent>,
+        pub pull_request_review: event::OptionalBody<GenericEvent>,
+        pub pull_request_review_comment: event::OptionalBody<GenericEvent>,
+        pub pull_request_target: event::OptionalBody<PullRequest>,
+        pub push: event::OptionalBody<Push>,
+        pub registry_package: event::OptionalBody<GenericEvent>,
+        pub release: event::OptionalBody<GenericEvent>,
+        pub repository_dispatch: event::OptionalBody<GenericEvent>,
+        pub schedule: event::OptionalBody<Vec<Cron>>,
+        pub watch: event::OptionalBody<GenericEvent>,
+        pub workflow_call: event::OptionalBody<WorkflowCall>,
+        pub workflow_dispatch: event::OptionalBody<WorkflowDispatch>,
+        pub workflow_run: event::OptionalBody<WorkflowRun>,
+    }
+
+    impl TryFrom<Events> for event::Events {
+        type Error = UnknownActivityType;
+
+        fn try_from(events: Events) -> Result<Self, Self::Error> {
+            fn convert<T, U>(
+                body: event::OptionalBody<T>,
+                f: impl FnOnce(T) -> Result<U, UnknownActivityType>,
+            ) -> Result<event::OptionalBody<U>, UnknownActivityType> {
+                Ok(match body {
+                    event::OptionalBody::Default => event::OptionalBody::Default,
+                    event::OptionalBody::Missing => event::OptionalBody::Missing,
+                    event::OptionalBody::Body(body) => event::OptionalBody::Body(f(body)?),
+                })
+            }
+
+            Ok(Self {
+                branch_protection_rule: events.branch_protection_rule,
+                check_run: events.check_run,
+                check_suite: events.check_suite,
+                discussion: events.discussion,
+                discussion_comment: events.discussion_comment,
+                issue_comment: events.issue_comment,
+                issues: events.issues,
+                label: events.label,
+                merge_group: events.merge_group,
+                milestone: events.milestone,
+                project: events.project,
+                project_card: events.project_card,
+                project_column: events.project_column,
+                pull_request: convert(events.pull_request, TryInto::try_into)?,
+                pull_request_comment: events.pull_request_comment,
+                pull_request_review: events.pull_request_review,
+                pull_request_review_comment: events.pull_request_review_comment,
+                pull_request_target: convert(events.pull_request_target, TryInto::try_into)?,
+                push: events.push,
+                registry_package: events.registry_package,
+                release: events.release,
+                repository_dispatch: convert(events.repository_dispatch, |body| {
+                    Ok(event::RepositoryDispatch {
+                        types: body.types,
+                        event_types: vec![],
+                        extra: body.extra,
+                    })
+                })?,
+                schedule: convert(events.schedule, |crons| {
+                    Ok(crons.into_iter().map(Into::into).collect())
+                })?,
+                watch: events.watch,
+                workflow_call: events.workflow_call,
+                workflow_dispatch: events.workflow_dispatch,
+                workflow_run: events.workflow_run,
+                ..Default::default()
+            })
+        }
+    }
+
+    /// A 0.22 `pull_request` or `pull_request_target` body, whose activity
+    /// `types` were plain strings.
+    ///
+    /// Converting this fails with [`UnknownActivityType`] if any of
+    /// `types` isn't an [`event::PullRequestActivityType`].
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct PullRequest {
+        #[serde(default)]
+        pub types: Vec<String>,
+        #[serde(flatten)]
+        pub branch_filters: Option<BranchFilters>,
+        #[serde(flatten)]
+        pub path_filters: Option<PathFilters>,
+    }
+
+    impl TryFrom<PullRequest> for event::PullRequest {
+        type Error = UnknownActivityType;
+
+        fn try_from(body: PullRequest) -> Result<Self, Self::Error> {
+            let types = body
+                .types
+                .into_iter()
+                .map(|r#type| {
+                    event::PullRequestActivityType::deserialize(Value::String(r#type.clone()))
+                        .map_err(|_| UnknownActivityType(r#type))
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(Self {
+                types,
+                branch_filters: body.branch_filters,
+                path_filters: body.path_filters,
+                extra: Default::default(),
+            })
+        }
+    }
+
+    /// A 0.22 `pull_request` activity type that the current models don't
+    /// know, as produced when converting a [`PullRequest`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct UnknownActivityType(pub String);
+
+    impl fmt::Display for UnknownActivityType {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "unknown pull request activity type `{}`", self.0)
+        }
+    }
+
+    impl std::error::Error for UnknownActivityType {}
+
+    /// A 0.22 `schedule` entry, which wasn't validated.
+    ///
+    /// Converting this is lossless; use [`event::Cron::is_valid_schedule`]
+    /// to check the result.
+    #[derive(Deserialize)]
+    pub struct Cron {
+        pub cron: String,
+    }
+
+    impl From<Cron> for event::Cron {
+        fn from(cron: Cron) -> Self {
+            Self {
+                cron: cron.cron,
+                extra: Default::default(),
+            }
+        }
+    }
+
+    /// A 0.22 workflow step.
+    ///
+    /// Converting this into a [`job::Step`] is lossless: `timeout-minutes`
+    /// was a whole number, and becomes [`job::Minutes`]. See [`StepBody`]
+    /// for injected defaults.
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct Step {
+        pub id: Option<String>,
+        pub r#if: Option<If>,
+        pub name: Option<String>,
+        pub timeout_minutes: Option<LoE<u64>>,
+        #[serde(default)]
+        pub continue_on_error: BoE,
+        #[serde(flatten)]
+        pub body: StepBody,
+    }
+
+    impl From<Step> for job::Step {
+        fn from(step: Step) -> Self {
+            Self {
+                id: step.id,
+                r#if: step.r#if,
+                name: step.name,
+                timeout_minutes: step.timeout_minutes.map(|timeout| match timeout {
+                    LoE::Expr(expr) => LoE::Expr(expr),
+                    LoE::Literal(minutes) => LoE::Literal(minutes.into()),
+                }),
+                continue_on_error: step.continue_on_error,
+                body: step.body.into(),
+                extra: Default::default(),
+            }
+        }
+    }
+
+    /// A 0.22 workflow step body.
+    ///
+    /// 0.22 didn't model `env:` on `uses:` steps; converting a
+    /// [`StepBody::Uses`] gives it an empty `env`. A [`StepBody::Run`]'s
+    /// `shell:` was a plain string, and is parsed into a [`Shell`].
+    #[derive(Deserialize)]
+    #[serde(
+        rename_all = "kebab-case",
+        rename_all_fields = "kebab-case",
+        remote = "Self"
+    )]
+    pub enum StepBody {
+        Uses {
+            #[serde(deserialize_with = "crate::common::step_uses")]
+            uses: Uses,
+            #[serde(default)]
+            with: Env,
+        },
+        Run {
+            #[serde(deserialize_with = "crate::common::bool_is_string")]
+            run: String,
+            working_directory: Option<String>,
+            shell: Option<String>,
+            #[serde(default)]
+            env: LoE<Env>,
+        },
+    }
+
+    impl crate::common::StepBodyVariants for StepBody {
+        fn deserialize_variant<'de, D>(de: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            Self::deserialize(de)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for StepBody {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            crate::common::step_body(deserializer)
+        }
+    }
+
+    impl From<StepBody> for job::StepBody {
+        fn from(body: StepBody) -> Self {
+            match body {
+                StepBody::Uses { uses, with } => Self::Uses {
+                    uses,
+                    with,
+                    env: Default::default(),
+                },
+                StepBody::Run {
+                    run,
+                    working_directory,
+                    shell,
+                    env,
+                } => Self::Run {
+                    run,
+                    working_directory,
+                    shell: shell.map(|shell| {
+                        let Ok(shell) = shell.parse::<Shell>();
+                        shell
+                    }),
+                    env,
+                },
+            }
+        }
+    }
+
+    /// A 0.22 concurrency setting, whose group was a plain string.
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case", rename_all_fields = "kebab-case", untagged)]
+    pub enum Concurrency {
+        Bare(String),
+        Rich {
+            group: String,
+            #[serde(default)]
+            cancel_in_progress: BoE,
+        },
+    }
+
+    impl From<Concurrency> for crate::workflow::Concurrency {
+        fn from(concurrency: Concurrency) -> Self {
+            match concurrency {
+                Concurrency::Bare(group) => Self::Bare(expr::string_loe(group)),
+                Concurrency::Rich {
+                    group,
+                    cancel_in_progress,
+                } => Self::Rich {
+                    group: expr::string_loe(group),
+                    cancel_in_progress,
+                },
+            }
+        }
+    }
+
+    /// A 0.22 deployment environment, whose name and URL were plain strings.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum DeploymentEnvironment {
+        Name(String),
+        NameURL { name: String, url: Option<String> },
+    }
+
+    impl From<DeploymentEnvironment> for job::DeploymentEnvironment {
+        fn from(environment: DeploymentEnvironment) -> Self {
+            match environment {
+                DeploymentEnvironment::Name(name) => Self::Name(expr::string_loe(name)),
+                DeploymentEnvironment::NameURL { name, url } => Self::NameURL {
+                    name: expr::string_loe(name),
+                    url: url.map(expr::string_loe),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        common::{expr::LoE, Shell},
+        workflow::{
+            event::{BranchFilters, OptionalBody, PullRequestActivityType},
+            job, Concurrency, Trigger,
+        },
+    };
+
+    use super::v0_22;
+
+    #[test]
+    fn test_v0_22_step() {
+        let old = serde_yaml::from_str::<v0_22::Step>(
+            "id: checkout\nuses: actions/checkout@v4\nwith:\n  persist-credentials: false",
+        )
+        .unwrap();
+        let step = job::Step::from(old);
+        assert_eq!(step.id.as_deref(), Some("checkout"));
+        let job::StepBody::Uses { uses, with, env } = step.body else {
+            panic!("expected uses step");
+        };
+        assert_eq!(uses.to_string(), "actions/checkout@v4");
+        assert_eq!(with.len(), 1);
+        assert_eq!(env, LoE::default());
+
+        let old = serde_yaml::from_str::<v0_22::Step>("run: true\nshell: bash").unwrap();
+        let job::StepBody::Run { run, shell, .. } = job::Step::from(old).body else {
+            panic!("expected run step");
+        };
+        assert_eq!(run, "true");
+        assert_eq!(shell, Some(Shell::Bash));
+    }
+
+    #[test]
+    fn test_v0_22_trigger() {
+        let old = serde_yaml::from_str::<v0_22::Trigger>(
+            "
+pull_request:
+  types: [opened, synchronize]
+  branches: [main]
+repository_dispatch:
+  types: deploy
+schedule:
+  - cron: '99 * * * *'
+",
+        )
+        .unwrap();
+        let Trigger::Events(events) = Trigger::try_from(old).unwrap() else {
+            panic!("expected events");
+        };
+        let OptionalBody::Body(pull_request) = &events.pull_request else {
+            panic!("expected pull_request body");
+        };
+        assert_eq!(
+            pull_request.types,
+            [
+                PullRequestActivityType::Opened,
+                PullRequestActivityType::Synchronize
+            ]
+        );
+        assert!(matches!(
+            pull_request.branch_filters,
+            Some(BranchFilters::Branches(_))
+        ));
+        let OptionalBody::Body(dispatch) = &events.repository_dispatch else {
+            panic!("expected repository_dispatch body");
+        };
+        assert_eq!(dispatch.all_event_types().collect::<Vec<_>>(), ["deploy"]);
+        // 0.22 didn't validate schedules, so neither does the conversion.
+        let OptionalBody::Body(schedule) = &events.schedule else {
+            panic!("expected schedule body");
+        };
+        assert!(!schedule[0].is_valid_schedule());
+
+        let old = serde_yaml::from_str::<v0_22::Trigger>("[push, fork]").unwrap();
+        assert!(matches!(
+            Trigger::try_from(old).unwrap(),
+            Trigger::BareEvents(events) if events.len() == 2
+        ));
+
+        // 0.22 accepted any activity type.
+        let old =
+            serde_yaml::from_str::<v0_22::Trigger>("pull_request:\n  types: [opend]").unwrap();
+        let Err(err) = Trigger::try_from(old) else {
+            panic!("expected unknown activity type to fail");
+        };
+        assert_eq!(err, v0_22::UnknownActivityType("opend".into()));
+    }
+
+    #[test]
+    fn test_v0_22_concurrency() {
+        let old = serde_yaml::from_str::<v0_22::Concurrency>(
+            "group: ${{ github.ref }}\ncancel-in-progress: true",
+        )
+        .unwrap();
+        let Concurrency::Rich {
+            group: LoE::Expr(group),
+            cancel_in_progress: LoE::Literal(true),
+        } = Concurrency::from(old)
+        else {
+            panic!("expected rich concurrency with expression group");
+        };
+        assert_eq!(group.as_bare(), "github.ref");
+
+        let old = serde_yaml::from_str::<v0_22::Concurrency>("ci-${{ github.ref }}").unwrap();
+        let concurrency = Concurrency::from(old);
+        assert!(matches!(concurrency, Concurrency::Bare(LoE::Literal(_))));
+        assert_eq!(concurrency.group(), "ci-${{ github.ref }}");
+    }
+
+    #[test]
+    fn test_v0_22_deployment_environment() {
+        let old = serde_yaml::from_str::<v0_22::DeploymentEnvironment>(
+            "name: production\nurl: ${{ steps.deploy.outputs.url }}",
+        )
+        .unwrap();
+        let environment = job::DeploymentEnvironment::from(old);
+        assert!(matches!(
+            &environment,
+            job::DeploymentEnvironment::NameURL {
+                name: LoE::Literal(_),
+                url: Some(LoE::Expr(_)),
+            }
+        ));
+        assert_eq!(environment.name(), "production");
+        assert_eq!(environment.url(), Some("${{ steps.deploy.outputs.url }}"));
+    }
+}
@@ -4,11 +4,19 @@
 //! * [Configuration options for the `dependabot.yml` file](https://docs.github.com/en/code-security/dependabot/dependabot-version-updates/configuration-options-for-the-dependabot.yml-file)
 //! * [JSON Schema for Dependabot v2](https://json.schemastore.org/dependabot-2.0.json)
 
+use std::{path::Path, str::FromStr};
+
 use indexmap::{IndexMap, IndexSet};
 use serde::Deserialize;
+use serde_yaml::Value;
+
+use crate::common::{
+    diagnostic::{self, Diagnostic},
+    UnknownKey,
+};
 
 /// A `dependabot.yml` configuration file.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct Dependabot {
     /// Invariant: `2`
@@ -18,10 +26,138 @@ pub struct Dependabot {
     #[serde(default)]
     pub registries: IndexMap<String, Registry>,
     pub updates: Vec<Update>,
+
+    /// Any other top-level keys.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl FromStr for Dependabot {
+    type Err = crate::Error;
+
+    /// Parse a `dependabot.yml` configuration from a YAML string.
+    ///
+    /// ```
+    /// use github_actions_models::dependabot::v2::Dependabot;
+    ///
+    /// let dependabot: Dependabot = "
+    /// version: 2
+    /// updates:
+    ///   - package-ecosystem: cargo
+    ///     directory: /
+    ///     schedule:
+    ///       interval: weekly
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// assert_eq!(dependabot.updates.len(), 1);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::common::from_yaml(s)
+    }
+}
+
+impl Dependabot {
+    /// Parse a `dependabot.yml` configuration from raw YAML bytes.
+    ///
+    /// The bytes are decoded as UTF-8 (with or without a BOM), or as
+    /// UTF-16LE/BE if they begin with the corresponding BOM. Invalid
+    /// sequences produce [`crate::Error::Encoding`] rather than being replaced.
+    pub fn from_slice(contents: &[u8]) -> Result<Self, crate::Error> {
+        crate::common::from_yaml_slice(contents)
+    }
+
+    /// Parse a `dependabot.yml` configuration from a YAML string, like
+    /// [`FromStr`], but also reject keys that Dependabot doesn't accept.
+    ///
+    /// The first of [`Self::unknown_keys`], if any, produces a
+    /// [`crate::Error::UnknownKey`] with its path and location.
+    pub fn from_str_strict(s: &str) -> Result<Self, crate::Error> {
+        crate::common::strict::from_yaml_strict(s, Self::unknown_keys)
+    }
+
+    /// Load a `dependabot.yml` configuration from the file at `path`.
+    ///
+    /// The file's contents are decoded as with [`Self::from_slice`].
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, crate::LoadError> {
+        crate::common::from_path(path.as_ref())
+    }
+
+    /// Returns every key that this configuration's models didn't
+    /// recognize, such as `directories` in place of `directory`.
+    ///
+    /// Unlike [`Self::from_str_strict`], this doesn't reject the
+    /// configuration, and returns all such keys rather than the first.
+    /// Keys are collected from the top level, and each update entry and
+    /// its `schedule:`, in that order.
+    pub fn unknown_keys(&self) -> Vec<UnknownKey<'_>> {
+        use crate::common::{strict::child, unknown_keys};
+
+        let mut unknown: Vec<_> = unknown_keys("", &self.extra).collect();
+        for (index, update) in self.updates.iter().enumerate() {
+            let path = format!("updates[{index}]");
+            unknown.extend(unknown_keys(&path, &update.extra));
+            unknown.extend(unknown_keys(
+                &child(&path, "schedule"),
+                &update.schedule.extra,
+            ));
+        }
+
+        unknown
+    }
+
+    /// Check that each update's `registries` are configured under the
+    /// top-level `registries:`, returning an
+    /// [`UNKNOWN_REGISTRY`](diagnostic::UNKNOWN_REGISTRY) diagnostic for
+    /// each name that isn't.
+    ///
+    /// Diagnostics are in update declaration order, and then `registries`
+    /// order.
+    pub fn validate_registries(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        for (index, update) in self.updates.iter().enumerate() {
+            for (registry_index, name) in update.registries.iter().enumerate() {
+                if name == "*" || self.registries.contains_key(name) {
+                    continue;
+                }
+
+                diagnostics.push(Diagnostic::new(
+                    diagnostic::UNKNOWN_REGISTRY,
+                    [
+                        "updates".into(),
+                        index.to_string(),
+                        "registries".into(),
+                        registry_index.to_string(),
+                    ],
+                    format!("update uses unknown registry `{name}`"),
+                ));
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Returns every update entry for the given `ecosystem`, in
+    /// declaration order.
+    pub fn updates_for_ecosystem(&self, ecosystem: &PackageEcosystem) -> Vec<&Update> {
+        self.updates
+            .iter()
+            .filter(|update| &update.package_ecosystem == ecosystem)
+            .collect()
+    }
+
+    /// Returns the ecosystem of each update entry, in declaration order.
+    ///
+    /// Ecosystems with multiple update entries are yielded once per entry.
+    pub fn ecosystems(&self) -> impl Iterator<Item = &PackageEcosystem> {
+        self.updates.iter().map(|update| &update.package_ecosystem)
+    }
 }
 
 /// Different registries known to Dependabot.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case", tag = "type")]
 pub enum Registry {
     ComposerRepository {
@@ -89,7 +225,7 @@ pub enum Registry {
 }
 
 /// A single `update` directive.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct Update {
     #[serde(default)]
@@ -117,7 +253,7 @@ pub struct Update {
     #[serde(default = "default_open_pull_requests_limit")]
     pub open_pull_requests_limit: u64,
     pub package_ecosystem: PackageEcosystem,
-    // TODO: pull-request-branch-name
+    pub pull_request_branch_name: Option<PullRequestBranchName>,
     #[serde(default)]
     pub rebase_strategy: RebaseStrategy,
     #[serde(default, deserialize_with = "crate::common::scalar_or_vector")]
@@ -129,6 +265,38 @@ pub struct Update {
     #[serde(default)]
     pub vendor: bool,
     pub versioning_strategy: Option<VersioningStrategy>,
+
+    /// Any other keys in this update.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl Update {
+    /// Resolves this update's `registries` against the registries
+    /// configured in `dependabot`, in the order they're listed.
+    ///
+    /// The special name `*` resolves to every configured registry.
+    /// Names that don't correspond to a configured registry are skipped;
+    /// see [`Update::has_unknown_registries`] and
+    /// [`Dependabot::validate_registries`].
+    pub fn resolve_registries<'a>(&self, dependabot: &'a Dependabot) -> Vec<&'a Registry> {
+        if self.registries.iter().any(|name| name == "*") {
+            return dependabot.registries.values().collect();
+        }
+
+        self.registries
+            .iter()
+            .filter_map(|name| dependabot.registries.get(name))
+            .collect()
+    }
+
+    /// Returns whether this update lists any registry names that aren't
+    /// configured in `dependabot`.
+    pub fn has_unknown_registries(&self, dependabot: &Dependabot) -> bool {
+        self.registries
+            .iter()
+            .any(|name| name != "*" && !dependabot.registries.contains_key(name))
+    }
 }
 
 #[inline]
@@ -142,8 +310,26 @@ fn default_open_pull_requests_limit() -> u64 {
     5
 }
 
+/// Branch name settings for Dependabot pull requests.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct PullRequestBranchName {
+    /// The separator to use in generated branch names, if set.
+    ///
+    /// See [`PullRequestBranchName::separator`] for the effective separator.
+    pub separator: Option<String>,
+}
+
+impl PullRequestBranchName {
+    /// Returns the separator to use in generated branch names, which
+    /// defaults to `/`.
+    pub fn separator(&self) -> &str {
+        self.separator.as_deref().unwrap_or("/")
+    }
+}
+
 /// Allow rules for Dependabot updates.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct Allow {
     pub dependency_name: Option<String>,
@@ -151,7 +337,7 @@ pub struct Allow {
 }
 
 /// Dependency types in `allow` rules.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum DependencyType {
     Direct,
@@ -162,7 +348,7 @@ pub enum DependencyType {
 }
 
 /// Commit message settings for Dependabot updates.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct CommitMessage {
     pub prefix: Option<String>,
@@ -172,7 +358,7 @@ pub struct CommitMessage {
 }
 
 /// Group settings for batched updates.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct Group {
     /// This can only be [`DependencyType::Development`] or
@@ -187,7 +373,7 @@ pub struct Group {
 }
 
 /// Update types for grouping.
-#[derive(Deserialize, Debug, Hash, Eq, PartialEq)]
+#[derive(Clone, Deserialize, Debug, Hash, Eq, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum UpdateType {
     Major,
@@ -196,7 +382,7 @@ pub enum UpdateType {
 }
 
 /// Dependency ignore settings for updates.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct Ignore {
     pub dependency_name: Option<String>,
@@ -208,8 +394,155 @@ pub struct Ignore {
     pub versions: IndexSet<String>,
 }
 
+impl Ignore {
+    /// Returns whether this rule's `dependency-name` matches `dep_name`.
+    ///
+    /// Like Dependabot, `*` in the name matches any sequence of characters,
+    /// and names are compared ASCII case-insensitively. A rule without a
+    /// `dependency-name` matches every dependency.
+    pub fn matches_dependency(&self, dep_name: &str) -> bool {
+        match &self.dependency_name {
+            Some(pattern) => glob_matches(pattern, dep_name),
+            None => true,
+        }
+    }
+
+    /// Returns whether any of this rule's `versions` matches `version`.
+    ///
+    /// Dependabot interprets these ranges with each ecosystem's own
+    /// syntax, so this only supports the common forms:
+    ///
+    /// * exact versions, e.g. `1.2.3`;
+    /// * wildcards, e.g. `4.x` or `4.*`;
+    /// * comparisons, e.g. `>= 1.0` or `~> 2.1`, with multiple
+    ///   comparisons separated by commas (e.g. `>= 1.0, < 2.0`).
+    ///
+    /// Versions are compared component by component, numerically where
+    /// possible. A rule without `versions` matches every version.
+    pub fn matches_version(&self, version: &str) -> bool {
+        self.versions.is_empty()
+            || self
+                .versions
+                .iter()
+                .any(|range| version_range_matches(range, version))
+    }
+
+    /// Returns whether this rule applies to version `version` of `dep_name`.
+    ///
+    /// Note that this doesn't consider `update-types`, which depend on
+    /// the update being proposed rather than just the dependency.
+    pub fn applies(&self, dep_name: &str, version: &str) -> bool {
+        self.matches_dependency(dep_name) && self.matches_version(version)
+    }
+}
+
+/// Match `name` against a glob `pattern` where `*` matches any sequence of
+/// characters, ASCII case-insensitively.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let name = name.to_ascii_lowercase();
+
+    let mut parts = pattern.split('*');
+    // There's always a first part, even if it's empty.
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` at all, so the pattern must match exactly.
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+/// Returns whether `version` satisfies every comma-separated constraint
+/// in `range`.
+fn version_range_matches(range: &str, version: &str) -> bool {
+    range
+        .split(',')
+        .all(|constraint| version_constraint_matches(constraint.trim(), version.trim()))
+}
+
+fn version_constraint_matches(constraint: &str, version: &str) -> bool {
+    use std::cmp::Ordering;
+
+    let operators = [">=", "<=", "!=", "~>", ">", "<", "="];
+    let Some((op, wanted)) = operators.iter().find_map(|op| {
+        constraint
+            .strip_prefix(op)
+            .map(|wanted| (*op, wanted.trim()))
+    }) else {
+        // A bare version, possibly ending in a wildcard component.
+        let wildcard = constraint
+            .strip_suffix(".x")
+            .or_else(|| constraint.strip_suffix(".*"));
+        return match wildcard {
+            Some(prefix) => compare_versions(prefix, version, true) == Ordering::Equal,
+            None if constraint == "*" => true,
+            None => compare_versions(constraint, version, false) == Ordering::Equal,
+        };
+    };
+
+    let ordering = compare_versions(version, wanted, false);
+    match op {
+        ">=" => ordering.is_ge(),
+        "<=" => ordering.is_le(),
+        "!=" => ordering.is_ne(),
+        ">" => ordering.is_gt(),
+        "<" => ordering.is_lt(),
+        "=" => ordering.is_eq(),
+        // `~> 2.1` means `>= 2.1` with the same leading components, i.e.
+        // `< 3.0`.
+        _ => {
+            let leading = wanted
+                .rsplit_once('.')
+                .map_or(wanted, |(leading, _)| leading);
+            ordering.is_ge() && compare_versions(leading, version, true) == Ordering::Equal
+        }
+    }
+}
+
+/// Compare two dotted versions component by component, numerically where
+/// both components are numbers. Missing components are treated as `0`.
+///
+/// If `prefix` is set, only the components in `a` are compared, so `4`
+/// is equal to `4.1.2`.
+fn compare_versions(a: &str, b: &str, prefix: bool) -> std::cmp::Ordering {
+    let a = a.trim_start_matches('v').split('.').collect::<Vec<_>>();
+    let b = b.trim_start_matches('v').split('.').collect::<Vec<_>>();
+    let len = if prefix {
+        a.len()
+    } else {
+        a.len().max(b.len())
+    };
+
+    for idx in 0..len {
+        let a = a.get(idx).copied().unwrap_or("0");
+        let b = b.get(idx).copied().unwrap_or("0");
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+
+    std::cmp::Ordering::Equal
+}
+
 /// An "allow"/"deny" toggle.
-#[derive(Deserialize, Debug, Default)]
+#[derive(Clone, Deserialize, Debug, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum AllowDeny {
     Allow,
@@ -218,7 +551,7 @@ pub enum AllowDeny {
 }
 
 /// Supported packaging ecosystems.
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum PackageEcosystem {
     Bundler,
@@ -240,8 +573,53 @@ pub enum PackageEcosystem {
     Terraform,
 }
 
+impl PackageEcosystem {
+    /// Returns this ecosystem's name as written in `dependabot.yml`,
+    /// e.g. `github-actions`.
+    pub fn canonical_name(&self) -> &'static str {
+        match self {
+            PackageEcosystem::Bundler => "bundler",
+            PackageEcosystem::Cargo => "cargo",
+            PackageEcosystem::Composer => "composer",
+            PackageEcosystem::Docker => "docker",
+            PackageEcosystem::Elm => "elm",
+            PackageEcosystem::Gitsubmodule => "gitsubmodule",
+            PackageEcosystem::GithubActions => "github-actions",
+            PackageEcosystem::Gomod => "gomod",
+            PackageEcosystem::Gradle => "gradle",
+            PackageEcosystem::Maven => "maven",
+            PackageEcosystem::Mix => "mix",
+            PackageEcosystem::Npm => "npm",
+            PackageEcosystem::Nuget => "nuget",
+            PackageEcosystem::Pip => "pip",
+            PackageEcosystem::Pub => "pub",
+            PackageEcosystem::Swift => "swift",
+            PackageEcosystem::Terraform => "terraform",
+        }
+    }
+
+    /// Returns whether this ecosystem updates a programming language's
+    /// packages, rather than references to images, actions, submodules,
+    /// or modules.
+    pub fn is_language_ecosystem(&self) -> bool {
+        !matches!(
+            self,
+            PackageEcosystem::Docker
+                | PackageEcosystem::GithubActions
+                | PackageEcosystem::Gitsubmodule
+                | PackageEcosystem::Terraform
+        )
+    }
+}
+
+impl std::fmt::Display for PackageEcosystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.canonical_name())
+    }
+}
+
 /// Rebase strategies for Dependabot updates.
-#[derive(Deserialize, Debug, Default, PartialEq)]
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum RebaseStrategy {
     #[default]
@@ -250,17 +628,21 @@ pub enum RebaseStrategy {
 }
 
 /// Scheduling settings for Dependabot updates.
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct Schedule {
     pub interval: Interval,
     pub day: Option<Day>,
     pub time: Option<String>,
     pub timezone: Option<String>,
+
+    /// Any other keys in this schedule.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// Schedule intervals.
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum Interval {
     Daily,
@@ -269,7 +651,7 @@ pub enum Interval {
 }
 
 /// Days of the week.
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum Day {
     Monday,
@@ -282,7 +664,7 @@ pub enum Day {
 }
 
 /// Versioning strategies.
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum VersioningStrategy {
     Auto,
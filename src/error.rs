@@ -0,0 +1,186 @@
+//! Errors produced while loading models.
+
+use std::{fmt, io, path::PathBuf};
+
+/// An error produced while loading a workflow, action, or other model.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input is not valid YAML, or doesn't match the model's shape.
+    ///
+    /// The underlying error's message includes its location when
+    /// available. It may also start with part of the path to the
+    /// offending field, but that path stops at any value that the models
+    /// buffer to pick a variant, e.g. `jobs: ... at line 3 column 3` for an
+    /// error within a job. Only [`Error::Field`] carries the full path.
+    Yaml(serde_yaml::Error),
+    /// Like [`Error::Yaml`], but with the path to the offending field,
+    /// e.g. `jobs.test.steps[3].with`.
+    ///
+    /// This is only produced with the `error-paths` feature, in place of
+    /// [`Error::Yaml`] for errors below the document's root.
+    Field {
+        /// The path to the offending field, using job IDs, step indices,
+        /// and so on.
+        path: String,
+        /// The underlying YAML error.
+        source: serde_yaml::Error,
+    },
+    /// The input bytes aren't validly encoded.
+    ///
+    /// Inputs are decoded as UTF-8 unless they begin with a UTF-16 byte
+    /// order mark.
+    Encoding {
+        /// The detected encoding, e.g. `UTF-8` or `UTF-16LE`.
+        encoding: &'static str,
+        /// The byte offset of the first invalid sequence in the input.
+        offset: usize,
+    },
+    /// The input contains a key that isn't valid at its position, as
+    /// rejected by strict parsing, e.g.
+    /// [`Workflow::from_str_strict`](crate::workflow::Workflow::from_str_strict).
+    UnknownKey {
+        /// The path to the unknown key, e.g. `jobs.test.timeout_minutes`.
+        path: String,
+        /// The 1-based line and column of the unknown key, if it could be
+        /// found in the input.
+        location: Option<(usize, usize)>,
+    },
+    /// The target of an incremental re-parse, like a job or step,
+    /// doesn't exist.
+    MissingTarget {
+        /// The ID of the targeted job.
+        job: String,
+        /// The index of the targeted step, if a step was targeted.
+        ///
+        /// If `job` exists, it doesn't have this step. Reusable workflow
+        /// call jobs have no steps at all.
+        step: Option<usize>,
+    },
+}
+
+impl Error {
+    /// Returns the 1-based line and column where the error occurred, if
+    /// it's tied to a location in the input.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::Yaml(e) | Error::Field { source: e, .. } => {
+                e.location().map(|loc| (loc.line(), loc.column()))
+            }
+            Error::UnknownKey { location, .. } => *location,
+            Error::Encoding { .. } | Error::MissingTarget { .. } => None,
+        }
+    }
+
+    /// Returns the path to the offending field, e.g. `jobs.test.steps[3]`,
+    /// if known.
+    ///
+    /// Unknown keys always have a path; otherwise, paths are only tracked
+    /// with the `error-paths` feature.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Error::Field { path, .. } | Error::UnknownKey { path, .. } => Some(path),
+            Error::Yaml(_) | Error::Encoding { .. } | Error::MissingTarget { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // NOTE: The YAML error's own message often names part of the
+            // path already, so we leave it as-is.
+            Error::Yaml(e) | Error::Field { source: e, .. } => write!(f, "{e}"),
+            Error::Encoding { encoding, offset } => {
+                write!(f, "invalid {encoding} sequence at byte offset {offset}")
+            }
+            Error::UnknownKey { path, location } => {
+                write!(f, "unknown key `{path}`")?;
+                match location {
+                    Some((line, column)) => write!(f, " at line {line} column {column}"),
+                    None => Ok(()),
+                }
+            }
+            Error::MissingTarget { job, step: None } => write!(f, "no job `{job}`"),
+            Error::MissingTarget {
+                job,
+                step: Some(step),
+            } => write!(f, "job `{job}` has no step {step}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Yaml(e) | Error::Field { source: e, .. } => Some(e),
+            Error::Encoding { .. } | Error::UnknownKey { .. } | Error::MissingTarget { .. } => None,
+        }
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(value: serde_yaml::Error) -> Self {
+        Error::Yaml(value)
+    }
+}
+
+/// An error produced while loading a model from a file, e.g. with
+/// [`Workflow::from_path`](crate::workflow::Workflow::from_path).
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file couldn't be read.
+    Io {
+        /// The path to the file.
+        path: PathBuf,
+        /// The underlying I/O error.
+        source: io::Error,
+    },
+    /// The file's contents couldn't be loaded as the model.
+    Parse {
+        /// The path to the file.
+        path: PathBuf,
+        /// The underlying load error.
+        source: Error,
+    },
+}
+
+impl LoadError {
+    /// Returns the path to the file that couldn't be loaded.
+    pub fn path(&self) -> &std::path::Path {
+        match self {
+            LoadError::Io { path, .. } | LoadError::Parse { path, .. } => path,
+        }
+    }
+
+    /// Returns the 1-based line and column within the file where the
+    /// error occurred, if it's tied to one.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            LoadError::Io { .. } => None,
+            LoadError::Parse { source, .. } => source.location(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, source } => {
+                write!(f, "couldn't read {}: {source}", path.display())
+            }
+            LoadError::Parse { path, source } => {
+                write!(f, "couldn't load {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            LoadError::Parse { source, .. } => Some(source),
+        }
+    }
+}
@@ -1,10 +1,29 @@
 //! High-quality data models for GitHub Actions and associated machinery.
+//!
+//! ## Unknown keys
+//!
+//! Models of user-written mappings keep the keys they don't recognize in
+//! an `extra` field, rather than dropping them. These are usually
+//! misspellings (like `time-out` for `timeout-minutes`), or keys that
+//! GitHub added after the model was written.
+//!
+//! Each top-level model's `unknown_keys` method collects these keys
+//! along with their paths, and its `from_str_strict` constructor rejects
+//! documents that have any.
 
 #![deny(rustdoc::broken_intra_doc_links)]
 #![allow(clippy::redundant_field_names)]
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "action")]
 pub mod action;
 pub mod common;
+#[cfg(feature = "workflow")]
+pub mod compat;
+#[cfg(feature = "dependabot")]
 pub mod dependabot;
+mod error;
+#[cfg(feature = "workflow")]
 pub mod workflow;
+
+pub use error::{Error, LoadError};
@@ -1,7 +1,12 @@
 //! Workflow events.
 
+use std::{collections::BTreeSet, fmt::Display};
+
 use indexmap::IndexMap;
-use serde::{Deserialize, Serialize};
+use serde::{de, ser::SerializeMap, Deserialize, Deserializer, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use crate::common::{strict::child, EnvValue, UnknownKey};
 
 /// "Bare" workflow event triggers.
 ///
@@ -11,7 +16,7 @@ use serde::{Deserialize, Serialize};
 /// ```yaml
 /// on: push
 /// ```
-#[derive(Deserialize, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum BareEvent {
     BranchProtectionRule,
@@ -20,6 +25,7 @@ pub enum BareEvent {
     Create,
     Delete,
     Deployment,
+    DeploymentProtectionRule,
     DeploymentStatus,
     Discussion,
     DiscussionComment,
@@ -52,30 +58,179 @@ pub enum BareEvent {
     WorkflowRun,
 }
 
+impl BareEvent {
+    /// Returns this event's name, as it appears in a workflow
+    /// (e.g. `pull_request`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BareEvent::BranchProtectionRule => "branch_protection_rule",
+            BareEvent::CheckRun => "check_run",
+            BareEvent::CheckSuite => "check_suite",
+            BareEvent::Create => "create",
+            BareEvent::Delete => "delete",
+            BareEvent::Deployment => "deployment",
+            BareEvent::DeploymentProtectionRule => "deployment_protection_rule",
+            BareEvent::DeploymentStatus => "deployment_status",
+            BareEvent::Discussion => "discussion",
+            BareEvent::DiscussionComment => "discussion_comment",
+            BareEvent::Fork => "fork",
+            BareEvent::Gollum => "gollum",
+            BareEvent::IssueComment => "issue_comment",
+            BareEvent::Issues => "issues",
+            BareEvent::Label => "label",
+            BareEvent::MergeGroup => "merge_group",
+            BareEvent::Milestone => "milestone",
+            BareEvent::PageBuild => "page_build",
+            BareEvent::Project => "project",
+            BareEvent::ProjectCard => "project_card",
+            BareEvent::ProjectColumn => "project_column",
+            BareEvent::Public => "public",
+            BareEvent::PullRequest => "pull_request",
+            BareEvent::PullRequestComment => "pull_request_comment",
+            BareEvent::PullRequestReview => "pull_request_review",
+            BareEvent::PullRequestReviewComment => "pull_request_review_comment",
+            BareEvent::PullRequestTarget => "pull_request_target",
+            BareEvent::Push => "push",
+            BareEvent::RegistryPackage => "registry_package",
+            BareEvent::Release => "release",
+            BareEvent::RepositoryDispatch => "repository_dispatch",
+            BareEvent::Status => "status",
+            BareEvent::Watch => "watch",
+            BareEvent::WorkflowCall => "workflow_call",
+            BareEvent::WorkflowDispatch => "workflow_dispatch",
+            BareEvent::WorkflowRun => "workflow_run",
+        }
+    }
+}
+
+impl Display for BareEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The kind of a workflow event trigger, regardless of whether it's
+/// specified in bare or rich form.
+///
+/// Unlike [`BareEvent`], this includes [`EventKind::Schedule`], which
+/// can only appear in the rich form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EventKind {
+    BranchProtectionRule,
+    CheckRun,
+    CheckSuite,
+    Create,
+    Delete,
+    Deployment,
+    DeploymentProtectionRule,
+    DeploymentStatus,
+    Discussion,
+    DiscussionComment,
+    Fork,
+    Gollum,
+    IssueComment,
+    Issues,
+    Label,
+    MergeGroup,
+    Milestone,
+    PageBuild,
+    Project,
+    ProjectCard,
+    ProjectColumn,
+    Public,
+    PullRequest,
+    PullRequestComment,
+    PullRequestReview,
+    PullRequestReviewComment,
+    PullRequestTarget,
+    Push,
+    RegistryPackage,
+    Release,
+    RepositoryDispatch,
+    Schedule,
+    Status,
+    Watch,
+    WorkflowCall,
+    WorkflowDispatch,
+    WorkflowRun,
+}
+
+impl From<BareEvent> for EventKind {
+    fn from(event: BareEvent) -> Self {
+        match event {
+            BareEvent::BranchProtectionRule => Self::BranchProtectionRule,
+            BareEvent::CheckRun => Self::CheckRun,
+            BareEvent::CheckSuite => Self::CheckSuite,
+            BareEvent::Create => Self::Create,
+            BareEvent::Delete => Self::Delete,
+            BareEvent::Deployment => Self::Deployment,
+            BareEvent::DeploymentProtectionRule => Self::DeploymentProtectionRule,
+            BareEvent::DeploymentStatus => Self::DeploymentStatus,
+            BareEvent::Discussion => Self::Discussion,
+            BareEvent::DiscussionComment => Self::DiscussionComment,
+            BareEvent::Fork => Self::Fork,
+            BareEvent::Gollum => Self::Gollum,
+            BareEvent::IssueComment => Self::IssueComment,
+            BareEvent::Issues => Self::Issues,
+            BareEvent::Label => Self::Label,
+            BareEvent::MergeGroup => Self::MergeGroup,
+            BareEvent::Milestone => Self::Milestone,
+            BareEvent::PageBuild => Self::PageBuild,
+            BareEvent::Project => Self::Project,
+            BareEvent::ProjectCard => Self::ProjectCard,
+            BareEvent::ProjectColumn => Self::ProjectColumn,
+            BareEvent::Public => Self::Public,
+            BareEvent::PullRequest => Self::PullRequest,
+            BareEvent::PullRequestComment => Self::PullRequestComment,
+            BareEvent::PullRequestReview => Self::PullRequestReview,
+            BareEvent::PullRequestReviewComment => Self::PullRequestReviewComment,
+            BareEvent::PullRequestTarget => Self::PullRequestTarget,
+            BareEvent::Push => Self::Push,
+            BareEvent::RegistryPackage => Self::RegistryPackage,
+            BareEvent::Release => Self::Release,
+            BareEvent::RepositoryDispatch => Self::RepositoryDispatch,
+            BareEvent::Status => Self::Status,
+            BareEvent::Watch => Self::Watch,
+            BareEvent::WorkflowCall => Self::WorkflowCall,
+            BareEvent::WorkflowDispatch => Self::WorkflowDispatch,
+            BareEvent::WorkflowRun => Self::WorkflowRun,
+        }
+    }
+}
+
 /// Workflow event triggers, with bodies.
 ///
 /// Like [`BareEvent`], but with per-event properties.
-#[derive(Default, Deserialize, Serialize)]
-#[serde(default, rename_all = "snake_case")]
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, rename_all = "snake_case", remote = "Self")]
 pub struct Events {
     pub branch_protection_rule: OptionalBody<GenericEvent>,
     pub check_run: OptionalBody<GenericEvent>,
     pub check_suite: OptionalBody<GenericEvent>,
-    // NOTE: `create` and `delete` are omitted, since they are always bare.
-    // NOTE: `deployment` and `deployment_status` are omitted, since they are always bare.
+    // NOTE: `create`, `delete`, `deployment`, `deployment_status`, `fork`, `gollum`,
+    // `page_build`, `public`, and `status` have no configuration, but can still
+    // appear in the mapping form with an empty body (e.g. `fork:`). They're
+    // modeled here so that every `BareEvent` has a slot for `set_default`,
+    // which `Trigger::to_events`, `Trigger::merge` and `EventKind` rely on.
+    pub create: OptionalBody<GenericEvent>,
+    pub delete: OptionalBody<GenericEvent>,
+    pub deployment: OptionalBody<GenericEvent>,
+    pub deployment_protection_rule: OptionalBody<GenericEvent>,
+    pub deployment_status: OptionalBody<GenericEvent>,
     pub discussion: OptionalBody<GenericEvent>,
     pub discussion_comment: OptionalBody<GenericEvent>,
-    // NOTE: `fork` and `gollum` are omitted, since they are always bare.
+    pub fork: OptionalBody<GenericEvent>,
+    pub gollum: OptionalBody<GenericEvent>,
     pub issue_comment: OptionalBody<GenericEvent>,
     pub issues: OptionalBody<GenericEvent>,
     pub label: OptionalBody<GenericEvent>,
     pub merge_group: OptionalBody<GenericEvent>,
     pub milestone: OptionalBody<GenericEvent>,
-    // NOTE: `page_build` is omitted, since it is always bare.
+    pub page_build: OptionalBody<GenericEvent>,
     pub project: OptionalBody<GenericEvent>,
     pub project_card: OptionalBody<GenericEvent>,
     pub project_column: OptionalBody<GenericEvent>,
-    // NOTE: `public` is omitted, since it is always bare.
+    pub public: OptionalBody<GenericEvent>,
     pub pull_request: OptionalBody<PullRequest>,
     pub pull_request_comment: OptionalBody<GenericEvent>,
     pub pull_request_review: OptionalBody<GenericEvent>,
@@ -85,14 +240,18 @@ pub struct Events {
     pub push: OptionalBody<Push>,
     pub registry_package: OptionalBody<GenericEvent>,
     pub release: OptionalBody<GenericEvent>,
-    pub repository_dispatch: OptionalBody<GenericEvent>,
+    pub repository_dispatch: OptionalBody<RepositoryDispatch>,
     pub schedule: OptionalBody<Vec<Cron>>,
-    // NOTE: `status` is omitted, since it is always bare.
+    pub status: OptionalBody<GenericEvent>,
     pub watch: OptionalBody<GenericEvent>,
     pub workflow_call: OptionalBody<WorkflowCall>,
     // TODO: Custom type.
     pub workflow_dispatch: OptionalBody<WorkflowDispatch>,
     pub workflow_run: OptionalBody<WorkflowRun>,
+
+    /// Any events that aren't modeled above.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 impl Events {
@@ -118,16 +277,25 @@ impl Events {
             branch_protection_rule,
             check_run,
             check_suite,
+            create,
+            delete,
+            deployment,
+            deployment_protection_rule,
+            deployment_status,
             discussion,
             discussion_comment,
+            fork,
+            gollum,
             issue_comment,
             issues,
             label,
             merge_group,
             milestone,
+            page_build,
             project,
             project_card,
             project_column,
+            public,
             pull_request,
             pull_request_comment,
             pull_request_review,
@@ -138,6 +306,7 @@ impl Events {
             release,
             repository_dispatch,
             schedule,
+            status,
             watch,
             workflow_call,
             workflow_dispatch,
@@ -146,6 +315,373 @@ impl Events {
 
         count
     }
+
+    /// Returns the unknown keys captured in these triggers, given the path
+    /// to them (e.g. `on`): unknown events, and unknown keys within each
+    /// present event's body.
+    pub(crate) fn unknown_keys(&self, path: &str) -> Vec<UnknownKey<'_>> {
+        let mut unknown: Vec<_> = crate::common::unknown_keys(path, &self.extra).collect();
+
+        macro_rules! push_if_present {
+            ($($field:ident),*) => {
+                $(
+                    if let OptionalBody::Body(body) = &self.$field {
+                        let path = child(path, stringify!($field));
+                        unknown.extend(crate::common::unknown_keys(&path, &body.extra));
+                    }
+                )*
+            };
+        }
+
+        push_if_present!(
+            branch_protection_rule,
+            check_run,
+            check_suite,
+            create,
+            delete,
+            deployment,
+            deployment_protection_rule,
+            deployment_status,
+            discussion,
+            discussion_comment,
+            fork,
+            gollum,
+            issue_comment,
+            issues,
+            label,
+            merge_group,
+            milestone,
+            page_build,
+            project,
+            project_card,
+            project_column,
+            public,
+            pull_request,
+            pull_request_comment,
+            pull_request_review,
+            pull_request_review_comment,
+            pull_request_target,
+            push,
+            registry_package,
+            release,
+            repository_dispatch,
+            status,
+            watch,
+            workflow_call,
+            workflow_dispatch,
+            workflow_run
+        );
+
+        if let OptionalBody::Body(crons) = &self.schedule {
+            for (index, cron) in crons.iter().enumerate() {
+                let path = format!("{}[{index}]", child(path, "schedule"));
+                unknown.extend(crate::common::unknown_keys(&path, &cron.extra));
+            }
+        }
+        if let OptionalBody::Body(call) = &self.workflow_call {
+            let path = child(path, "workflow_call");
+            for (name, input) in &call.inputs {
+                let path = child(&child(&path, "inputs"), name);
+                unknown.extend(crate::common::unknown_keys(&path, &input.extra));
+            }
+            for (name, output) in &call.outputs {
+                let path = child(&child(&path, "outputs"), name);
+                unknown.extend(crate::common::unknown_keys(&path, &output.extra));
+            }
+            for (name, secret) in &call.secrets {
+                if let Some(secret) = secret {
+                    let path = child(&child(&path, "secrets"), name);
+                    unknown.extend(crate::common::unknown_keys(&path, &secret.extra));
+                }
+            }
+        }
+        if let OptionalBody::Body(dispatch) = &self.workflow_dispatch {
+            let path = child(path, "workflow_dispatch");
+            for (name, input) in &dispatch.inputs {
+                let path = child(&child(&path, "inputs"), name);
+                unknown.extend(crate::common::unknown_keys(&path, &input.extra));
+            }
+        }
+
+        unknown
+    }
+
+    /// Returns the kinds of all present event triggers.
+    pub fn kinds(&self) -> BTreeSet<EventKind> {
+        let mut kinds = BTreeSet::new();
+
+        macro_rules! insert_if_present {
+            ($($field:ident => $kind:ident),*) => {
+                $(
+                    if !matches!(self.$field, OptionalBody::Missing) {
+                        kinds.insert(EventKind::$kind);
+                    }
+                )*
+            };
+        }
+
+        insert_if_present!(
+            branch_protection_rule => BranchProtectionRule,
+            check_run => CheckRun,
+            check_suite => CheckSuite,
+            create => Create,
+            delete => Delete,
+            deployment => Deployment,
+            deployment_protection_rule => DeploymentProtectionRule,
+            deployment_status => DeploymentStatus,
+            discussion => Discussion,
+            discussion_comment => DiscussionComment,
+            fork => Fork,
+            gollum => Gollum,
+            issue_comment => IssueComment,
+            issues => Issues,
+            label => Label,
+            merge_group => MergeGroup,
+            milestone => Milestone,
+            page_build => PageBuild,
+            project => Project,
+            project_card => ProjectCard,
+            project_column => ProjectColumn,
+            public => Public,
+            pull_request => PullRequest,
+            pull_request_comment => PullRequestComment,
+            pull_request_review => PullRequestReview,
+            pull_request_review_comment => PullRequestReviewComment,
+            pull_request_target => PullRequestTarget,
+            push => Push,
+            registry_package => RegistryPackage,
+            release => Release,
+            repository_dispatch => RepositoryDispatch,
+            schedule => Schedule,
+            status => Status,
+            watch => Watch,
+            workflow_call => WorkflowCall,
+            workflow_dispatch => WorkflowDispatch,
+            workflow_run => WorkflowRun
+        );
+
+        kinds
+    }
+
+    /// Enable the given bare event, as if it appeared in the mapping
+    /// form with an empty body (e.g. `push:`).
+    pub fn set_default(&mut self, event: BareEvent) {
+        match event {
+            BareEvent::BranchProtectionRule => self.branch_protection_rule = OptionalBody::Default,
+            BareEvent::CheckRun => self.check_run = OptionalBody::Default,
+            BareEvent::CheckSuite => self.check_suite = OptionalBody::Default,
+            BareEvent::Create => self.create = OptionalBody::Default,
+            BareEvent::Delete => self.delete = OptionalBody::Default,
+            BareEvent::Deployment => self.deployment = OptionalBody::Default,
+            BareEvent::DeploymentProtectionRule => {
+                self.deployment_protection_rule = OptionalBody::Default
+            }
+            BareEvent::DeploymentStatus => self.deployment_status = OptionalBody::Default,
+            BareEvent::Discussion => self.discussion = OptionalBody::Default,
+            BareEvent::DiscussionComment => self.discussion_comment = OptionalBody::Default,
+            BareEvent::Fork => self.fork = OptionalBody::Default,
+            BareEvent::Gollum => self.gollum = OptionalBody::Default,
+            BareEvent::IssueComment => self.issue_comment = OptionalBody::Default,
+            BareEvent::Issues => self.issues = OptionalBody::Default,
+            BareEvent::Label => self.label = OptionalBody::Default,
+            BareEvent::MergeGroup => self.merge_group = OptionalBody::Default,
+            BareEvent::Milestone => self.milestone = OptionalBody::Default,
+            BareEvent::PageBuild => self.page_build = OptionalBody::Default,
+            BareEvent::Project => self.project = OptionalBody::Default,
+            BareEvent::ProjectCard => self.project_card = OptionalBody::Default,
+            BareEvent::ProjectColumn => self.project_column = OptionalBody::Default,
+            BareEvent::Public => self.public = OptionalBody::Default,
+            BareEvent::PullRequest => self.pull_request = OptionalBody::Default,
+            BareEvent::PullRequestComment => self.pull_request_comment = OptionalBody::Default,
+            BareEvent::PullRequestReview => self.pull_request_review = OptionalBody::Default,
+            BareEvent::PullRequestReviewComment => {
+                self.pull_request_review_comment = OptionalBody::Default
+            }
+            BareEvent::PullRequestTarget => self.pull_request_target = OptionalBody::Default,
+            BareEvent::Push => self.push = OptionalBody::Default,
+            BareEvent::RegistryPackage => self.registry_package = OptionalBody::Default,
+            BareEvent::Release => self.release = OptionalBody::Default,
+            BareEvent::RepositoryDispatch => self.repository_dispatch = OptionalBody::Default,
+            BareEvent::Status => self.status = OptionalBody::Default,
+            BareEvent::Watch => self.watch = OptionalBody::Default,
+            BareEvent::WorkflowCall => self.workflow_call = OptionalBody::Default,
+            BareEvent::WorkflowDispatch => self.workflow_dispatch = OptionalBody::Default,
+            BareEvent::WorkflowRun => self.workflow_run = OptionalBody::Default,
+        }
+    }
+
+    /// Merge two sets of event triggers, with `overlay` taking precedence.
+    ///
+    /// The result contains the union of both sets of events. An event
+    /// that is present in `overlay` (either with a body or as an empty body)
+    /// replaces the `base` configuration for that event wholesale; bodies
+    /// are never merged field-by-field.
+    pub fn merge(base: &Events, overlay: &Events) -> Events {
+        macro_rules! merge_fields {
+            ($($field:ident),*) => {
+                Events {
+                    $(
+                        $field: match overlay.$field {
+                            OptionalBody::Missing => base.$field.clone(),
+                            _ => overlay.$field.clone(),
+                        },
+                    )*
+                    extra: base
+                        .extra
+                        .iter()
+                        .chain(&overlay.extra)
+                        .map(|(event, body)| (event.clone(), body.clone()))
+                        .collect(),
+                }
+            };
+        }
+
+        merge_fields!(
+            branch_protection_rule,
+            check_run,
+            check_suite,
+            create,
+            delete,
+            deployment,
+            deployment_protection_rule,
+            deployment_status,
+            discussion,
+            discussion_comment,
+            fork,
+            gollum,
+            issue_comment,
+            issues,
+            label,
+            merge_group,
+            milestone,
+            page_build,
+            project,
+            project_card,
+            project_column,
+            public,
+            pull_request,
+            pull_request_comment,
+            pull_request_review,
+            pull_request_review_comment,
+            pull_request_target,
+            push,
+            registry_package,
+            release,
+            repository_dispatch,
+            schedule,
+            status,
+            watch,
+            workflow_call,
+            workflow_dispatch,
+            workflow_run
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for Events {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let events = Mapping::deserialize(deserializer)?;
+
+        // Every event body except `schedule` is a mapping (or empty).
+        // A common mistake is to write a filter directly as the body,
+        // e.g. `push: [main]`, so we check for that before delegating
+        // and suggest the likely intended filter key.
+        for (event, body) in &events {
+            let Some(event) = event.as_str() else {
+                continue;
+            };
+            if event == "schedule" || matches!(body, Value::Null | Value::Mapping(_)) {
+                continue;
+            }
+            // Unknown events are ignored, as with any other unknown key.
+            if BareEvent::deserialize(Value::String(event.into())).is_err() {
+                continue;
+            }
+
+            let hint = match event {
+                "push" | "pull_request" | "pull_request_target" => Some("branches"),
+                "workflow_run" => Some("workflows"),
+                "workflow_call" | "workflow_dispatch" => None,
+                _ => Some("types"),
+            };
+            let message = match hint {
+                Some(key) => format!(
+                    "event `{event}` configuration must be a mapping; did you mean `{key}: [...]`?"
+                ),
+                None => format!("event `{event}` configuration must be a mapping"),
+            };
+            return Err(de::Error::custom(message));
+        }
+
+        Self::deserialize(Value::Mapping(events)).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Events {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.count() as usize))?;
+
+        macro_rules! serialize_if_present {
+            ($($field:ident),*) => {
+                $(
+                    if !matches!(self.$field, OptionalBody::Missing) {
+                        map.serialize_entry(stringify!($field), &self.$field)?;
+                    }
+                )*
+            };
+        }
+
+        serialize_if_present!(
+            branch_protection_rule,
+            check_run,
+            check_suite,
+            create,
+            delete,
+            deployment,
+            deployment_protection_rule,
+            deployment_status,
+            discussion,
+            discussion_comment,
+            fork,
+            gollum,
+            issue_comment,
+            issues,
+            label,
+            merge_group,
+            milestone,
+            page_build,
+            project,
+            project_card,
+            project_column,
+            public,
+            pull_request,
+            pull_request_comment,
+            pull_request_review,
+            pull_request_review_comment,
+            pull_request_target,
+            push,
+            registry_package,
+            release,
+            repository_dispatch,
+            schedule,
+            status,
+            watch,
+            workflow_call,
+            workflow_dispatch,
+            workflow_run
+        );
+        for (event, body) in &self.extra {
+            map.serialize_entry(event, body)?;
+        }
+
+        map.end()
+    }
 }
 
 /// A generic container type for distinguishing between
@@ -155,7 +691,7 @@ impl Events {
 /// between the non-presence of an event (no trigger) and the presence
 /// of an empty event body (e.g. `pull_request:`), which means "trigger
 /// with the defaults for this event type."
-#[derive(Default, Serialize)]
+#[derive(Clone, Default)]
 pub enum OptionalBody<T> {
     Default,
     #[default]
@@ -175,6 +711,23 @@ where
     }
 }
 
+impl<T> Serialize for OptionalBody<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // NOTE: `Missing` bodies are skipped entirely by `Events`;
+        // if serialized directly, they're indistinguishable from `Default`.
+        match self {
+            OptionalBody::Default | OptionalBody::Missing => serializer.serialize_none(),
+            OptionalBody::Body(body) => body.serialize(serializer),
+        }
+    }
+}
+
 impl<T> From<Option<T>> for OptionalBody<T> {
     fn from(value: Option<T>) -> Self {
         match value {
@@ -185,29 +738,117 @@ impl<T> From<Option<T>> for OptionalBody<T> {
 }
 
 /// A generic event trigger body.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct GenericEvent {
-    #[serde(default, deserialize_with = "crate::common::scalar_or_vector")]
+    #[serde(
+        default,
+        deserialize_with = "crate::common::scalar_or_vector",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub types: Vec<String>,
+
+    /// Any keys other than `types`.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
+/// The body of a `repository_dispatch` event trigger.
+///
+/// The dispatched event's type is filtered with `types`, like other
+/// activity types; `event-types` is accepted as well, since it mirrors
+/// the `event_type` field of the dispatch API.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RepositoryDispatch {
+    #[serde(
+        default,
+        deserialize_with = "crate::common::scalar_or_vector",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub types: Vec<String>,
+
+    #[serde(
+        default,
+        deserialize_with = "crate::common::scalar_or_vector",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub event_types: Vec<String>,
+
+    /// Any keys other than `types` and `event-types`.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl RepositoryDispatch {
+    /// Returns every event type this trigger is filtered to, from both
+    /// `types` and `event-types`.
+    ///
+    /// An empty result means that every event type triggers the workflow.
+    pub fn all_event_types(&self) -> impl Iterator<Item = &str> {
+        self.types
+            .iter()
+            .chain(&self.event_types)
+            .map(String::as_str)
+    }
+}
+
+/// An activity type for the `pull_request` and `pull_request_target`
+/// event triggers.
+///
+/// See [`pull_request`] for the supported activity types.
+///
+/// [`pull_request`]: https://docs.github.com/en/actions/using-workflows/events-that-trigger-workflows#pull_request
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullRequestActivityType {
+    Assigned,
+    Unassigned,
+    Labeled,
+    Unlabeled,
+    Opened,
+    Edited,
+    Closed,
+    Reopened,
+    Synchronize,
+    ConvertedToDraft,
+    Locked,
+    Unlocked,
+    Enqueued,
+    Dequeued,
+    Milestoned,
+    Demilestoned,
+    ReadyForReview,
+    ReviewRequested,
+    ReviewRequestRemoved,
+    AutoMergeEnabled,
+    AutoMergeDisabled,
+}
+
+// NOTE: In bodies with flattened filters (`PullRequest`, `Push` and
+// `WorkflowRun`), `extra` has to be flattened last, so that it only sees the
+// keys that none of the filters consume.
+
 /// The body of a `pull_request` event trigger.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct PullRequest {
-    #[serde(default)]
-    pub types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<PullRequestActivityType>,
 
     #[serde(flatten)]
     pub branch_filters: Option<BranchFilters>,
 
     #[serde(flatten)]
     pub path_filters: Option<PathFilters>,
+
+    /// Any keys other than `types` and the branch and path filters.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// The body of a `push` event trigger.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Push {
     #[serde(flatten)]
@@ -218,90 +859,556 @@ pub struct Push {
 
     #[serde(flatten)]
     pub tag_filters: Option<TagFilters>,
+
+    /// Any keys other than the branch, path and tag filters.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// The body of a `cron` event trigger.
-#[derive(Deserialize, Serialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", remote = "Self")]
 pub struct Cron {
     pub cron: String,
+
+    /// Any other keys in this schedule entry.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl Cron {
+    /// Returns whether this schedule is a well-formed POSIX cron expression,
+    /// i.e. has exactly five fields with in-range values.
+    ///
+    /// This performs the same check as deserialization, and is useful
+    /// for `Cron`s constructed by hand.
+    pub fn is_valid_schedule(&self) -> bool {
+        parse_cron(&self.cron).is_ok()
+    }
+
+    /// Returns the hours (in UTC, which GitHub always uses for schedules)
+    /// at which this schedule fires, in ascending order.
+    ///
+    /// Returns `None` if this schedule is invalid.
+    pub fn hours_utc(&self) -> Option<Vec<u8>> {
+        let [_, hours, ..] = parse_cron(&self.cron).ok()?;
+        Some(hours.into_iter().collect())
+    }
+
+    /// Describe when this schedule fires, in both UTC and the given
+    /// display offset (in minutes east of UTC, e.g. `-480` for PST).
+    ///
+    /// No timezone database is used: the offset is applied as-is, so
+    /// the description doesn't account for daylight saving time.
+    ///
+    /// Returns `None` if this schedule is invalid.
+    pub fn shifted(&self, display_offset_minutes: i32) -> Option<CronDescription> {
+        let [minutes, hours, days_of_month, months, days_of_week] = parse_cron(&self.cron).ok()?;
+
+        let mut utc_times = vec![];
+        for hour in &hours {
+            for minute in &minutes {
+                utc_times.push((*hour, *minute));
+            }
+        }
+
+        let shifted_times = utc_times
+            .iter()
+            .map(|(hour, minute)| {
+                let total = i32::from(*hour) * 60 + i32::from(*minute) + display_offset_minutes;
+                let day_shift = total.div_euclid(24 * 60) as i8;
+                let total = total.rem_euclid(24 * 60);
+                ((total / 60) as u8, (total % 60) as u8, day_shift)
+            })
+            .collect();
+
+        let [_, _, dom_spec, month_spec, dow_spec] = &CRON_FIELDS;
+        let mut days = match (
+            dom_spec.is_full(&days_of_month),
+            dow_spec.is_full(&days_of_week),
+        ) {
+            (true, true) => "every day".to_string(),
+            (true, false) if days_of_week == BTreeSet::from([1, 2, 3, 4, 5]) => {
+                "weekdays".to_string()
+            }
+            (true, false) if days_of_week == BTreeSet::from([0, 6]) => "weekends".to_string(),
+            (true, false) => format!("on {}", dow_spec.names_for(&days_of_week)),
+            (false, dow_full) => {
+                let days = days_of_month
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                // NOTE: cron matches *either* field when both are restricted.
+                match dow_full {
+                    true => format!("on day {days} of the month"),
+                    false => format!(
+                        "on day {days} of the month or on {}",
+                        dow_spec.names_for(&days_of_week)
+                    ),
+                }
+            }
+        };
+
+        if !month_spec.is_full(&months) {
+            days = format!("{days} in {}", month_spec.names_for(&months));
+        }
+
+        Some(CronDescription {
+            days,
+            utc_times,
+            offset_minutes: display_offset_minutes,
+            shifted_times,
+            dst_caveat: display_offset_minutes != 0,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Cron {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let cron = Self::deserialize(deserializer)?;
+
+        parse_cron(&cron.cron).map_err(|e| {
+            de::Error::custom(format!("invalid cron schedule `{}`: {e}", cron.cron))
+        })?;
+
+        Ok(cron)
+    }
+}
+
+impl Serialize for Cron {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Self::serialize(self, serializer)
+    }
+}
+
+/// A single field in a cron schedule: its name, its inclusive numeric bounds,
+/// and any symbolic names (e.g. `JAN`, `MON`) it accepts in place of numbers.
+struct CronField {
+    name: &'static str,
+    min: u8,
+    max: u8,
+    names: &'static [&'static str],
+}
+
+const CRON_FIELDS: [CronField; 5] = [
+    CronField {
+        name: "minute",
+        min: 0,
+        max: 59,
+        names: &[],
+    },
+    CronField {
+        name: "hour",
+        min: 0,
+        max: 23,
+        names: &[],
+    },
+    CronField {
+        name: "day of month",
+        min: 1,
+        max: 31,
+        names: &[],
+    },
+    CronField {
+        name: "month",
+        min: 1,
+        max: 12,
+        names: &[
+            "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+        ],
+    },
+    CronField {
+        name: "day of week",
+        min: 0,
+        max: 6,
+        names: &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"],
+    },
+];
+
+impl CronField {
+    /// Render the given values with their symbolic names, e.g. `Mon, Wed`.
+    fn names_for(&self, values: &BTreeSet<u8>) -> String {
+        values
+            .iter()
+            .map(|v| {
+                let name = self.names[usize::from(v - self.min)];
+                // `MON` -> `Mon`
+                format!("{}{}", &name[..1], name[1..].to_ascii_lowercase())
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Parse a single value (number or symbolic name) within this field.
+    fn value(&self, value: &str) -> Result<u8, String> {
+        // Symbolic names map onto the field's numeric range, in order.
+        if let Some(idx) = self
+            .names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(value))
+        {
+            return Ok(self.min + idx as u8);
+        }
+
+        match value.parse::<u8>() {
+            Ok(v) if (self.min..=self.max).contains(&v) => Ok(v),
+            _ => Err(format!(
+                "{name} value `{value}` is not in {min}-{max}",
+                name = self.name,
+                min = self.min,
+                max = self.max
+            )),
+        }
+    }
+
+    /// Parse a single field, which is a comma-separated list of
+    /// `*`, `N`, or `N-M` items, each with an optional `/STEP`,
+    /// into the set of values it matches.
+    fn values(&self, field: &str) -> Result<BTreeSet<u8>, String> {
+        let mut values = BTreeSet::new();
+
+        for item in field.split(',') {
+            let (base, step) = match item.split_once('/') {
+                Some((base, step)) => (base, Some(step)),
+                None => (item, None),
+            };
+
+            let step = match step {
+                None => None,
+                Some(step) => match step.parse::<u8>() {
+                    Ok(step) if step > 0 => Some(step),
+                    _ => return Err(format!("invalid step `{step}` in {}", self.name)),
+                },
+            };
+
+            let (start, end) = match base.split_once('-') {
+                _ if base == "*" => (self.min, self.max),
+                Some((start, end)) => {
+                    let (start, end) = (self.value(start)?, self.value(end)?);
+                    if start > end {
+                        return Err(format!("invalid range `{base}` in {}", self.name));
+                    }
+                    (start, end)
+                }
+                // `N/STEP` means "every STEP, starting at N", even for a
+                // step of 1.
+                None if step.is_some() => (self.value(base)?, self.max),
+                None => {
+                    let value = self.value(base)?;
+                    (value, value)
+                }
+            };
+
+            values.extend((start..=end).step_by(step.unwrap_or(1).into()));
+        }
+
+        Ok(values)
+    }
+
+    /// Returns whether the given values are every value in this field,
+    /// i.e. are equivalent to `*`.
+    fn is_full(&self, values: &BTreeSet<u8>) -> bool {
+        values.len() == usize::from(self.max - self.min) + 1
+    }
+}
+
+/// Parse a POSIX cron schedule, as accepted by GitHub Actions, into
+/// the set of values matched by each of its fields.
+fn parse_cron(cron: &str) -> Result<[BTreeSet<u8>; 5], String> {
+    let fields = cron.split_whitespace().collect::<Vec<_>>();
+    if fields.len() != CRON_FIELDS.len() {
+        return Err(format!("expected 5 fields, got {}", fields.len()));
+    }
+
+    let mut parsed: [BTreeSet<u8>; 5] = Default::default();
+    for (pos, (field, spec)) in fields.iter().zip(CRON_FIELDS.iter()).enumerate() {
+        parsed[pos] = spec
+            .values(field)
+            .map_err(|e| format!("field {} ({}): {e}", pos + 1, spec.name))?;
+    }
+
+    Ok(parsed)
+}
+
+/// A human-readable description of when a [`Cron`] schedule fires,
+/// in both UTC and a fixed display offset.
+///
+/// Produced by [`Cron::shifted`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CronDescription {
+    /// The days on which the schedule fires, e.g. `"weekdays"` or
+    /// `"every day"`. These are always UTC days.
+    pub days: String,
+    /// The `(hour, minute)` times of day at which the schedule fires, in UTC.
+    pub utc_times: Vec<(u8, u8)>,
+    /// The display offset from UTC, in minutes.
+    pub offset_minutes: i32,
+    /// The `(hour, minute, day_shift)` times of day at which the schedule
+    /// fires in the display offset. `day_shift` is `-1` if the time falls
+    /// on the previous day relative to UTC, `1` if on the next day,
+    /// and `0` otherwise.
+    pub shifted_times: Vec<(u8, u8, i8)>,
+    /// Whether [`CronDescription::shifted_times`] may be off by an hour
+    /// for part of the year.
+    ///
+    /// Fixed offsets don't observe daylight saving time, so this is
+    /// `true` for any non-zero offset: the caller's intended timezone
+    /// may not be at this offset year-round.
+    pub dst_caveat: bool,
+}
+
+impl CronDescription {
+    /// Formats the display offset like `UTC-08:00`.
+    fn offset(&self) -> String {
+        let sign = if self.offset_minutes < 0 { '-' } else { '+' };
+        let offset = self.offset_minutes.unsigned_abs();
+        format!("UTC{sign}{:02}:{:02}", offset / 60, offset % 60)
+    }
+}
+
+impl Display for CronDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Schedules that fire many times a day (e.g. every 5 minutes)
+        // aren't usefully described by listing each time.
+        const MAX_TIMES: usize = 6;
+
+        if self.utc_times.len() > MAX_TIMES {
+            return write!(f, "{} at {} times a day", self.days, self.utc_times.len());
+        }
+
+        let utc = self
+            .utc_times
+            .iter()
+            .map(|(h, m)| format!("{h:02}:{m:02}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{} at {utc} UTC", self.days)?;
+
+        if self.offset_minutes != 0 {
+            let shifted = self
+                .shifted_times
+                .iter()
+                .map(|(h, m, shift)| match shift {
+                    -1 => format!("{h:02}:{m:02} (previous day)"),
+                    1 => format!("{h:02}:{m:02} (next day)"),
+                    _ => format!("{h:02}:{m:02}"),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, " / {shifted} {}", self.offset())?;
+        }
+
+        if self.dst_caveat {
+            write!(f, " (fixed offset; daylight saving time not applied)")?;
+        }
+
+        Ok(())
+    }
 }
 
 /// The body of a `workflow_call` event trigger.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct WorkflowCall {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub inputs: IndexMap<String, WorkflowCallInput>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub outputs: IndexMap<String, WorkflowCallOutput>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub secrets: IndexMap<String, Option<WorkflowCallSecret>>,
+
+    /// Any keys other than `inputs`, `outputs` and `secrets`.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl WorkflowCall {
+    /// Look up an input by name, returning its declared name and its spec.
+    ///
+    /// Like GitHub, this matches names ASCII case-insensitively. If
+    /// several inputs differ only by case, an exact-case match wins;
+    /// otherwise, the first declared match does.
+    pub fn input(&self, name: &str) -> Option<(&str, &WorkflowCallInput)> {
+        lookup(&self.inputs, name)
+    }
+
+    /// Look up a secret by name, returning its declared name and its spec
+    /// (if it has one).
+    ///
+    /// Names are matched like [`WorkflowCall::input`].
+    pub fn secret(&self, name: &str) -> Option<(&str, Option<&WorkflowCallSecret>)> {
+        lookup(&self.secrets, name).map(|(name, secret)| (name, secret.as_ref()))
+    }
+
+    /// Returns the name of each secret, in declaration order.
+    pub fn secret_names(&self) -> impl Iterator<Item = &str> {
+        self.secrets.keys().map(String::as_str)
+    }
+
+    /// Returns each secret that callers must pass, i.e. each one declared
+    /// with `required: true`.
+    pub fn required_secrets(&self) -> impl Iterator<Item = (&str, &WorkflowCallSecret)> {
+        self.secrets
+            .iter()
+            .filter_map(|(name, secret)| match secret {
+                Some(secret) if secret.required => Some((name.as_str(), secret)),
+                _ => None,
+            })
+    }
+
+    /// Returns each secret that callers may omit, along with its spec
+    /// (if it has one).
+    ///
+    /// This is the complement of [`WorkflowCall::required_secrets`]:
+    /// a secret without a spec is optional.
+    pub fn optional_secrets(&self) -> impl Iterator<Item = (&str, Option<&WorkflowCallSecret>)> {
+        self.secrets
+            .iter()
+            .filter(|(_, secret)| !secret.as_ref().is_some_and(|secret| secret.required))
+            .map(|(name, secret)| (name.as_str(), secret.as_ref()))
+    }
+
+    /// Look up an output by name, returning its declared name and its spec.
+    ///
+    /// Names are matched like [`WorkflowCall::input`].
+    pub fn output(&self, name: &str) -> Option<(&str, &WorkflowCallOutput)> {
+        lookup(&self.outputs, name)
+    }
+
+    /// Returns the names of any outputs that can't be referenced by
+    /// callers via `needs.<job>.outputs.<name>`, since they aren't
+    /// identifiers (e.g. `foo.bar`).
+    pub(crate) fn unreachable_output_names(&self) -> impl Iterator<Item = &str> {
+        self.outputs
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !crate::common::is_identifier(name))
+    }
+}
+
+/// ASCII case-insensitive lookup, preferring an exact-case match.
+fn lookup<'a, V>(map: &'a IndexMap<String, V>, name: &str) -> Option<(&'a str, &'a V)> {
+    map.get_key_value(name)
+        .or_else(|| map.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)))
+        .map(|(key, value)| (key.as_str(), value))
 }
 
 /// A single input in a `workflow_call` event trigger body.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct WorkflowCallInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    // TODO: model `default`?
-    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<EnvValue>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub required: bool,
     pub r#type: String,
+
+    /// Any other keys in this input.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// A single output in a `workflow_call` event trigger body.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct WorkflowCallOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub value: String,
+
+    /// Any other keys in this output.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// A single secret in a `workflow_call` event trigger body.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct WorkflowCallSecret {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub required: bool,
+
+    /// Any other keys in this secret.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// The body of a `workflow_dispatch` event trigger.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct WorkflowDispatch {
-    #[serde(default)]
+    #[serde(default, deserialize_with = "null_inputs")]
     pub inputs: IndexMap<String, WorkflowDispatchInput>, // TODO: WorkflowDispatchInput
+
+    /// Any keys other than `inputs`.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+/// Deserialize `workflow_dispatch` inputs, treating a null `inputs:` as
+/// empty and a null input body as an all-default input, like
+/// `null_to_default` does for environment values.
+fn null_inputs<'de, D>(de: D) -> Result<IndexMap<String, WorkflowDispatchInput>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let inputs = Option::<IndexMap<String, Option<WorkflowDispatchInput>>>::deserialize(de)?;
+    Ok(inputs
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, input)| (name, input.unwrap_or_default()))
+        .collect())
 }
 
 /// A single input in a `workflow_dispatch` event trigger body.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct WorkflowDispatchInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    // TODO: model `default`?
-    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<EnvValue>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub required: bool,
     // TODO: Model as boolean, choice, number, environment, string; default is string.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub r#type: Option<String>,
     // Only present when `type` is `choice`.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub options: Vec<String>,
+
+    /// Any other keys in this input.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// The body of a `workflow_run` event trigger.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct WorkflowRun {
     pub workflows: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub types: Vec<String>,
     #[serde(flatten)]
     pub branch_filters: Option<BranchFilters>,
+
+    /// Any keys other than `workflows`, `types` and the branch filters.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
 /// Branch filtering variants for event trigger bodies.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum BranchFilters {
     Branches(Vec<String>),
@@ -309,7 +1416,7 @@ pub enum BranchFilters {
 }
 
 /// Tag filtering variants for event trigger bodies.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum TagFilters {
     Tags(Vec<String>),
@@ -317,7 +1424,7 @@ pub enum TagFilters {
 }
 
 /// Path filtering variants for event trigger bodies.
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum PathFilters {
     Paths(Vec<String>),
@@ -326,6 +1433,13 @@ pub enum PathFilters {
 
 #[cfg(test)]
 mod tests {
+    use serde_yaml::Value;
+
+    use super::{
+        BranchFilters, Cron, Events, OptionalBody, PathFilters, PullRequest,
+        PullRequestActivityType, Push, TagFilters, WorkflowCall, WorkflowDispatch, WorkflowRun,
+    };
+
     #[test]
     fn test_events_count() {
         let events = "
@@ -337,4 +1451,336 @@ issue_comment:";
         let events = serde_yaml::from_str::<super::Events>(events).unwrap();
         assert_eq!(events.count(), 4);
     }
+
+    #[test]
+    fn test_workflow_dispatch_null_bodies() {
+        let dispatch = serde_yaml::from_str::<WorkflowDispatch>("inputs:").unwrap();
+        assert!(dispatch.inputs.is_empty());
+
+        let dispatch = serde_yaml::from_str::<WorkflowDispatch>("{}").unwrap();
+        assert!(dispatch.inputs.is_empty());
+
+        let dispatch = serde_yaml::from_str::<WorkflowDispatch>(
+            "inputs:\n  foo:\n  bar:\n    required: true\n    type: boolean",
+        )
+        .unwrap();
+        let foo = &dispatch.inputs["foo"];
+        assert!(foo.description.is_none());
+        assert!(!foo.required);
+        assert!(foo.r#type.is_none());
+        assert!(foo.options.is_empty());
+        let bar = &dispatch.inputs["bar"];
+        assert!(bar.required);
+        assert_eq!(bar.r#type.as_deref(), Some("boolean"));
+
+        let events = serde_yaml::from_str::<Events>("workflow_dispatch:\n  inputs:").unwrap();
+        let OptionalBody::Body(dispatch) = events.workflow_dispatch else {
+            panic!("expected workflow_dispatch body");
+        };
+        assert!(dispatch.inputs.is_empty());
+    }
+
+    #[test]
+    fn test_pull_request_activity_types() {
+        let pr = serde_yaml::from_str::<PullRequest>(
+            "types: [opened, synchronize, ready_for_review, auto_merge_enabled]",
+        )
+        .unwrap();
+        assert_eq!(
+            pr.types,
+            [
+                PullRequestActivityType::Opened,
+                PullRequestActivityType::Synchronize,
+                PullRequestActivityType::ReadyForReview,
+                PullRequestActivityType::AutoMergeEnabled,
+            ]
+        );
+        assert_eq!(
+            serde_yaml::to_string(&pr.types).unwrap(),
+            "- opened\n- synchronize\n- ready_for_review\n- auto_merge_enabled\n"
+        );
+
+        let Err(err) = serde_yaml::from_str::<PullRequest>("types: [opened, submitted]") else {
+            panic!("expected an unknown activity type to fail");
+        };
+        assert!(err.to_string().contains("unknown variant `submitted`"));
+    }
+
+    #[test]
+    fn test_filter_extra_keys() {
+        let pr = serde_yaml::from_str::<PullRequest>(
+            "
+types: [opened]
+branches: [main]
+paths-ignore: [docs/**]
+future-filter: [foo]
+another: true
+",
+        )
+        .unwrap();
+        assert_eq!(pr.types, [PullRequestActivityType::Opened]);
+        assert!(matches!(pr.branch_filters, Some(BranchFilters::Branches(b)) if b == ["main"]));
+        assert!(matches!(pr.path_filters, Some(PathFilters::PathsIgnore(p)) if p == ["docs/**"]));
+        assert_eq!(
+            pr.extra.keys().collect::<Vec<_>>(),
+            ["future-filter", "another"]
+        );
+        assert_eq!(pr.extra["another"], Value::Bool(true));
+
+        let push = serde_yaml::from_str::<Push>(
+            "branches-ignore: [wip]\ntags: [v*]\npaths: [src/**]\nnovel: x",
+        )
+        .unwrap();
+        assert!(matches!(
+            push.branch_filters,
+            Some(BranchFilters::BranchesIgnore(_))
+        ));
+        assert!(matches!(push.path_filters, Some(PathFilters::Paths(_))));
+        assert!(matches!(push.tag_filters, Some(TagFilters::Tags(_))));
+        assert_eq!(push.extra.keys().collect::<Vec<_>>(), ["novel"]);
+
+        // Extra keys round-trip.
+        let yaml = serde_yaml::to_string(&push).unwrap();
+        let push = serde_yaml::from_str::<Push>(&yaml).unwrap();
+        assert!(matches!(push.tag_filters, Some(TagFilters::Tags(_))));
+        assert_eq!(push.extra["novel"], Value::String("x".into()));
+
+        let run = serde_yaml::from_str::<WorkflowRun>(
+            "workflows: [CI]\ntypes: [completed]\nbranches: [main]\nnovel: x",
+        )
+        .unwrap();
+        assert!(matches!(
+            run.branch_filters,
+            Some(BranchFilters::Branches(_))
+        ));
+        assert_eq!(run.extra.keys().collect::<Vec<_>>(), ["novel"]);
+
+        let push = serde_yaml::from_str::<Push>("branches: [main]").unwrap();
+        assert!(push.extra.is_empty());
+        assert!(push.path_filters.is_none());
+    }
+
+    #[test]
+    fn test_workflow_call_lookup() {
+        let call = serde_yaml::from_str::<WorkflowCall>(
+            "
+inputs:
+  Environment:
+    type: string
+  dry-run:
+    type: boolean
+  DRY-RUN:
+    type: string
+    required: true
+secrets:
+  Deploy-Key:
+  api-token:
+    required: true
+outputs:
+  Version:
+    value: ${{ jobs.build.outputs.version }}
+",
+        )
+        .unwrap();
+
+        let (name, input) = call.input("environment").unwrap();
+        assert_eq!(name, "Environment");
+        assert_eq!(input.r#type, "string");
+        assert!(call.input("ENVIRONMENT").is_some());
+        assert!(call.input("env").is_none());
+
+        // Exact case wins on ambiguity, then declaration order.
+        assert_eq!(call.input("DRY-RUN").unwrap().0, "DRY-RUN");
+        assert_eq!(call.input("dry-run").unwrap().0, "dry-run");
+        assert_eq!(call.input("Dry-Run").unwrap().0, "dry-run");
+
+        let (name, secret) = call.secret("deploy-key").unwrap();
+        assert_eq!(name, "Deploy-Key");
+        assert!(secret.is_none());
+        let (name, secret) = call.secret("API-TOKEN").unwrap();
+        assert_eq!(name, "api-token");
+        assert!(secret.unwrap().required);
+        assert!(call.secret("missing").is_none());
+
+        let (name, output) = call.output("version").unwrap();
+        assert_eq!(name, "Version");
+        assert_eq!(output.value, "${{ jobs.build.outputs.version }}");
+    }
+
+    #[test]
+    fn test_cron_valid() {
+        let vectors = [
+            "0 12 * * *",
+            "33 */9 * * *",
+            "30 03 * * *",
+            "*/15 0-6,18-23 * * MON-FRI",
+            "0 0 1 jan,jul *",
+            "5 4 * * sun",
+        ];
+
+        for vector in vectors {
+            let cron = serde_yaml::from_str::<Cron>(&format!("cron: \"{vector}\"")).unwrap();
+            assert!(cron.is_valid_schedule());
+        }
+    }
+
+    #[test]
+    fn test_cron_invalid() {
+        let vectors = [
+            ("* * * * * *", "expected 5 fields, got 6"),
+            ("* * * *", "expected 5 fields, got 4"),
+            (
+                "99 * * * *",
+                "field 1 (minute): minute value `99` is not in 0-59",
+            ),
+            (
+                "0 24 * * *",
+                "field 2 (hour): hour value `24` is not in 0-23",
+            ),
+            (
+                "0 0 0 * *",
+                "field 3 (day of month): day of month value `0` is not in 1-31",
+            ),
+            (
+                "0 0 * FOO *",
+                "field 4 (month): month value `FOO` is not in 1-12",
+            ),
+            (
+                "0 0 * * 5-1",
+                "field 5 (day of week): invalid range `5-1` in day of week",
+            ),
+            (
+                "*/0 * * * *",
+                "field 1 (minute): invalid step `0` in minute",
+            ),
+        ];
+
+        for (vector, err) in vectors {
+            let cron = Cron {
+                cron: vector.into(),
+                extra: Default::default(),
+            };
+            assert!(!cron.is_valid_schedule());
+
+            let err = format!("invalid cron schedule `{vector}`: {err}");
+            let actual = serde_yaml::from_str::<Cron>(&format!("cron: \"{vector}\""))
+                .unwrap_err()
+                .to_string();
+            assert!(actual.starts_with(&err), "{actual} != {err}");
+        }
+    }
+
+    #[test]
+    fn test_cron_hours_utc() {
+        let cron = |cron: &str| Cron {
+            cron: cron.into(),
+            extra: Default::default(),
+        };
+
+        assert_eq!(cron("0 9 * * 1-5").hours_utc(), Some(vec![9]));
+        assert_eq!(cron("0 */6 * * *").hours_utc(), Some(vec![0, 6, 12, 18]));
+        assert_eq!(cron("0 1,13 * * *").hours_utc(), Some(vec![1, 13]));
+        assert_eq!(cron("0 24 * * *").hours_utc(), None);
+    }
+
+    #[test]
+    fn test_cron_shifted() {
+        let cron = Cron {
+            cron: "0 9 * * 1-5".into(),
+            extra: Default::default(),
+        };
+
+        // PST: same day, earlier.
+        let desc = cron.shifted(-8 * 60).unwrap();
+        assert_eq!(desc.days, "weekdays");
+        assert_eq!(desc.utc_times, [(9, 0)]);
+        assert_eq!(desc.shifted_times, [(1, 0, 0)]);
+        assert!(desc.dst_caveat);
+        assert_eq!(
+            desc.to_string(),
+            "weekdays at 09:00 UTC / 01:00 UTC-08:00 (fixed offset; daylight saving time not applied)"
+        );
+
+        // IST: half-hour offset.
+        let desc = cron.shifted(5 * 60 + 30).unwrap();
+        assert_eq!(desc.shifted_times, [(14, 30, 0)]);
+        assert!(desc.to_string().contains("14:30 UTC+05:30"));
+
+        // JST: crosses into the next day.
+        let desc = Cron {
+            cron: "30 20 * * 1-5".into(),
+            extra: Default::default(),
+        }
+        .shifted(9 * 60)
+        .unwrap();
+        assert_eq!(desc.shifted_times, [(5, 30, 1)]);
+        assert!(desc.to_string().contains("05:30 (next day) UTC+09:00"));
+
+        // UTC: no shift and no DST caveat.
+        let desc = cron.shifted(0).unwrap();
+        assert!(!desc.dst_caveat);
+        assert_eq!(desc.to_string(), "weekdays at 09:00 UTC");
+    }
+
+    #[test]
+    fn test_cron_shifted_days() {
+        let days = |cron: &str| {
+            Cron {
+                cron: cron.into(),
+                extra: Default::default(),
+            }
+            .shifted(0)
+            .unwrap()
+            .days
+        };
+
+        assert_eq!(days("0 0 * * *"), "every day");
+        assert_eq!(days("0 0 * * SAT,SUN"), "weekends");
+        assert_eq!(days("0 0 * * 1,3"), "on Mon, Wed");
+        assert_eq!(days("0 0 1,15 * *"), "on day 1, 15 of the month");
+        assert_eq!(days("0 0 * JAN-MAR *"), "every day in Jan, Feb, Mar");
+    }
+
+    #[test]
+    fn test_event_body_shape_errors() {
+        let vectors = [
+            (
+                "push: [main]",
+                "event `push` configuration must be a mapping; did you mean `branches: [...]`?",
+            ),
+            (
+                "pull_request: main",
+                "event `pull_request` configuration must be a mapping; did you mean `branches: [...]`?",
+            ),
+            (
+                "issues: [opened]",
+                "event `issues` configuration must be a mapping; did you mean `types: [...]`?",
+            ),
+            (
+                "workflow_run: ci",
+                "event `workflow_run` configuration must be a mapping; did you mean `workflows: [...]`?",
+            ),
+            (
+                "workflow_dispatch: true",
+                "event `workflow_dispatch` configuration must be a mapping",
+            ),
+        ];
+
+        for (events, expected) in vectors {
+            let err = serde_yaml::from_str::<super::Events>(events)
+                .map(|_| ())
+                .unwrap_err()
+                .to_string();
+            assert!(err.starts_with(expected), "{err:?} != {expected:?}");
+        }
+
+        // Null and mapping bodies are fine, as are schedules and unknown events.
+        let events = "push:\npull_request: {}\nschedule: [{cron: '0 0 * * *'}]\nnot_an_event: [x]";
+        assert_eq!(
+            serde_yaml::from_str::<super::Events>(events)
+                .unwrap()
+                .count(),
+            3
+        );
+    }
 }
@@ -0,0 +1,373 @@
+//! Collection of expressions from a workflow's models, for
+//! [`Workflow::expressions`].
+
+use indexmap::IndexMap;
+use serde_yaml::Value;
+
+use crate::common::{
+    expr::{self, LoE},
+    Env, EnvValue, If, Shell,
+};
+
+use super::{
+    event::OptionalBody,
+    job::{
+        Container, DeploymentEnvironment, NormalJob, Port, ReusableWorkflowCallJob, RunsOn,
+        Secrets, Step, StepBody, Strategy,
+    },
+    Concurrency, Defaults, ExpressionLocation, Job, Trigger, Workflow, WorkflowExpression,
+};
+
+/// Collects expressions, in the order their keys appear when the models
+/// are serialized.
+pub(super) struct Expressions<'a> {
+    found: Vec<WorkflowExpression<'a>>,
+    job_id: Option<&'a str>,
+    step_index: Option<usize>,
+}
+
+impl<'a> Expressions<'a> {
+    pub(super) fn collect(workflow: &'a Workflow) -> Vec<WorkflowExpression<'a>> {
+        let mut expressions = Self {
+            found: vec![],
+            job_id: None,
+            step_index: None,
+        };
+
+        expressions.workflow(workflow);
+        for (job_id, job) in &workflow.jobs {
+            expressions.job_id = Some(job_id);
+            expressions.step_index = None;
+
+            match job {
+                Job::NormalJob(job) => {
+                    expressions.normal_job(job);
+                    for (index, step) in job.steps.iter().enumerate() {
+                        expressions.step_index = Some(index);
+                        expressions.step(step);
+                    }
+                }
+                Job::ReusableWorkflowCallJob(job) => expressions.reusable_job(job),
+            }
+        }
+
+        expressions.found
+    }
+
+    fn workflow(&mut self, workflow: &Workflow) {
+        if let Some(name) = &workflow.name {
+            self.string("name", name);
+        }
+        if let Some(run_name) = &workflow.run_name {
+            self.string("run-name", run_name.as_str());
+        }
+
+        // NOTE: GitHub only evaluates expressions in `workflow_call`'s
+        // input defaults and output values within `on:`.
+        if let Trigger::Events(events) = &workflow.on {
+            if let OptionalBody::Body(call) = &events.workflow_call {
+                for (name, input) in &call.inputs {
+                    if let Some(EnvValue::String(default)) = &input.default {
+                        self.string(&format!("on.workflow_call.inputs.{name}.default"), default);
+                    }
+                }
+                for (name, output) in &call.outputs {
+                    self.string(
+                        &format!("on.workflow_call.outputs.{name}.value"),
+                        &output.value,
+                    );
+                }
+            }
+        }
+
+        self.env("env", &workflow.env);
+        self.defaults("defaults", &workflow.defaults);
+        self.concurrency("concurrency", &workflow.concurrency);
+        self.extra("", &workflow.extra);
+    }
+
+    fn normal_job(&mut self, job: &NormalJob) {
+        if let Some(name) = &job.name {
+            self.string("name", name);
+        }
+        self.condition(&job.r#if);
+
+        match &job.runs_on {
+            LoE::Expr(expr) => self.string("runs-on", expr.as_raw()),
+            LoE::Literal(RunsOn::Target(labels)) => self.strings("runs-on", labels),
+            LoE::Literal(RunsOn::Group { group, labels }) => {
+                if let Some(group) = group {
+                    self.string("runs-on.group", group);
+                }
+                self.strings("runs-on.labels", labels);
+            }
+        }
+
+        match &job.environment {
+            Some(DeploymentEnvironment::Name(name)) => self.string("environment", name.as_str()),
+            Some(DeploymentEnvironment::NameURL { name, url }) => {
+                self.string("environment.name", name.as_str());
+                if let Some(url) = url {
+                    self.string("environment.url", url.as_str());
+                }
+            }
+            None => {}
+        }
+
+        self.concurrency("concurrency", &job.concurrency);
+        for (name, value) in &job.outputs {
+            self.string(&format!("outputs.{name}"), value);
+        }
+        self.env("env", &job.env);
+        self.defaults("defaults", &job.defaults);
+        if let Some(timeout) = &job.timeout_minutes {
+            self.expr("timeout-minutes", timeout);
+        }
+        self.strategy("strategy", &job.strategy);
+        self.expr("continue-on-error", &job.continue_on_error);
+        if let Some(container) = &job.container {
+            self.container("container", container);
+        }
+        for (name, service) in &job.services {
+            self.container(&format!("services.{name}"), service);
+        }
+        self.extra("", &job.extra);
+    }
+
+    fn reusable_job(&mut self, job: &ReusableWorkflowCallJob) {
+        if let Some(name) = &job.name {
+            self.string("name", name);
+        }
+        self.condition(&job.r#if);
+        self.strategy("strategy", &job.strategy);
+        self.concurrency("concurrency", &job.concurrency);
+        self.inputs("with", &job.with);
+        if let Some(Secrets::Env(secrets)) = &job.secrets {
+            self.inputs("secrets", secrets);
+        }
+        self.extra("", &job.extra);
+    }
+
+    fn step(&mut self, step: &Step) {
+        self.condition(&step.r#if);
+        if let Some(name) = &step.name {
+            self.string("name", name);
+        }
+        if let Some(timeout) = &step.timeout_minutes {
+            self.expr("timeout-minutes", timeout);
+        }
+        self.expr("continue-on-error", &step.continue_on_error);
+
+        match &step.body {
+            StepBody::Uses { with, env, .. } => {
+                self.inputs("with", with);
+                self.env("env", env);
+            }
+            StepBody::Run {
+                run,
+                working_directory,
+                shell,
+                env,
+            } => {
+                self.string("run", run);
+                if let Some(working_directory) = working_directory {
+                    self.string("working-directory", working_directory);
+                }
+                if let Some(Shell::Custom(shell)) = shell {
+                    self.string("shell", shell);
+                }
+                self.env("env", env);
+            }
+        }
+
+        self.extra("", &step.extra);
+    }
+
+    fn defaults(&mut self, key: &str, defaults: &Option<Defaults>) {
+        let Some(run) = defaults.as_ref().and_then(|defaults| defaults.run.as_ref()) else {
+            return;
+        };
+
+        if let Some(Shell::Custom(shell)) = &run.shell {
+            self.string(&format!("{key}.run.shell"), shell);
+        }
+        if let Some(working_directory) = &run.working_directory {
+            self.string(&format!("{key}.run.working-directory"), working_directory);
+        }
+    }
+
+    fn concurrency(&mut self, key: &str, concurrency: &Option<Concurrency>) {
+        match concurrency {
+            Some(Concurrency::Bare(group)) => self.string(key, group.as_str()),
+            Some(Concurrency::Rich {
+                group,
+                cancel_in_progress,
+            }) => {
+                self.string(&format!("{key}.group"), group.as_str());
+                self.expr(&format!("{key}.cancel-in-progress"), cancel_in_progress);
+            }
+            None => {}
+        }
+    }
+
+    fn strategy(&mut self, key: &str, strategy: &Option<Strategy>) {
+        let Some(strategy) = strategy else {
+            return;
+        };
+
+        let matrix_key = format!("{key}.matrix");
+        match &strategy.matrix {
+            Some(LoE::Expr(expr)) => self.string(&matrix_key, expr.as_raw()),
+            Some(LoE::Literal(matrix)) => {
+                for (name, entries) in [("include", &matrix.include), ("exclude", &matrix.exclude)]
+                {
+                    let key = format!("{matrix_key}.{name}");
+                    let Some(entries) = self.expr(&key, entries) else {
+                        continue;
+                    };
+                    for (index, entry) in entries.iter().enumerate() {
+                        self.extra(&format!("{key}[{index}]"), entry);
+                    }
+                }
+
+                if let Some(dimensions) = self.expr(&matrix_key, &matrix.dimensions) {
+                    for (name, values) in dimensions {
+                        let key = format!("{matrix_key}.{name}");
+                        if let Some(values) = self.expr(&key, values) {
+                            for (index, value) in values.iter().enumerate() {
+                                self.value(&format!("{key}[{index}]"), value);
+                            }
+                        }
+                    }
+                }
+            }
+            None => {}
+        }
+
+        if let Some(fail_fast) = &strategy.fail_fast {
+            self.expr(&format!("{key}.fail-fast"), fail_fast);
+        }
+        if let Some(max_parallel) = &strategy.max_parallel {
+            self.expr(&format!("{key}.max-parallel"), max_parallel);
+        }
+    }
+
+    fn container(&mut self, key: &str, container: &LoE<Container>) {
+        match container {
+            LoE::Expr(expr) => self.string(key, expr.as_raw()),
+            LoE::Literal(Container::Name(image)) => self.string(key, image),
+            LoE::Literal(Container::Container {
+                image,
+                credentials,
+                env,
+                ports,
+                volumes,
+                options,
+            }) => {
+                self.string(&format!("{key}.image"), image.as_str());
+                if let Some(credentials) = credentials {
+                    if let Some(username) = &credentials.username {
+                        self.string(&format!("{key}.credentials.username"), username.as_str());
+                    }
+                    if let Some(password) = &credentials.password {
+                        self.string(&format!("{key}.credentials.password"), password.as_str());
+                    }
+                }
+                self.env(&format!("{key}.env"), env);
+                for (index, port) in ports.iter().enumerate() {
+                    if let Port::String(port) = port {
+                        self.string(&format!("{key}.ports[{index}]"), port);
+                    }
+                }
+                self.strings(&format!("{key}.volumes"), volumes);
+                if let Some(options) = options {
+                    self.string(&format!("{key}.options"), options);
+                }
+            }
+        }
+    }
+
+    /// An `env:` mapping, which may be an expression itself.
+    fn env(&mut self, key: &str, env: &LoE<Env>) {
+        if let Some(env) = self.expr(key, env) {
+            self.inputs(key, env);
+        }
+    }
+
+    /// A mapping of string-like values, e.g. `with:` or a literal `env:`.
+    fn inputs(&mut self, key: &str, inputs: &Env) {
+        for (name, value) in inputs {
+            if let EnvValue::String(value) = value {
+                self.string(&format!("{key}.{name}"), value);
+            }
+        }
+    }
+
+    /// Untyped entries, e.g. unknown keys or matrix include rows.
+    fn extra(&mut self, key: &str, extra: &IndexMap<String, Value>) {
+        for (name, value) in extra {
+            match key {
+                "" => self.value(name, value),
+                key => self.value(&format!("{key}.{name}"), value),
+            }
+        }
+    }
+
+    fn value(&mut self, key: &str, value: &Value) {
+        for (key, expression) in crate::common::find_expressions(value, key) {
+            self.push(key, expression);
+        }
+    }
+
+    /// A value that may be an expression, returning its literal otherwise.
+    fn expr<'v, T>(&mut self, key: &str, value: &'v LoE<T>) -> Option<&'v T> {
+        match value {
+            LoE::Expr(expr) => {
+                self.string(key, expr.as_raw());
+                None
+            }
+            LoE::Literal(value) => Some(value),
+        }
+    }
+
+    /// An `if:` condition, which is an expression even without `${{ }}`.
+    fn condition(&mut self, condition: &Option<If>) {
+        let Some(If::Expr(condition)) = condition else {
+            return;
+        };
+
+        let interpolations = expr::interpolations(condition);
+        if interpolations.is_empty() {
+            if !condition.trim().is_empty() {
+                self.push("if".into(), condition.trim().into());
+            }
+        } else {
+            for (_, expression) in interpolations {
+                self.push("if".into(), expression.into());
+            }
+        }
+    }
+
+    fn strings(&mut self, key: &str, values: &[String]) {
+        for (index, value) in values.iter().enumerate() {
+            self.string(&format!("{key}[{index}]"), value);
+        }
+    }
+
+    fn string(&mut self, key: &str, value: &str) {
+        for (_, expression) in expr::interpolations(value) {
+            self.push(key.into(), expression.into());
+        }
+    }
+
+    fn push(&mut self, key: String, expression: String) {
+        self.found.push(WorkflowExpression {
+            location: ExpressionLocation {
+                job_id: self.job_id,
+                step_index: self.step_index,
+                key,
+            },
+            expression,
+        });
+    }
+}
@@ -0,0 +1,337 @@
+//! Job dependency graphs.
+
+use std::collections::VecDeque;
+
+use indexmap::IndexMap;
+
+use super::{CycleError, Job};
+
+/// A workflow's job dependency graph, as built from each job's `needs`.
+///
+/// Nodes are identified by their job IDs, and are ordered by declaration
+/// wherever an order isn't otherwise implied by the graph.
+///
+/// `needs` entries that don't name a job in the workflow don't contribute
+/// edges; they're reported by [`DependencyGraph::unknown_needs`] instead.
+#[derive(Clone)]
+pub struct DependencyGraph<'a> {
+    jobs: &'a IndexMap<String, Job>,
+    /// For each job (by index), the indices of the jobs it needs.
+    needs: Vec<Vec<usize>>,
+    unknown_needs: Vec<UnknownNeed<'a>>,
+}
+
+/// A `needs` entry that doesn't name a job in the workflow, as returned
+/// by [`DependencyGraph::unknown_needs`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownNeed<'a> {
+    /// The ID of the job with the `needs` entry.
+    pub job_id: &'a str,
+    /// The unknown job ID it needs.
+    pub need: &'a str,
+}
+
+impl<'a> DependencyGraph<'a> {
+    pub(crate) fn new(jobs: &'a IndexMap<String, Job>) -> Self {
+        let mut needs = vec![vec![]; jobs.len()];
+        let mut unknown_needs = vec![];
+
+        for (idx, (job_id, job)) in jobs.iter().enumerate() {
+            for need in job.needs() {
+                match jobs.get_index_of(need) {
+                    Some(need_idx) if !needs[idx].contains(&need_idx) => needs[idx].push(need_idx),
+                    Some(_) => {}
+                    None => unknown_needs.push(UnknownNeed { job_id, need }),
+                }
+            }
+        }
+
+        Self {
+            jobs,
+            needs,
+            unknown_needs,
+        }
+    }
+
+    fn id(&self, idx: usize) -> &'a str {
+        self.jobs.get_index(idx).unwrap().0
+    }
+
+    /// Returns the IDs of the jobs that can start immediately, i.e. that
+    /// don't need any other job in the workflow.
+    pub fn roots(&self) -> Vec<&'a str> {
+        self.needs
+            .iter()
+            .enumerate()
+            .filter(|(_, needs)| needs.is_empty())
+            .map(|(idx, _)| self.id(idx))
+            .collect()
+    }
+
+    /// Returns the IDs of the jobs that `job_id` needs, or `None` if
+    /// there's no such job.
+    pub fn needs(&self, job_id: &str) -> Option<Vec<&'a str>> {
+        let idx = self.jobs.get_index_of(job_id)?;
+        Some(self.needs[idx].iter().map(|idx| self.id(*idx)).collect())
+    }
+
+    /// Returns the IDs of the jobs that need `job_id`, or `None` if
+    /// there's no such job.
+    pub fn dependents(&self, job_id: &str) -> Option<Vec<&'a str>> {
+        let idx = self.jobs.get_index_of(job_id)?;
+        Some(
+            self.needs
+                .iter()
+                .enumerate()
+                .filter(|(_, needs)| needs.contains(&idx))
+                .map(|(dependent, _)| self.id(dependent))
+                .collect(),
+        )
+    }
+
+    /// Returns each `needs` entry that doesn't name a job in the workflow.
+    pub fn unknown_needs(&self) -> &[UnknownNeed<'a>] {
+        &self.unknown_needs
+    }
+
+    /// Returns the job IDs ordered so that each job comes after every job
+    /// it needs.
+    ///
+    /// Jobs with no needs come first, and ties are otherwise broken by
+    /// declaration order.
+    ///
+    /// Returns a [`CycleError`] for one of the cycles if the graph has
+    /// any; see [`DependencyGraph::cycles`] for all of them.
+    pub fn topological_order(&self) -> Result<Vec<&'a str>, CycleError> {
+        // Kahn's algorithm, with indices into `self.jobs` as node IDs.
+        let mut in_degree = self.needs.iter().map(Vec::len).collect::<Vec<_>>();
+        let mut dependents = vec![vec![]; self.jobs.len()];
+        for (idx, needs) in self.needs.iter().enumerate() {
+            for &need_idx in needs {
+                dependents[need_idx].push(idx);
+            }
+        }
+
+        let mut queue = in_degree
+            .iter()
+            .enumerate()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(idx, _)| idx)
+            .collect::<VecDeque<_>>();
+        let mut order = Vec::with_capacity(self.jobs.len());
+        while let Some(idx) = queue.pop_front() {
+            order.push(self.id(idx));
+            for &dependent in &dependents[idx] {
+                in_degree[dependent] -= 1;
+                if in_degree[dependent] == 0 {
+                    queue.push_back(dependent);
+                }
+            }
+        }
+
+        if order.len() < self.jobs.len() {
+            // Every remaining job needs at least one other remaining job,
+            // so following those needs from any of them must find a cycle.
+            let start = in_degree.iter().position(|degree| *degree > 0).unwrap();
+            return Err(self.find_cycle(start, |idx| in_degree[idx] > 0));
+        }
+
+        Ok(order)
+    }
+
+    /// Returns a cycle for each group of jobs that (transitively) need
+    /// each other, ordered by each group's first declared job.
+    ///
+    /// Each group is reported once, even if it contains several distinct
+    /// cycles. A job that needs itself is a cycle of its own.
+    pub fn cycles(&self) -> Vec<CycleError> {
+        // Tarjan's strongly connected components algorithm.
+        struct State {
+            index: usize,
+            indices: Vec<Option<usize>>,
+            low_links: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            components: Vec<Vec<usize>>,
+        }
+
+        fn connect(needs: &[Vec<usize>], state: &mut State, idx: usize) {
+            state.indices[idx] = Some(state.index);
+            state.low_links[idx] = state.index;
+            state.index += 1;
+            state.stack.push(idx);
+            state.on_stack[idx] = true;
+
+            for &need in &needs[idx] {
+                match state.indices[need] {
+                    None => {
+                        connect(needs, state, need);
+                        state.low_links[idx] = state.low_links[idx].min(state.low_links[need]);
+                    }
+                    Some(need_index) if state.on_stack[need] => {
+                        state.low_links[idx] = state.low_links[idx].min(need_index);
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            if Some(state.low_links[idx]) == state.indices[idx] {
+                let mut component = vec![];
+                loop {
+                    let member = state.stack.pop().unwrap();
+                    state.on_stack[member] = false;
+                    component.push(member);
+                    if member == idx {
+                        break;
+                    }
+                }
+                state.components.push(component);
+            }
+        }
+
+        let len = self.jobs.len();
+        let mut state = State {
+            index: 0,
+            indices: vec![None; len],
+            low_links: vec![0; len],
+            stack: vec![],
+            on_stack: vec![false; len],
+            components: vec![],
+        };
+        for idx in 0..len {
+            if state.indices[idx].is_none() {
+                connect(&self.needs, &mut state, idx);
+            }
+        }
+
+        let mut components = state
+            .components
+            .into_iter()
+            .filter(|component| match component.as_slice() {
+                [idx] => self.needs[*idx].contains(idx),
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+        for component in &mut components {
+            component.sort_unstable();
+        }
+        components.sort_unstable_by_key(|component| component[0]);
+
+        components
+            .into_iter()
+            .map(|component| self.find_cycle(component[0], |idx| component.contains(&idx)))
+            .collect()
+    }
+
+    /// Follow needs from `start` through jobs satisfying `within` until a
+    /// job repeats, and return the cycle found.
+    ///
+    /// Every job satisfying `within` must need at least one other such job.
+    fn find_cycle(&self, start: usize, within: impl Fn(usize) -> bool) -> CycleError {
+        let mut walk: Vec<usize> = vec![];
+        let mut current = start;
+        while !walk.contains(&current) {
+            walk.push(current);
+            current = *self.needs[current]
+                .iter()
+                .find(|need_idx| within(**need_idx))
+                .unwrap();
+        }
+        let start = walk.iter().position(|idx| *idx == current).unwrap();
+
+        CycleError {
+            jobs: walk[start..]
+                .iter()
+                .map(|idx| self.id(*idx).to_string())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::workflow::Workflow;
+
+    use super::UnknownNeed;
+
+    fn workflow(jobs: &[(&str, &str)]) -> Workflow {
+        let jobs = jobs
+            .iter()
+            .map(|(id, needs)| {
+                format!(
+                    "  {id}:\n    runs-on: ubuntu-latest\n    needs: [{needs}]\n    steps: []\n"
+                )
+            })
+            .collect::<String>();
+        format!("on: push\njobs:\n{jobs}").parse().unwrap()
+    }
+
+    #[test]
+    fn test_diamond() {
+        let workflow = workflow(&[
+            ("d", "b, c"),
+            ("b", "a"),
+            ("c", "a"),
+            ("a", ""),
+            ("lint", ""),
+        ]);
+        let graph = workflow.dependency_graph();
+
+        assert_eq!(graph.roots(), ["a", "lint"]);
+        assert_eq!(
+            graph.topological_order().unwrap(),
+            ["a", "lint", "b", "c", "d"]
+        );
+        assert!(graph.cycles().is_empty());
+        assert!(graph.unknown_needs().is_empty());
+
+        assert_eq!(graph.needs("d").unwrap(), ["b", "c"]);
+        assert_eq!(graph.dependents("a").unwrap(), ["b", "c"]);
+        assert!(graph.dependents("d").unwrap().is_empty());
+        assert!(graph.needs("missing").is_none());
+    }
+
+    #[test]
+    fn test_cycles() {
+        let workflow = workflow(&[
+            ("setup", ""),
+            ("a", "setup, c"),
+            ("b", "a"),
+            ("c", "b"),
+            ("after", "c"),
+            ("self", "self"),
+        ]);
+        let graph = workflow.dependency_graph();
+
+        assert_eq!(graph.roots(), ["setup"]);
+        assert_eq!(graph.topological_order().unwrap_err().jobs, ["a", "c", "b"]);
+
+        let cycles = graph.cycles();
+        assert_eq!(cycles.len(), 2);
+        assert_eq!(cycles[0].jobs, ["a", "c", "b"]);
+        assert_eq!(cycles[1].to_string(), "job dependency cycle: self -> self");
+    }
+
+    #[test]
+    fn test_unknown_needs() {
+        let workflow = workflow(&[("a", "missing"), ("b", "a, typo")]);
+        let graph = workflow.dependency_graph();
+
+        assert_eq!(
+            graph.unknown_needs(),
+            [
+                UnknownNeed {
+                    job_id: "a",
+                    need: "missing"
+                },
+                UnknownNeed {
+                    job_id: "b",
+                    need: "typo"
+                },
+            ]
+        );
+        // Unknown needs don't contribute edges.
+        assert_eq!(graph.roots(), ["a"]);
+        assert_eq!(graph.topological_order().unwrap(), ["a", "b"]);
+    }
+}
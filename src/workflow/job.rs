@@ -1,58 +1,816 @@
 //! Workflow jobs.
 
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
 use indexmap::IndexMap;
-use serde::{de, Deserialize};
-use serde_yaml::Value;
+use serde::{de, ser::SerializeMap, Deserialize, Deserializer, Serialize};
+use serde_yaml::{
+    value::{Tag, TaggedValue},
+    Value,
+};
 
-use crate::common::expr::{BoE, LoE};
-use crate::common::{Env, If, Permissions, Uses};
+use crate::common::expr::{self, BoE, ContextPath, ExplicitExpr, LoE, Segment, TemplatePart};
+use crate::common::{
+    diagnostic::{self, Diagnostic},
+    BasePermission, DockerUses, Env, EnvValue, If, Permission, Permissions, RepositoryUses, Shell,
+    Uses, UsesError,
+};
 
 use super::{Concurrency, Defaults};
 
 /// A "normal" GitHub Actions workflow job, i.e. a job composed of one
 /// or more steps on a runner.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct NormalJob {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
-    #[serde(default)]
-    pub permissions: Permissions,
-    #[serde(default, deserialize_with = "crate::common::scalar_or_vector")]
+    /// This job's `permissions`, or `None` if it inherits the workflow's.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
+    #[serde(
+        default,
+        deserialize_with = "crate::common::scalar_or_vector",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub needs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub r#if: Option<If>,
     pub runs_on: LoE<RunsOn>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub environment: Option<DeploymentEnvironment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub concurrency: Option<Concurrency>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub outputs: IndexMap<String, String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "crate::common::is_default")]
     pub env: LoE<Env>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub defaults: Option<Defaults>,
     pub steps: Vec<Step>,
-    pub timeout_minutes: Option<LoE<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_minutes: Option<LoE<Minutes>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub strategy: Option<Strategy>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "crate::common::is_default")]
     pub continue_on_error: BoE,
-    pub container: Option<Container>,
-    #[serde(default)]
-    pub services: IndexMap<String, Container>,
+    #[serde(
+        default,
+        deserialize_with = "container",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub container: Option<LoE<Container>>,
+    #[serde(
+        default,
+        deserialize_with = "services",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub services: IndexMap<String, LoE<Container>>,
+
+    /// Any other keys in this job.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl NormalJob {
+    /// Returns the step with the given `id`, if present.
+    ///
+    /// If multiple steps share the same `id`, the first is returned.
+    pub fn step_by_id(&self, id: &str) -> Option<&Step> {
+        self.steps
+            .iter()
+            .find(|step| step.id.as_deref() == Some(id))
+    }
+
+    /// Returns an iterator over each step that has an `id`, along
+    /// with that `id`.
+    pub fn steps_with_ids(&self) -> impl Iterator<Item = (&str, &Step)> {
+        self.steps
+            .iter()
+            .filter_map(|step| step.id.as_deref().map(|id| (id, step)))
+    }
+
+    /// Returns an [`IndexedStep`] for each step in this job, in order.
+    pub fn indexed_steps(&self) -> impl Iterator<Item = IndexedStep<'_>> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| IndexedStep { index, step })
+    }
+
+    /// Returns whether every step `id` in this job is unique, i.e. whether
+    /// [`NormalJob::duplicate_step_ids`] finds nothing.
+    ///
+    /// Duplicate step IDs break `steps.<id>` context references.
+    pub fn step_ids_are_unique(&self) -> bool {
+        self.duplicate_step_ids().is_empty()
+    }
+
+    /// Returns the shell that `step` runs with, following GitHub's
+    /// fallback chain: the step's `shell`, then this job's
+    /// `defaults.run.shell`, then `workflow_defaults.run.shell`.
+    ///
+    /// Returns `None` if `step` isn't a `run:` step, or if none of those
+    /// set a shell, in which case the runner's default shell is used
+    /// (`bash` or `sh` on Linux and macOS, `pwsh` on Windows).
+    pub fn effective_shell<'a>(
+        &'a self,
+        step: &'a Step,
+        workflow_defaults: Option<&'a Defaults>,
+    ) -> Option<&'a Shell> {
+        let StepBody::Run { shell, .. } = &step.body else {
+            return None;
+        };
+
+        shell.as_ref().or_else(|| {
+            [self.defaults.as_ref(), workflow_defaults]
+                .into_iter()
+                .flatten()
+                .find_map(|defaults| defaults.run.as_ref()?.shell.as_ref())
+        })
+    }
+
+    /// Returns the working directory that `step` runs in, following the
+    /// same fallback chain as [`NormalJob::effective_shell`] for
+    /// `working-directory`.
+    ///
+    /// Returns `None` if `step` isn't a `run:` step, or if none of those
+    /// set a working directory, in which case the step runs in the
+    /// workspace directory.
+    pub fn effective_working_directory<'a>(
+        &'a self,
+        step: &'a Step,
+        workflow_defaults: Option<&'a Defaults>,
+    ) -> Option<&'a str> {
+        let StepBody::Run {
+            working_directory, ..
+        } = &step.body
+        else {
+            return None;
+        };
+
+        working_directory.as_deref().or_else(|| {
+            [self.defaults.as_ref(), workflow_defaults]
+                .into_iter()
+                .flatten()
+                .find_map(|defaults| defaults.run.as_ref()?.working_directory.as_deref())
+        })
+    }
+
+    /// Check this job for step `id`s that GitHub rejects because an
+    /// earlier step already uses them, returning a
+    /// [`DUPLICATE_STEP_ID`](diagnostic::DUPLICATE_STEP_ID) diagnostic for
+    /// each such step.
+    ///
+    /// IDs are compared case-sensitively. Steps without an `id` are ignored.
+    ///
+    /// Each diagnostic's path is relative to this job, and points at the
+    /// later step's `id`; see [`Workflow::validate_step_ids`] for paths
+    /// relative to the workflow.
+    ///
+    /// [`Workflow::validate_step_ids`]: super::Workflow::validate_step_ids
+    pub fn duplicate_step_ids(&self) -> Vec<Diagnostic> {
+        let mut first_index: HashMap<&str, usize> = HashMap::new();
+        let mut diagnostics = vec![];
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let Some(id) = step.id.as_deref() else {
+                continue;
+            };
+
+            match first_index.get(id) {
+                Some(first) => diagnostics.push(Diagnostic::new(
+                    diagnostic::DUPLICATE_STEP_ID,
+                    ["steps", &index.to_string(), "id"],
+                    format!("step ID `{id}` is already used by step {first}"),
+                )),
+                None => {
+                    first_index.insert(id, index);
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Returns the names of any outputs that can't be referenced via
+    /// `needs.<job>.outputs.<name>`, since they aren't identifiers
+    /// (e.g. `foo.bar`).
+    pub(crate) fn unreachable_output_names(&self) -> impl Iterator<Item = &str> {
+        self.outputs
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !crate::common::is_identifier(name))
+    }
+
+    /// Returns the output `name`'s value, split into its literal text and
+    /// interpolated expressions.
+    pub fn output_parts(&self, name: &str) -> Option<Vec<TemplatePart<'_>>> {
+        self.outputs
+            .get(name)
+            .map(|value| expr::template_parts(value))
+    }
+
+    /// Returns each `steps.<id>.outputs.<name>` reference in this job's
+    /// `outputs`, in declaration order.
+    ///
+    /// References aren't checked against the job's steps, so callers can
+    /// validate them with [`NormalJob::step_by_id`]. Outputs with literal
+    /// values contribute no references, and outputs that interpolate
+    /// multiple expressions may contribute several.
+    ///
+    /// ```
+    /// use github_actions_models::workflow::job::NormalJob;
+    ///
+    /// let job: NormalJob = serde_yaml::from_str("
+    /// runs-on: ubuntu-latest
+    /// outputs:
+    ///   image: ${{ steps.build.outputs.name }}@${{ steps.build.outputs.digest }}
+    /// steps:
+    ///   - id: build
+    ///     run: ./build.sh
+    /// ").unwrap();
+    ///
+    /// let refs = job.output_references();
+    /// assert_eq!(refs.len(), 2);
+    /// assert_eq!(refs[1].step_id, "build");
+    /// assert_eq!(refs[1].name, "digest");
+    /// assert!(job.step_by_id(&refs[1].step_id).is_some());
+    /// ```
+    pub fn output_references(&self) -> Vec<StepOutputReference<'_>> {
+        fn name_of(segment: &Segment) -> Option<&str> {
+            match segment {
+                Segment::Ident(name) | Segment::Key(name) => Some(name),
+                _ => None,
+            }
+        }
+
+        let mut references = vec![];
+
+        for (output, value) in &self.outputs {
+            for (_, body) in expr::interpolations(value) {
+                let paths = expr::context_paths(body)
+                    .into_iter()
+                    .filter_map(ContextPath::parse);
+
+                for path in paths {
+                    let [steps, step_id, outputs, name, ..] = path.segments() else {
+                        continue;
+                    };
+                    let (Some(steps), Some(step_id), Some(outputs), Some(name)) = (
+                        name_of(steps),
+                        name_of(step_id),
+                        name_of(outputs),
+                        name_of(name),
+                    ) else {
+                        continue;
+                    };
+
+                    if steps.eq_ignore_ascii_case("steps")
+                        && outputs.eq_ignore_ascii_case("outputs")
+                    {
+                        references.push(StepOutputReference {
+                            output,
+                            step_id: step_id.into(),
+                            name: name.into(),
+                        });
+                    }
+                }
+            }
+        }
+
+        references
+    }
+
+    /// Returns a summary of this job's deployment `environment`, if it has one.
+    ///
+    /// ```
+    /// use github_actions_models::workflow::{job::NameKind, Job, Workflow};
+    ///
+    /// let workflow: Workflow = "
+    /// on: push
+    /// jobs:
+    ///   deploy:
+    ///     runs-on: ubuntu-latest
+    ///     if: github.ref == 'refs/heads/main'
+    ///     environment: production
+    ///     steps:
+    ///       - run: ./deploy.sh
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// let Job::NormalJob(job) = &workflow.jobs["deploy"] else { unreachable!() };
+    /// let gate = job.environment_gate().unwrap();
+    /// assert_eq!(gate.name, NameKind::Literal("production"));
+    /// assert_eq!(gate.required_refs, ["refs/heads/main"]);
+    /// ```
+    pub fn environment_gate(&self) -> Option<EnvironmentGate<'_>> {
+        let environment = self.environment.as_ref()?;
+
+        let name = if environment.is_dynamic() {
+            NameKind::Expression(environment.name())
+        } else {
+            NameKind::Literal(environment.name())
+        };
+        let url = environment.url();
+
+        let required_refs = match &self.r#if {
+            Some(cond @ If::Expr(_)) => expr::comparisons(cond.normalized())
+                .into_iter()
+                .filter(|cmp| cmp.equal && cmp.context.eq_ignore_ascii_case("github.ref"))
+                .map(|cmp| cmp.literal)
+                .collect(),
+            _ => vec![],
+        };
+
+        Some(EnvironmentGate {
+            name,
+            url,
+            required_refs,
+        })
+    }
+
+    /// Returns whether this job deploys to an `environment`.
+    pub fn uses_environment(&self) -> bool {
+        self.environment.is_some()
+    }
+
+    /// Returns the name of this job's deployment `environment`, exactly as
+    /// written, if it has one.
+    pub fn environment_name(&self) -> Option<&str> {
+        self.environment.as_ref().map(DeploymentEnvironment::name)
+    }
+
+    /// Returns whether this job's own `permissions` grant write access to
+    /// the given `scope`.
+    ///
+    /// See [`Permissions::has_write_access_to`] for the caveats.
+    pub fn has_write_permission(&self, scope: &str) -> bool {
+        self.permissions
+            .as_ref()
+            .is_some_and(|permissions| permissions.has_write_access_to(scope))
+    }
+
+    /// Returns whether this job deploys to an `environment` while its own
+    /// `permissions` grant write access to any scope.
+    ///
+    /// Environments can hold secrets gated behind protection rules, so
+    /// these jobs warrant extra scrutiny.
+    pub fn uses_privileged_environment(&self) -> bool {
+        let writes = match &self.permissions {
+            Some(Permissions::Base(base)) => *base == BasePermission::WriteAll,
+            Some(Permissions::Explicit(perms)) => {
+                perms.values().any(|perm| *perm == Permission::Write)
+            }
+            None => false,
+        };
+
+        self.uses_environment() && writes
+    }
+
+    /// Returns the body of every expression in this job that may reference
+    /// other jobs, e.g. `needs.<job>.outputs.<name>`.
+    ///
+    /// `if:` conditions are expressions as a whole; everywhere else, only
+    /// the contents of `${{ }}` are.
+    pub(crate) fn expression_bodies(&self) -> Vec<&str> {
+        fn string(value: &EnvValue) -> Option<&str> {
+            match value {
+                EnvValue::String(s) => Some(s),
+                _ => None,
+            }
+        }
+
+        let mut conditions = vec![];
+        let mut strings = vec![];
+
+        if let Some(cond @ If::Expr(_)) = &self.r#if {
+            conditions.push(cond);
+        }
+        strings.extend(self.outputs.values().map(String::as_str));
+        if let LoE::Literal(env) = &self.env {
+            strings.extend(env.values().filter_map(string));
+        }
+
+        for container in self.container.iter().chain(self.services.values()) {
+            let LoE::Literal(container) = container else {
+                continue;
+            };
+            strings.push(container.image());
+            if let Container::Container {
+                env: LoE::Literal(env),
+                ..
+            } = container
+            {
+                strings.extend(env.values().filter_map(string));
+            }
+        }
+
+        for step in &self.steps {
+            if let Some(cond @ If::Expr(_)) = &step.r#if {
+                conditions.push(cond);
+            }
+            strings.extend(step.name.as_deref());
+            match &step.body {
+                StepBody::Uses { with, env, .. } => {
+                    strings.extend(with.values().filter_map(string));
+                    if let LoE::Literal(env) = env {
+                        strings.extend(env.values().filter_map(string));
+                    }
+                }
+                StepBody::Run { run, env, .. } => {
+                    strings.push(run.as_str());
+                    if let LoE::Literal(env) = env {
+                        strings.extend(env.values().filter_map(string));
+                    }
+                }
+            }
+        }
+
+        expression_bodies(conditions, strings)
+    }
+}
+
+/// Returns the body of each of `conditions`, followed by the body of each
+/// `${{ }}` expression in `strings`.
+fn expression_bodies<'a>(
+    conditions: impl IntoIterator<Item = &'a If>,
+    strings: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    conditions
+        .into_iter()
+        .map(If::normalized)
+        .chain(
+            strings
+                .into_iter()
+                .flat_map(expr::interpolations)
+                .map(|(_, body)| body),
+        )
+        .collect()
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "kebab-case", untagged, remote = "Self")]
 pub enum RunsOn {
     #[serde(deserialize_with = "crate::common::scalar_or_vector")]
     Target(Vec<String>),
     Group {
+        #[serde(skip_serializing_if = "Option::is_none")]
         group: Option<String>,
         // NOTE(ww): serde struggles with the null/empty case for custom
         // deserializers, so we help it out by telling it that it can default
         // to Vec::default.
-        #[serde(deserialize_with = "crate::common::scalar_or_vector", default)]
+        #[serde(
+            deserialize_with = "crate::common::scalar_or_vector",
+            default,
+            skip_serializing_if = "Vec::is_empty"
+        )]
         labels: Vec<String>,
     },
 }
 
+impl RunsOn {
+    /// Returns the runner labels targeted by this `runs-on`, regardless
+    /// of whether it's a bare list of labels or a runner group.
+    ///
+    /// A runner group with no labels returns an empty slice.
+    pub fn effective_labels(&self) -> &[String] {
+        match self {
+            RunsOn::Target(labels) => labels,
+            RunsOn::Group { labels, .. } => labels,
+        }
+    }
+
+    /// Returns whether this `runs-on` targets the given label,
+    /// e.g. `self-hosted`.
+    pub fn contains_label(&self, label: &str) -> bool {
+        self.effective_labels().iter().any(|l| l == label)
+    }
+
+    /// Returns the runner group targeted by this `runs-on`, if any.
+    pub fn group(&self) -> Option<&str> {
+        match self {
+            RunsOn::Target(_) => None,
+            RunsOn::Group { group, .. } => group.as_deref(),
+        }
+    }
+
+    /// Classifies the kind of runner targeted by this `runs-on`.
+    ///
+    /// Runner groups and label sets containing `self-hosted` are
+    /// self-hosted. Label sets made up entirely of [`KnownRunner`] labels
+    /// and default larger runner labels (like `ubuntu-latest-16-cores`)
+    /// are GitHub-hosted. Anything else,
+    /// including labels containing expressions, can't be classified
+    /// statically.
+    pub fn runner_kind(&self) -> RunnerKind {
+        let labels = match self {
+            RunsOn::Group { .. } => return RunnerKind::SelfHosted,
+            RunsOn::Target(labels) => labels,
+        };
+
+        if labels.iter().any(|l| l.eq_ignore_ascii_case("self-hosted")) {
+            RunnerKind::SelfHosted
+        } else if !labels.is_empty() && labels.iter().all(|l| is_github_hosted_label(l)) {
+            RunnerKind::GitHubHosted
+        } else {
+            RunnerKind::Unknown
+        }
+    }
+
+    /// Returns whether this `runs-on` targets a self-hosted runner,
+    /// i.e. uses a runner group or the `self-hosted` label.
+    pub fn is_self_hosted(&self) -> bool {
+        self.runner_kind() == RunnerKind::SelfHosted
+    }
+
+    /// Returns whether this `runs-on` definitely targets a GitHub-hosted runner.
+    pub fn is_github_hosted(&self) -> bool {
+        self.runner_kind() == RunnerKind::GitHubHosted
+    }
+
+    /// Returns each of this `runs-on`'s labels, classified by whether
+    /// they contain expressions.
+    pub fn classified_labels(&self) -> impl Iterator<Item = RunnerLabel<'_>> {
+        self.effective_labels()
+            .iter()
+            .map(|label| RunnerLabel::classify(label))
+    }
+
+    /// Returns whether any of this `runs-on`'s labels, or its runner group,
+    /// contain an expression (e.g. `ubuntu-${{ matrix.version }}`).
+    ///
+    /// Such labels can't be treated as literal runner names.
+    pub fn has_embedded_expressions(&self) -> bool {
+        self.group()
+            .into_iter()
+            .chain(self.effective_labels().iter().map(String::as_str))
+            .any(|label| !matches!(RunnerLabel::classify(label), RunnerLabel::Literal(_)))
+    }
+
+    /// Returns each of this `runs-on`'s labels as a [`KnownRunner`].
+    ///
+    /// Labels that aren't standard GitHub-hosted runner labels, including
+    /// `self-hosted` and custom larger runner names, are [`KnownRunner::Other`].
+    pub fn known_runners(&self) -> impl Iterator<Item = KnownRunner> + '_ {
+        self.effective_labels()
+            .iter()
+            .map(|label| match label.parse() {
+                Ok(runner) => runner,
+                Err(never) => match never {},
+            })
+    }
+}
+
+/// Returns whether `label` is a GitHub-hosted runner label: either a
+/// [`KnownRunner`], or a larger runner label (see [`is_larger_runner_label`]).
+fn is_github_hosted_label(label: &str) -> bool {
+    let known = match label.parse() {
+        Ok(runner) => !matches!(runner, KnownRunner::Other(_)),
+        Err(never) => match never {},
+    };
+
+    known || is_larger_runner_label(label)
+}
+
+/// Returns whether `label` is a larger runner label in GitHub's default
+/// `<os>-<version>-<cores>-cores` or `<os>-<version>-<cores>core` form,
+/// e.g. `ubuntu-latest-16-cores` or `ubuntu-22.04-4core`.
+fn is_larger_runner_label(label: &str) -> bool {
+    let label = label.to_ascii_lowercase();
+    let Some(rest) = label
+        .strip_suffix("-cores")
+        .or_else(|| label.strip_suffix("core"))
+    else {
+        return false;
+    };
+    let Some((image, cores)) = rest.rsplit_once('-') else {
+        return false;
+    };
+    let Some((os, version)) = image.split_once('-') else {
+        return false;
+    };
+
+    !cores.is_empty()
+        && cores.bytes().all(|b| b.is_ascii_digit())
+        && matches!(os, "ubuntu" | "windows")
+        && (version == "latest"
+            || (!version.is_empty() && version.bytes().all(|b| b.is_ascii_digit() || b == b'.')))
+}
+
+/// A single `runs-on` label, classified by whether it contains expressions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunnerLabel<'a> {
+    /// A literal label, e.g. `ubuntu-latest`.
+    Literal(&'a str),
+    /// A label that's entirely an expression, e.g. `${{ matrix.os }}`.
+    /// The expression's body is stored.
+    WholeExpression(&'a str),
+    /// A label with one or more expressions embedded in literal text,
+    /// e.g. `ubuntu-${{ matrix.version }}`.
+    Template { parts: Vec<expr::TemplatePart<'a>> },
+}
+
+impl<'a> RunnerLabel<'a> {
+    fn classify(label: &'a str) -> Self {
+        match expr::template_parts(label).as_slice() {
+            [] | [expr::TemplatePart::Literal(_)] => Self::Literal(label),
+            [expr::TemplatePart::Expr(body)] => Self::WholeExpression(body),
+            parts => Self::Template {
+                parts: parts.to_vec(),
+            },
+        }
+    }
+}
+
+/// A standard GitHub-hosted runner label.
+///
+/// See [GitHub-hosted runners] for the current set of labels.
+///
+/// [GitHub-hosted runners]: https://docs.github.com/en/actions/using-github-hosted-runners/using-github-hosted-runners/about-github-hosted-runners
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KnownRunner {
+    /// `ubuntu-latest`
+    UbuntuLatest,
+    /// `ubuntu-24.04`
+    Ubuntu2404,
+    /// `ubuntu-22.04`
+    Ubuntu2204,
+    /// `ubuntu-24.04-arm`
+    Ubuntu2404Arm,
+    /// `ubuntu-22.04-arm`
+    Ubuntu2204Arm,
+    /// `windows-latest`
+    WindowsLatest,
+    /// `windows-2025`
+    Windows2025,
+    /// `windows-2022`
+    Windows2022,
+    /// `windows-11-arm`
+    Windows11Arm,
+    /// `macos-latest`
+    MacosLatest,
+    /// `macos-15`
+    Macos15,
+    /// `macos-14`
+    Macos14,
+    /// `macos-13`
+    Macos13,
+    /// `macos-latest-large`
+    MacosLatestLarge,
+    /// `macos-15-large`
+    Macos15Large,
+    /// `macos-14-large`
+    Macos14Large,
+    /// `macos-13-large`
+    Macos13Large,
+    /// `macos-latest-xlarge`
+    MacosLatestXlarge,
+    /// `macos-15-xlarge`
+    Macos15Xlarge,
+    /// `macos-14-xlarge`
+    Macos14Xlarge,
+    /// `macos-13-xlarge`
+    Macos13Xlarge,
+    /// Any other label, e.g. `self-hosted` or a custom larger runner name.
+    Other(String),
+}
+
+impl KnownRunner {
+    /// Returns the operating system this runner implies, if known.
+    pub fn os(&self) -> Option<RunnerOs> {
+        use KnownRunner::*;
+
+        match self {
+            UbuntuLatest | Ubuntu2404 | Ubuntu2204 | Ubuntu2404Arm | Ubuntu2204Arm => {
+                Some(RunnerOs::Linux)
+            }
+            WindowsLatest | Windows2025 | Windows2022 | Windows11Arm => Some(RunnerOs::Windows),
+            MacosLatest | Macos15 | Macos14 | Macos13 | MacosLatestLarge | Macos15Large
+            | Macos14Large | Macos13Large | MacosLatestXlarge | Macos15Xlarge | Macos14Xlarge
+            | Macos13Xlarge => Some(RunnerOs::MacOs),
+            Other(_) => None,
+        }
+    }
+
+    /// Returns the CPU architecture this runner implies, if known.
+    pub fn arch(&self) -> Option<RunnerArch> {
+        use KnownRunner::*;
+
+        match self {
+            UbuntuLatest | Ubuntu2404 | Ubuntu2204 | WindowsLatest | Windows2025 | Windows2022
+            | Macos13 | MacosLatestLarge | Macos15Large | Macos14Large | Macos13Large => {
+                Some(RunnerArch::X64)
+            }
+            Ubuntu2404Arm | Ubuntu2204Arm | Windows11Arm | MacosLatest | Macos15 | Macos14
+            | MacosLatestXlarge | Macos15Xlarge | Macos14Xlarge | Macos13Xlarge => {
+                Some(RunnerArch::Arm64)
+            }
+            Other(_) => None,
+        }
+    }
+}
+
+impl FromStr for KnownRunner {
+    type Err = std::convert::Infallible;
+
+    /// Parse a runner label, case-insensitively. Unknown labels become
+    /// [`KnownRunner::Other`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use KnownRunner::*;
+
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "ubuntu-latest" => UbuntuLatest,
+            "ubuntu-24.04" => Ubuntu2404,
+            "ubuntu-22.04" => Ubuntu2204,
+            "ubuntu-24.04-arm" => Ubuntu2404Arm,
+            "ubuntu-22.04-arm" => Ubuntu2204Arm,
+            "windows-latest" => WindowsLatest,
+            "windows-2025" => Windows2025,
+            "windows-2022" => Windows2022,
+            "windows-11-arm" => Windows11Arm,
+            "macos-latest" => MacosLatest,
+            "macos-15" => Macos15,
+            "macos-14" => Macos14,
+            "macos-13" => Macos13,
+            "macos-latest-large" => MacosLatestLarge,
+            "macos-15-large" => Macos15Large,
+            "macos-14-large" => Macos14Large,
+            "macos-13-large" => Macos13Large,
+            "macos-latest-xlarge" => MacosLatestXlarge,
+            "macos-15-xlarge" => Macos15Xlarge,
+            "macos-14-xlarge" => Macos14Xlarge,
+            "macos-13-xlarge" => Macos13Xlarge,
+            _ => Other(s.into()),
+        })
+    }
+}
+
+impl Display for KnownRunner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use KnownRunner::*;
+
+        let label = match self {
+            UbuntuLatest => "ubuntu-latest",
+            Ubuntu2404 => "ubuntu-24.04",
+            Ubuntu2204 => "ubuntu-22.04",
+            Ubuntu2404Arm => "ubuntu-24.04-arm",
+            Ubuntu2204Arm => "ubuntu-22.04-arm",
+            WindowsLatest => "windows-latest",
+            Windows2025 => "windows-2025",
+            Windows2022 => "windows-2022",
+            Windows11Arm => "windows-11-arm",
+            MacosLatest => "macos-latest",
+            Macos15 => "macos-15",
+            Macos14 => "macos-14",
+            Macos13 => "macos-13",
+            MacosLatestLarge => "macos-latest-large",
+            Macos15Large => "macos-15-large",
+            Macos14Large => "macos-14-large",
+            Macos13Large => "macos-13-large",
+            MacosLatestXlarge => "macos-latest-xlarge",
+            Macos15Xlarge => "macos-15-xlarge",
+            Macos14Xlarge => "macos-14-xlarge",
+            Macos13Xlarge => "macos-13-xlarge",
+            Other(label) => label,
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// The operating system of a [`KnownRunner`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RunnerOs {
+    Linux,
+    Windows,
+    MacOs,
+}
+
+/// The CPU architecture of a [`KnownRunner`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RunnerArch {
+    X64,
+    Arm64,
+}
+
+/// The kind of runner targeted by a [`RunsOn`], as returned by
+/// [`RunsOn::runner_kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunnerKind {
+    /// A GitHub-hosted runner, including larger runners.
+    GitHubHosted,
+    /// A self-hosted runner or runner group.
+    SelfHosted,
+    /// A runner that can't be classified statically, e.g. because its
+    /// labels are computed by an expression or are custom labels.
+    Unknown,
+}
+
 impl<'de> Deserialize<'de> for RunsOn {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -75,40 +833,312 @@ impl<'de> Deserialize<'de> for RunsOn {
     }
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case", untagged)]
+impl Serialize for RunsOn {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Self::serialize(self, serializer)
+    }
+}
+
+/// A job's deployment `environment`, either as a bare name or as
+/// a mapping with a `name` and optional `url`.
+///
+/// Either the name or the URL may be an expression, e.g.
+/// `${{ github.ref_name }}`. Names and URLs with expressions embedded in
+/// literal text (like `pr-${{ github.event.number }}`) are
+/// [`LoE::Literal`]; use [`DeploymentEnvironment::is_dynamic`] to detect
+/// any expression.
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
 pub enum DeploymentEnvironment {
-    Name(String),
-    NameURL { name: String, url: Option<String> },
+    Name(LoE<String>),
+    NameURL {
+        name: LoE<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<LoE<String>>,
+    },
+}
+
+impl DeploymentEnvironment {
+    /// Returns this environment's name, exactly as written.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::NameURL { name, .. } => name.as_str(),
+        }
+    }
+
+    /// Returns this environment's URL, exactly as written, if present.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::Name(_) => None,
+            Self::NameURL { url, .. } => url.as_ref().map(LoE::as_str),
+        }
+    }
+
+    /// Returns whether this environment's name contains an expression,
+    /// either as the whole name or embedded within it.
+    pub fn is_dynamic(&self) -> bool {
+        self.name().contains("${{")
+    }
+}
+
+impl<'de> Deserialize<'de> for DeploymentEnvironment {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct NameURL {
+            name: String,
+            url: Option<String>,
+        }
+
+        // NOTE: We deserialize plain strings and then classify them, rather
+        // than deserializing `LoE<String>` directly, so that e.g. a mapping
+        // `url` produces a type error rather than an untagged enum error.
+        let loe = expr::string_loe;
+
+        // NOTE: Like `Job`, we discriminate manually rather than using
+        // `#[serde(untagged)]`, so that a malformed mapping produces its
+        // underlying error rather than an opaque untagged enum error.
+        match Value::deserialize(deserializer)? {
+            Value::String(name) => Ok(Self::Name(loe(name))),
+            Value::Mapping(mapping) => NameURL::deserialize(Value::Mapping(mapping))
+                .map(|NameURL { name, url }| Self::NameURL {
+                    name: loe(name),
+                    url: url.map(loe),
+                })
+                .map_err(|e| de::Error::custom(format!("invalid environment: {e}"))),
+            _ => Err(de::Error::custom(
+                "invalid environment: expected a name or a mapping with `name` and `url`",
+            )),
+        }
+    }
+}
+
+/// A summary of a job's deployment `environment`, as returned by
+/// [`NormalJob::environment_gate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvironmentGate<'a> {
+    /// The environment's name.
+    pub name: NameKind<'a>,
+    /// The environment's URL, if any.
+    pub url: Option<&'a str>,
+    /// Any refs that the job's `if:` compares `github.ref` against with `==`,
+    /// e.g. `refs/heads/main`.
+    ///
+    /// This is a syntactic check: it doesn't account for how the comparisons
+    /// are combined with the rest of the condition.
+    pub required_refs: Vec<&'a str>,
+}
+
+/// A step along with its index within its job, as returned by
+/// [`NormalJob::indexed_steps`].
+#[derive(Clone, Copy)]
+pub struct IndexedStep<'a> {
+    /// The index of the step within its job.
+    pub index: usize,
+    /// The step itself.
+    pub step: &'a Step,
+}
+
+impl IndexedStep<'_> {
+    /// Returns a stable identifier for this step; see [`Step::effective_id`].
+    pub fn effective_id(&self) -> String {
+        self.step.effective_id(self.index)
+    }
+}
+
+/// A `steps.<id>.outputs.<name>` reference in a job's `outputs`, as
+/// returned by [`NormalJob::output_references`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepOutputReference<'a> {
+    /// The name of the job output containing the reference.
+    pub output: &'a str,
+    /// The referenced step's `id`.
+    pub step_id: String,
+    /// The name of the referenced step output.
+    pub name: String,
+}
+
+/// A deployment environment's name, which may be computed by an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NameKind<'a> {
+    /// A literal environment name, e.g. `production`.
+    Literal(&'a str),
+    /// A name containing one or more expressions, e.g. `${{ inputs.env }}`.
+    Expression(&'a str),
+}
+
+/// A non-negative number of minutes, as used by `timeout-minutes`.
+///
+/// GitHub accepts fractional minutes (e.g. `4.5`) and numeric strings
+/// (e.g. `"10"`), as well as plain integers.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Minutes(f64);
+
+impl Minutes {
+    /// Returns this number of minutes as an `f64`.
+    pub fn as_f64(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for Minutes {
+    type Error = String;
+
+    fn try_from(minutes: f64) -> Result<Self, Self::Error> {
+        if minutes.is_finite() && minutes >= 0.0 {
+            Ok(Self(minutes))
+        } else {
+            Err(format!(
+                "invalid minutes: expected a non-negative number, got {minutes}"
+            ))
+        }
+    }
+}
+
+impl From<u64> for Minutes {
+    fn from(minutes: u64) -> Self {
+        Self(minutes as f64)
+    }
+}
+
+impl<'de> Deserialize<'de> for Minutes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(f64),
+            String(String),
+        }
+
+        let minutes = match Raw::deserialize(deserializer)? {
+            Raw::Number(minutes) => minutes,
+            Raw::String(minutes) => minutes.trim().parse().map_err(|_| {
+                de::Error::custom(format!(
+                    "invalid minutes: expected a number, got {minutes:?}"
+                ))
+            })?,
+        };
+
+        Self::try_from(minutes).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Minutes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Keep whole minutes as integers, like they're normally written.
+        if self.0.fract() == 0.0 && self.0 <= u64::MAX as f64 {
+            serializer.serialize_u64(self.0 as u64)
+        } else {
+            serializer.serialize_f64(self.0)
+        }
+    }
 }
 
-#[derive(Deserialize)]
+expr::impl_from_expr!(
+    Minutes,
+    RunsOn,
+    Container,
+    Matrix,
+    Vec<IndexMap<String, Value>>,
+    IndexMap<String, LoE<Vec<Value>>>,
+);
+
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Step {
     /// An optional ID for this step.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
 
     /// An optional expression that prevents this step from running unless it evaluates to `true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub r#if: Option<If>,
 
     /// An optional name for this step.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
 
     /// An optional timeout for this step, in minutes.
-    pub timeout_minutes: Option<LoE<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_minutes: Option<LoE<Minutes>>,
 
     /// An optional boolean or expression that, if `true`, prevents the job from failing when
     /// this step fails.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "crate::common::is_default")]
     pub continue_on_error: BoE,
 
     /// The `run:` or `uses:` body for this step.
     #[serde(flatten)]
     pub body: StepBody,
+
+    /// Any keys that are in neither this step nor its body.
+    #[serde(flatten, deserialize_with = "crate::common::step_extra::<StepBody, _>")]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl Step {
+    /// Returns a stable identifier for this step, given its `index` within
+    /// its job: its `id` if it has one, or `step-{index}` otherwise.
+    ///
+    /// Generated identifiers aren't guaranteed to be unique, since a step
+    /// can be explicitly given an `id` like `step-0`.
+    pub fn effective_id(&self, index: usize) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => format!("step-{index}"),
+        }
+    }
+
+    /// Returns the action used by this step, if it's a `uses:` step.
+    pub fn uses(&self) -> Option<&Uses> {
+        match &self.body {
+            StepBody::Uses { uses, .. } => Some(uses),
+            StepBody::Run { .. } => None,
+        }
+    }
+
+    /// Returns the command run by this step, if it's a `run:` step.
+    pub fn run(&self) -> Option<&str> {
+        match &self.body {
+            StepBody::Uses { .. } => None,
+            StepBody::Run { run, .. } => Some(run),
+        }
+    }
+
+    /// Returns the inputs passed to this step's action, if it's a
+    /// `uses:` step.
+    pub fn with(&self) -> Option<&Env> {
+        match &self.body {
+            StepBody::Uses { with, .. } => Some(with),
+            StepBody::Run { .. } => None,
+        }
+    }
+
+    /// Returns this step's environment mapping, which is empty if unset.
+    pub fn env(&self) -> &LoE<Env> {
+        match &self.body {
+            StepBody::Uses { env, .. } | StepBody::Run { env, .. } => env,
+        }
+    }
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case", untagged)]
+#[derive(Clone, Deserialize)]
+#[serde(
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case",
+    remote = "Self"
+)]
 pub enum StepBody {
     Uses {
         /// The GitHub Action being used.
@@ -118,6 +1148,10 @@ pub enum StepBody {
         /// Any inputs to the action being used.
         #[serde(default)]
         with: Env,
+
+        /// An optional environment mapping for this step.
+        #[serde(default)]
+        env: LoE<Env>,
     },
     Run {
         /// The command to run.
@@ -129,7 +1163,7 @@ pub enum StepBody {
 
         /// An optional shell to run in. Defaults to the job or workflow's
         /// default shell.
-        shell: Option<String>,
+        shell: Option<Shell>,
 
         /// An optional environment mapping for this step.
         #[serde(default)]
@@ -137,64 +1171,900 @@ pub enum StepBody {
     },
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case")]
-pub struct Strategy {
-    pub matrix: Option<LoE<Matrix>>,
-    pub fail_fast: Option<BoE>,
-    pub max_parallel: Option<u64>,
+impl crate::common::StepBodyVariants for StepBody {
+    fn deserialize_variant<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::deserialize(de)
+    }
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case")]
-pub struct Matrix {
-    #[serde(default)]
-    pub include: LoE<Vec<IndexMap<String, Value>>>,
-    #[serde(default)]
-    pub exclude: LoE<Vec<IndexMap<String, Value>>>,
-    #[serde(flatten)]
-    pub dimensions: LoE<IndexMap<String, LoE<Vec<Value>>>>,
+impl<'de> Deserialize<'de> for StepBody {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        crate::common::step_body(deserializer)
+    }
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case", untagged)]
+impl StepBody {
+    /// Returns a typed view of this step's `with:` block, if this step
+    /// uses a Docker action (`uses: docker://...`).
+    ///
+    /// For Docker actions, `with.args` and `with.entrypoint` override the
+    /// container's arguments and entrypoint rather than being passed as
+    /// inputs, so they're separated from the remaining inputs here.
+    pub fn docker_with(&self) -> Option<DockerWith<'_>> {
+        let StepBody::Uses {
+            uses: Uses::Docker(_),
+            with,
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        let mut docker_with = DockerWith::default();
+        for (key, value) in with {
+            match key.as_str() {
+                "args" => docker_with.args = Some(value.to_string()),
+                "entrypoint" => docker_with.entrypoint = Some(value.to_string()),
+                _ => {
+                    docker_with.inputs.insert(key, value);
+                }
+            }
+        }
+
+        Some(docker_with)
+    }
+}
+
+/// The `with:` block of a Docker action step, as returned by
+/// [`StepBody::docker_with`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DockerWith<'a> {
+    /// Arguments to pass to the container, overriding its `CMD`.
+    pub args: Option<String>,
+    /// An entrypoint for the container, overriding its `ENTRYPOINT`.
+    pub entrypoint: Option<String>,
+    /// Any remaining `with:` entries.
+    pub inputs: IndexMap<&'a str, &'a EnvValue>,
+}
+
+impl Serialize for StepBody {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // NOTE: Steps flatten their body, so we serialize each variant's
+        // fields directly rather than with the derived external tagging.
+        let mut map = serializer.serialize_map(None)?;
+        match self {
+            StepBody::Uses { uses, with, env } => {
+                map.serialize_entry("uses", uses)?;
+                if !with.is_empty() {
+                    map.serialize_entry("with", with)?;
+                }
+                if !crate::common::is_default(env) {
+                    map.serialize_entry("env", env)?;
+                }
+            }
+            StepBody::Run {
+                run,
+                working_directory,
+                shell,
+                env,
+            } => {
+                map.serialize_entry("run", run)?;
+                if let Some(working_directory) = working_directory {
+                    map.serialize_entry("working-directory", working_directory)?;
+                }
+                if let Some(shell) = shell {
+                    map.serialize_entry("shell", shell)?;
+                }
+                if !crate::common::is_default(env) {
+                    map.serialize_entry("env", env)?;
+                }
+            }
+        }
+        map.end()
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Strategy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<LoE<Matrix>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fail_fast: Option<BoE>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parallel: Option<LoE<u64>>,
+}
+
+impl Strategy {
+    /// Returns whether this strategy cancels its other jobs when one
+    /// fails, resolving the default (`true`).
+    ///
+    /// Returns `None` if `fail-fast` is an expression.
+    pub fn fail_fast(&self) -> Option<bool> {
+        match &self.fail_fast {
+            None => Some(true),
+            Some(LoE::Literal(fail_fast)) => Some(*fail_fast),
+            Some(LoE::Expr(_)) => None,
+        }
+    }
+
+    /// Returns the maximum number of this strategy's jobs that can run at
+    /// once.
+    ///
+    /// Returns `None` if there's no limit (the default) or if
+    /// `max-parallel` is an expression; check the `max_parallel` field
+    /// to tell these apart.
+    pub fn max_parallel(&self) -> Option<u64> {
+        match &self.max_parallel {
+            Some(LoE::Literal(max_parallel)) => Some(*max_parallel),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of jobs this strategy runs, or `None` if its
+    /// matrix can't be expanded statically.
+    ///
+    /// This is the same as [`Strategy::combination_count`], since each
+    /// matrix combination is one job.
+    pub fn job_count(&self) -> Option<usize> {
+        self.combination_count()
+    }
+
+    /// Returns the names of this strategy's matrix dimensions, or an
+    /// empty list if there's no matrix or its dimensions are an expression.
+    ///
+    /// Keys that only appear in `include` entries aren't dimensions, and
+    /// aren't included.
+    pub fn matrix_keys(&self) -> Vec<&str> {
+        match &self.matrix {
+            Some(LoE::Literal(Matrix {
+                dimensions: LoE::Literal(dimensions),
+                ..
+            })) => dimensions.keys().map(String::as_str).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the number of jobs this strategy's matrix expands into, or
+    /// `None` if any part of the matrix is an expression.
+    ///
+    /// This is the cartesian product of the dimensions' lengths, adjusted
+    /// for `include` and `exclude` entries as described in
+    /// [`Matrix::expand`]. A strategy without a matrix runs a single job.
+    pub fn combination_count(&self) -> Option<usize> {
+        match &self.matrix {
+            None => Some(1),
+            Some(LoE::Expr(_)) => None,
+            Some(LoE::Literal(matrix)) => matrix.static_combination_count(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Matrix {
+    #[serde(default, skip_serializing_if = "crate::common::is_default")]
+    pub include: LoE<Vec<IndexMap<String, Value>>>,
+    #[serde(default, skip_serializing_if = "crate::common::is_default")]
+    pub exclude: LoE<Vec<IndexMap<String, Value>>>,
+    #[serde(flatten)]
+    pub dimensions: LoE<IndexMap<String, LoE<Vec<Value>>>>,
+}
+
+impl Matrix {
+    /// Expand this matrix into its concrete job combinations, in the
+    /// order GitHub would run them.
+    ///
+    /// This follows GitHub's [expansion rules]:
+    ///
+    /// 1. The dimensions are expanded into their cartesian product.
+    /// 2. `exclude` entries remove every combination they match, where
+    ///    an entry matches if all of its keys have the same values in
+    ///    the combination.
+    /// 3. Each `include` entry is added to every combination it can be
+    ///    added to without overwriting one of the combination's original
+    ///    (dimension) values; added values may be overwritten by later
+    ///    entries. An entry that can't be added to any combination
+    ///    becomes a new combination of its own.
+    ///
+    /// Returns a [`MatrixError`] if any part of the matrix is an
+    /// expression, since its combinations can't be known statically.
+    ///
+    /// [expansion rules]: https://docs.github.com/en/actions/writing-workflows/choosing-what-your-workflow-does/running-variations-of-jobs-in-a-workflow
+    pub fn expand(&self) -> Result<Vec<IndexMap<String, Value>>, MatrixError> {
+        let LoE::Literal(dimensions) = &self.dimensions else {
+            return Err(MatrixError::DynamicDimensions);
+        };
+        let LoE::Literal(include) = &self.include else {
+            return Err(MatrixError::DynamicInclude);
+        };
+        let LoE::Literal(exclude) = &self.exclude else {
+            return Err(MatrixError::DynamicExclude);
+        };
+
+        let mut combinations: Vec<IndexMap<String, Value>> = vec![IndexMap::new()];
+        for (key, values) in dimensions {
+            let LoE::Literal(values) = values else {
+                return Err(MatrixError::DynamicDimension(key.clone()));
+            };
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.insert(key.clone(), value.clone());
+                        combination
+                    })
+                })
+                .collect();
+        }
+
+        // No dimensions means no base combinations, only includes.
+        if dimensions.is_empty() {
+            combinations.clear();
+        }
+
+        combinations.retain(|combination| {
+            !exclude.iter().any(|exclude| {
+                exclude
+                    .iter()
+                    .all(|(k, v)| combination.get(k.as_str()) == Some(v))
+            })
+        });
+
+        let mut standalone = vec![];
+        for include in include {
+            let mut matched = false;
+            for combination in &mut combinations {
+                let compatible = include.iter().all(|(k, v)| {
+                    !dimensions.contains_key(k) || combination.get(k.as_str()) == Some(v)
+                });
+                if compatible {
+                    matched = true;
+                    for (k, v) in include {
+                        combination.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+
+            if !matched {
+                standalone.push(include.clone());
+            }
+        }
+
+        combinations.extend(standalone);
+        Ok(combinations)
+    }
+
+    /// Check this matrix for mistakes that GitHub rejects, returning a
+    /// [`Diagnostic`] for each one found:
+    ///
+    /// * A dimension named `include` or `exclude`. These can only arise
+    ///   from constructing a [`Matrix`] directly, since deserialization
+    ///   routes those keys to their own fields.
+    /// * A dimension with no values.
+    /// * An `exclude` entry with a key that isn't a declared dimension.
+    ///
+    /// Parts of the matrix that are expressions aren't checked.
+    ///
+    /// Each diagnostic's path is relative to this matrix.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let LoE::Literal(dimensions) = &self.dimensions else {
+            return vec![];
+        };
+
+        let mut diagnostics = vec![];
+
+        for (key, values) in dimensions {
+            if matches!(key.as_str(), "include" | "exclude") {
+                diagnostics.push(Diagnostic::new(
+                    diagnostic::RESERVED_MATRIX_DIMENSION,
+                    [key],
+                    format!("matrix dimension can't be named `{key}`"),
+                ));
+            } else if matches!(values, LoE::Literal(values) if values.is_empty()) {
+                diagnostics.push(Diagnostic::new(
+                    diagnostic::EMPTY_MATRIX_DIMENSION,
+                    [key],
+                    format!("matrix dimension `{key}` has no values"),
+                ));
+            }
+        }
+
+        if let LoE::Literal(exclude) = &self.exclude {
+            for (index, exclude) in exclude.iter().enumerate() {
+                for key in exclude.keys().filter(|key| !dimensions.contains_key(*key)) {
+                    diagnostics.push(Diagnostic::new(
+                        diagnostic::UNKNOWN_MATRIX_EXCLUDE_KEY,
+                        ["exclude", &index.to_string(), key],
+                        format!("matrix `exclude` key `{key}` isn't a dimension"),
+                    ));
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Returns the number of jobs this matrix expands into, if it can be
+    /// determined statically (i.e. no part of it is an expression).
+    pub(crate) fn static_combination_count(&self) -> Option<usize> {
+        self.expand().ok().map(|combinations| combinations.len())
+    }
+}
+
+/// An error produced while expanding a [`Matrix`] whose combinations
+/// can't be known statically.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MatrixError {
+    /// The matrix's dimensions are a single expression.
+    DynamicDimensions,
+    /// The named dimension's values are an expression.
+    DynamicDimension(String),
+    /// The matrix's `include` is an expression.
+    DynamicInclude,
+    /// The matrix's `exclude` is an expression.
+    DynamicExclude,
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatrixError::DynamicDimensions => write!(f, "matrix is an expression"),
+            MatrixError::DynamicDimension(key) => {
+                write!(f, "matrix dimension `{key}` is an expression")
+            }
+            MatrixError::DynamicInclude => write!(f, "matrix `include` is an expression"),
+            MatrixError::DynamicExclude => write!(f, "matrix `exclude` is an expression"),
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", remote = "Self")]
+#[allow(clippy::large_enum_variant)]
 pub enum Container {
     Name(String),
     Container {
-        image: String,
+        image: LoE<String>,
         credentials: Option<DockerCredentials>,
         #[serde(default)]
         env: LoE<Env>,
-        // TODO: model `ports`?
+        #[serde(default)]
+        ports: Vec<Port>,
         #[serde(default)]
         volumes: Vec<String>,
         options: Option<String>,
     },
 }
 
-#[derive(Deserialize)]
+impl<'de> Deserialize<'de> for Container {
+    // NOTE: See `from_buffered_with` for why this needs a closure.
+    #[allow(clippy::redundant_closure)]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // NOTE: Like `StepBody`, we pick the variant ourselves rather than
+        // using `#[serde(untagged)]`, so that errors within a container's
+        // mapping are reported directly.
+        match Value::deserialize(deserializer)? {
+            Value::String(image) => Ok(Container::Name(image)),
+            Value::Mapping(container) => {
+                let container = Value::Tagged(Box::new(TaggedValue {
+                    tag: Tag::new("container"),
+                    value: Value::Mapping(container),
+                }));
+                crate::common::from_buffered_with(container, |de| Self::deserialize(de))
+                    .map_err(de::Error::custom)
+            }
+            _ => Err(de::Error::custom(
+                "invalid container: expected an image name or a mapping",
+            )),
+        }
+    }
+}
+
+impl Serialize for Container {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let Container::Container {
+            image,
+            credentials,
+            env,
+            ports,
+            volumes,
+            options,
+        } = self
+        else {
+            return self.image().serialize(serializer);
+        };
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("image", image)?;
+        if let Some(credentials) = credentials {
+            map.serialize_entry("credentials", credentials)?;
+        }
+        if !crate::common::is_default(env) {
+            map.serialize_entry("env", env)?;
+        }
+        if !ports.is_empty() {
+            map.serialize_entry("ports", ports)?;
+        }
+        if !volumes.is_empty() {
+            map.serialize_entry("volumes", volumes)?;
+        }
+        if let Some(options) = options {
+            map.serialize_entry("options", options)?;
+        }
+        map.end()
+    }
+}
+
+/// A job's `container:` or one of its `services:`, which can also be a
+/// whole expression like `${{ matrix.container }}`.
+///
+/// This picks between the two itself rather than with `LoE`'s
+/// `#[serde(untagged)]`, so that errors within a container come through.
+struct ContainerOrExpr(LoE<Container>);
+
+impl<'de> Deserialize<'de> for ContainerOrExpr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let container = Value::deserialize(deserializer)?;
+        if let Some(expr) = container.as_str().and_then(ExplicitExpr::from_curly) {
+            return Ok(Self(LoE::Expr(expr)));
+        }
+
+        // NOTE: `Container` has an inherent `deserialize` (from
+        // `remote = "Self"`), so we name the trait's explicitly.
+        <Container as Deserialize>::deserialize(container)
+            .map(|container| Self(LoE::Literal(container)))
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Deserialize a job's `container:`.
+fn container<'de, D>(de: D) -> Result<Option<LoE<Container>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let container = Option::<ContainerOrExpr>::deserialize(de)?;
+    Ok(container.map(|container| container.0))
+}
+
+/// Deserialize a job's `services:`.
+fn services<'de, D>(de: D) -> Result<IndexMap<String, LoE<Container>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let services = IndexMap::<String, ContainerOrExpr>::deserialize(de)?;
+    Ok(services
+        .into_iter()
+        .map(|(name, service)| (name, service.0))
+        .collect())
+}
+
+impl Container {
+    /// Returns this container's image reference, exactly as written.
+    pub fn image(&self) -> &str {
+        match self {
+            Container::Name(image) => image,
+            Container::Container { image, .. } => image.as_str(),
+        }
+    }
+
+    /// Returns whether this container's image reference contains an
+    /// expression, either as the whole reference (e.g.
+    /// `${{ matrix.container }}`) or embedded within it
+    /// (e.g. `node:${{ matrix.node }}`).
+    pub fn image_is_expression(&self) -> bool {
+        self.image().contains("${{")
+    }
+
+    /// Parse this container's image reference into its components,
+    /// like a `uses: docker://...` step.
+    ///
+    /// Returns `None` if the image contains an expression (see
+    /// [`Container::image_is_expression`]), since its components can't be
+    /// known statically.
+    pub fn image_uses(&self) -> Result<Option<DockerUses>, UsesError> {
+        if self.image_is_expression() {
+            return Ok(None);
+        }
+
+        // NOTE: Container images are normally bare references, but
+        // GitHub also accepts the `docker://` prefix used by steps.
+        let image = self.image();
+        let image = image.strip_prefix("docker://").unwrap_or(image);
+        DockerUses::from_str(image).map(Some)
+    }
+
+    /// Returns the body of each expression in this container's `env`,
+    /// e.g. `secrets.TOKEN` for `TOKEN: ${{ secrets.TOKEN }}`.
+    ///
+    /// If the entire `env` is an expression, its body is the only item.
+    pub fn env_expressions(&self) -> Vec<&str> {
+        match self {
+            Container::Name(_) => vec![],
+            Container::Container {
+                env: LoE::Expr(expr),
+                ..
+            } => vec![expr.as_bare()],
+            Container::Container {
+                env: LoE::Literal(env),
+                ..
+            } => env
+                .values()
+                .filter_map(|value| match value {
+                    EnvValue::String(s) => Some(s),
+                    _ => None,
+                })
+                .flat_map(|s| expr::interpolations(s).into_iter().map(|(_, body)| body))
+                .collect(),
+        }
+    }
+}
+
+/// A port exposed by a job or service container, exactly as written.
+///
+/// GitHub passes ports through to Docker, so besides a bare container
+/// port (`5432`) or a `HOST:CONTAINER[/PROTOCOL]` mapping, they can bind a
+/// host IP (`127.0.0.1:5432:5432`), map port ranges (`8000-8010:8000-8010`),
+/// or contain expressions (`${{ matrix.port }}:5432`). Use
+/// [`Port::container_port`] to parse the common forms.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Port {
+    /// A bare container port, like `5432`.
+    Number(u64),
+    /// Any other port or port mapping, like `8080:80/tcp`.
+    String(String),
+}
+
+impl Port {
+    /// Parse this port as a [`ContainerPort`].
+    ///
+    /// This fails for the forms that [`ContainerPort`] doesn't model, i.e.
+    /// host IPs, port ranges and expressions, as well as for invalid ports.
+    pub fn container_port(&self) -> Result<ContainerPort, ContainerPortError> {
+        self.to_string().parse()
+    }
+}
+
+impl Display for Port {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Port::Number(port) => write!(f, "{port}"),
+            Port::String(port) => write!(f, "{port}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Port {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::Number(port) => match port.as_u64() {
+                Some(port) => Ok(Port::Number(port)),
+                None => Err(de::Error::custom(format!(
+                    "invalid port `{port}`: expected a port number"
+                ))),
+            },
+            Value::String(port) => Ok(Port::String(port)),
+            _ => Err(de::Error::custom(
+                "invalid port: expected a number or a `host:container` string",
+            )),
+        }
+    }
+}
+
+/// A port exposed by a job or service container, in Docker's
+/// `[HOST:]CONTAINER[/PROTOCOL]` syntax.
+///
+/// See [`Port::container_port`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContainerPort {
+    /// The host port, if mapped explicitly. If not, the runner maps the
+    /// container port to a random free port on the host.
+    pub host: Option<u16>,
+    /// The container port.
+    pub container: u16,
+    /// The protocol (e.g. `tcp` or `udp`), if specified.
+    pub protocol: Option<String>,
+}
+
+/// An error parsing a [`ContainerPort`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContainerPortError(String);
+
+impl Display for ContainerPortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ContainerPortError {}
+
+impl FromStr for ContainerPort {
+    type Err = ContainerPortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn number(port: &str) -> Result<u16, ContainerPortError> {
+            match port.trim().parse::<u16>() {
+                Ok(port) if port > 0 => Ok(port),
+                _ => Err(ContainerPortError(format!(
+                    "port `{port}` is not in 1-65535"
+                ))),
+            }
+        }
+
+        let (ports, protocol) = match s.split_once('/') {
+            Some((_, "")) => {
+                return Err(ContainerPortError(format!(
+                    "invalid port mapping `{s}`: empty protocol"
+                )))
+            }
+            Some((ports, protocol)) => (ports, Some(protocol.into())),
+            None => (s, None),
+        };
+
+        match ports.split(':').collect::<Vec<_>>().as_slice() {
+            [container] => Ok(Self {
+                host: None,
+                container: number(container)?,
+                protocol,
+            }),
+            [host, container] => Ok(Self {
+                host: Some(number(host)?),
+                container: number(container)?,
+                protocol,
+            }),
+            _ => Err(ContainerPortError(format!(
+                "invalid port mapping `{s}`: expected `[host:]container[/protocol]`"
+            ))),
+        }
+    }
+}
+
+impl Display for ContainerPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(host) = self.host {
+            write!(f, "{host}:")?;
+        }
+        write!(f, "{}", self.container)?;
+        if let Some(protocol) = &self.protocol {
+            write!(f, "/{protocol}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for ContainerPort {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::Number(port) => port.to_string().parse().map_err(de::Error::custom),
+            Value::String(port) => port.parse().map_err(de::Error::custom),
+            _ => Err(de::Error::custom(
+                "invalid port: expected a number or a `host:container` string",
+            )),
+        }
+    }
+}
+
+impl Serialize for ContainerPort {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self {
+                host: None,
+                container,
+                protocol: None,
+            } => serializer.serialize_u16(*container),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+/// Credentials for pulling a job or service container's image.
+#[derive(Clone, Deserialize, Serialize)]
 pub struct DockerCredentials {
-    pub username: Option<String>,
-    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<LoE<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<LoE<String>>,
+}
+
+impl DockerCredentials {
+    /// Returns whether the password is hard-coded, rather than
+    /// coming from an expression like `${{ secrets.REGISTRY_PASSWORD }}`.
+    pub fn has_static_password(&self) -> bool {
+        matches!(self.password, Some(LoE::Literal(_)))
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct ReusableWorkflowCallJob {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
-    #[serde(default)]
-    pub permissions: Permissions,
-    #[serde(default, deserialize_with = "crate::common::scalar_or_vector")]
+    /// This job's `permissions`, or `None` if it inherits the workflow's.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
+    #[serde(
+        default,
+        deserialize_with = "crate::common::scalar_or_vector",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub needs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub r#if: Option<If>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<Strategy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<Concurrency>,
     #[serde(deserialize_with = "crate::common::reusable_step_uses")]
     pub uses: Uses,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub with: Env,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub secrets: Option<Secrets>,
+
+    /// Any other keys in this job.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl ReusableWorkflowCallJob {
+    /// Returns the body of every expression in this job that may reference
+    /// other jobs, as with [`NormalJob::expression_bodies`].
+    pub(crate) fn expression_bodies(&self) -> Vec<&str> {
+        let strings = self.with.values().filter_map(|value| match value {
+            EnvValue::String(s) => Some(s.as_str()),
+            _ => None,
+        });
+
+        expression_bodies(
+            self.r#if.iter().filter(|cond| matches!(cond, If::Expr(_))),
+            strings,
+        )
+    }
+
+    /// Returns the called workflow's repository `uses:`, or `None` if it's
+    /// a local workflow like `./.github/workflows/ci.yml`.
+    pub fn callee_ref(&self) -> Option<&RepositoryUses> {
+        match &self.uses {
+            Uses::Repository(uses) => Some(uses),
+            Uses::Local(_) | Uses::Docker(_) => None,
+        }
+    }
+
+    /// Returns the owner of the called workflow's repository, or `None` for
+    /// a local workflow.
+    pub fn callee_owner(&self) -> Option<&str> {
+        self.callee_ref().map(|uses| uses.owner.as_str())
+    }
+
+    /// Returns the name of the called workflow's repository, or `None` for
+    /// a local workflow.
+    pub fn callee_repo(&self) -> Option<&str> {
+        self.callee_ref().map(|uses| uses.repo.as_str())
+    }
+
+    /// Returns the called workflow's path within its repository, e.g.
+    /// `.github/workflows/ci.yml`, or `None` for a local workflow.
+    pub fn callee_workflow_path(&self) -> Option<&str> {
+        self.callee_ref()?.subpath.as_deref()
+    }
+
+    /// Returns the `@<ref>` that the called workflow is pinned to, or
+    /// `None` for a local workflow.
+    pub fn callee_git_ref(&self) -> Option<&str> {
+        self.callee_ref()?.git_ref.as_deref()
+    }
+
+    /// Check this job's `with:` and `secrets:` for values that look like
+    /// they're in the wrong place:
+    ///
+    /// * `with:` entries that reference `secrets.*`, which should be passed
+    ///   under `secrets:` instead.
+    /// * `secrets:` entries that don't reference `secrets.*` or
+    ///   `github.token`, and so aren't secret at all.
+    ///
+    /// `with:` entries named in `allowed_inputs` are skipped, for callees
+    /// that deliberately accept a token as an input. Like GitHub's own
+    /// input matching (see [`WorkflowCall::input`]), names are compared
+    /// ASCII case-insensitively.
+    ///
+    /// [`WorkflowCall::input`]: crate::workflow::event::WorkflowCall::input
+    ///
+    /// Each diagnostic's path is relative to this job.
+    pub fn secret_placement_diagnostics(&self, allowed_inputs: &[&str]) -> Vec<Diagnostic> {
+        fn references(value: &str) -> Vec<&str> {
+            expr::interpolations(value)
+                .into_iter()
+                .flat_map(|(_, body)| expr::context_paths(body))
+                .collect()
+        }
+
+        fn is_secret(context: &str) -> bool {
+            let context = context.to_ascii_lowercase();
+            context.starts_with("secrets.")
+                || context.starts_with("secrets[")
+                || context == "github.token"
+        }
+
+        let mut diagnostics = vec![];
+
+        for (name, value) in &self.with {
+            let EnvValue::String(value) = value else {
+                continue;
+            };
+            if allowed_inputs
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(name))
+            {
+                continue;
+            }
+
+            if let Some(secret) = references(value)
+                .into_iter()
+                .find(|context| is_secret(context) && !context.eq_ignore_ascii_case("github.token"))
+            {
+                diagnostics.push(Diagnostic::new(
+                    diagnostic::SECRET_IN_WITH,
+                    ["with", name],
+                    format!(
+                        "input `{name}` is passed `{secret}`; secrets should be passed under \
+                         `secrets:` instead"
+                    ),
+                ));
+            }
+        }
+
+        if let Some(Secrets::Env(secrets)) = &self.secrets {
+            for (name, value) in secrets {
+                let value = value.to_string();
+                if !references(&value).into_iter().any(is_secret) {
+                    diagnostics.push(Diagnostic::new(
+                        diagnostic::NON_SECRET_IN_SECRETS,
+                        ["secrets", name],
+                        format!(
+                            "secret `{name}` is passed a value that isn't a secret; \
+                             non-secret values should be passed under `with:` instead"
+                        ),
+                    ));
+                }
+            }
+        }
+
+        diagnostics
+    }
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum Secrets {
     Inherit,
@@ -202,14 +2072,67 @@ pub enum Secrets {
     Env(#[serde(default)] Env),
 }
 
+impl Secrets {
+    /// Returns whether the caller's secrets are passed implicitly, with
+    /// `secrets: inherit`.
+    pub fn is_inherited(&self) -> bool {
+        matches!(self, Secrets::Inherit)
+    }
+
+    /// Returns the explicitly passed secrets, or `None` for
+    /// `secrets: inherit`.
+    pub fn as_env(&self) -> Option<&Env> {
+        match self {
+            Secrets::Inherit => None,
+            Secrets::Env(env) => Some(env),
+        }
+    }
+
+    /// Returns each explicitly passed secret's name and value.
+    ///
+    /// This is empty for `secrets: inherit`, since those secrets aren't
+    /// named; check [`Secrets::is_inherited`] to tell the cases apart.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &EnvValue)> {
+        self.as_env()
+            .into_iter()
+            .flatten()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Returns the number of explicitly passed secrets, which is zero for
+    /// `secrets: inherit`.
+    pub fn len(&self) -> usize {
+        self.as_env().map_or(0, Env::len)
+    }
+
+    /// Returns whether no secrets are explicitly passed, which is always
+    /// the case for `secrets: inherit`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use indexmap::IndexMap;
+    use serde_yaml::Value;
+
     use crate::{
-        common::{expr::LoE, EnvValue},
-        workflow::job::{Matrix, Secrets},
+        common::{
+            diagnostic::{self, Diagnostic},
+            expr::{LoE, TemplatePart},
+            DockerUses, EnvValue, Shell,
+        },
+        workflow::{
+            job::{Matrix, MatrixError, ReusableWorkflowCallJob, Secrets},
+            Workflow,
+        },
     };
 
-    use super::{RunsOn, Strategy};
+    use super::{
+        Container, ContainerPort, DeploymentEnvironment, KnownRunner, Minutes, NormalJob, Port,
+        RunnerArch, RunnerKind, RunnerLabel, RunnerOs, RunsOn, Step, StepBody, Strategy,
+    };
 
     #[test]
     fn test_secrets() {
@@ -218,8 +2141,24 @@ mod tests {
             Secrets::Inherit
         );
 
+        let inherit = serde_yaml::from_str::<Secrets>("inherit").unwrap();
+        assert!(inherit.is_inherited());
+        assert!(inherit.as_env().is_none());
+        assert_eq!(inherit.iter().count(), 0);
+        assert_eq!(inherit.len(), 0);
+        assert!(inherit.is_empty());
+
         let secrets = "foo-secret: bar";
-        let Secrets::Env(secrets) = serde_yaml::from_str::<Secrets>(secrets).unwrap() else {
+        let parsed = serde_yaml::from_str::<Secrets>(secrets).unwrap();
+        assert!(!parsed.is_inherited());
+        assert_eq!(
+            parsed.iter().collect::<Vec<_>>(),
+            [("foo-secret", &EnvValue::String("bar".into()))]
+        );
+        assert_eq!(parsed.len(), 1);
+        assert!(!parsed.is_empty());
+
+        let Secrets::Env(secrets) = parsed else {
             panic!("unexpected secrets variant");
         };
         assert_eq!(secrets["foo-secret"], EnvValue::String("bar".into()));
@@ -270,4 +2209,1375 @@ matrix:
             "runs-on must provide either `group` or one or more `labels`"
         );
     }
+
+    #[test]
+    fn test_step_ids() {
+        let job = "
+runs-on: ubuntu-latest
+steps:
+  - id: checkout
+    uses: actions/checkout@v4
+  - run: echo hello
+  - id: build
+    run: make
+";
+        let job = serde_yaml::from_str::<NormalJob>(job).unwrap();
+
+        assert!(std::ptr::eq(
+            job.step_by_id("checkout").unwrap(),
+            &job.steps[0]
+        ));
+        assert!(std::ptr::eq(
+            job.step_by_id("build").unwrap(),
+            &job.steps[2]
+        ));
+        assert!(job.step_by_id("missing").is_none());
+        assert_eq!(
+            job.steps_with_ids().map(|(id, _)| id).collect::<Vec<_>>(),
+            ["checkout", "build"]
+        );
+        assert!(job.step_ids_are_unique());
+        assert!(job.duplicate_step_ids().is_empty());
+    }
+
+    #[test]
+    fn test_indexed_steps() {
+        let job = "
+runs-on: ubuntu-latest
+steps:
+  - id: checkout
+    uses: actions/checkout@v4
+  - run: echo hello
+  - id: build
+    run: make
+";
+        let job = serde_yaml::from_str::<NormalJob>(job).unwrap();
+
+        assert_eq!(
+            job.indexed_steps()
+                .map(|step| (step.index, step.effective_id()))
+                .collect::<Vec<_>>(),
+            [
+                (0, "checkout".into()),
+                (1, "step-1".into()),
+                (2, "build".into())
+            ]
+        );
+        assert_eq!(job.steps[1].effective_id(7), "step-7");
+        assert_eq!(job.steps[2].effective_id(7), "build");
+    }
+
+    #[test]
+    fn test_step_ids_empty() {
+        let job = serde_yaml::from_str::<NormalJob>("runs-on: ubuntu-latest\nsteps: []").unwrap();
+
+        assert!(job.step_by_id("anything").is_none());
+        assert_eq!(job.steps_with_ids().count(), 0);
+        assert!(job.step_ids_are_unique());
+    }
+
+    #[test]
+    fn test_step_ids_duplicate() {
+        let job = "
+runs-on: ubuntu-latest
+steps:
+  - id: build
+    run: make
+  - run: echo no id
+  - run: echo no id either
+  - id: build
+    run: make again
+";
+        let job = serde_yaml::from_str::<NormalJob>(job).unwrap();
+
+        assert!(!job.step_ids_are_unique());
+        let diagnostics = job.duplicate_step_ids();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, diagnostic::DUPLICATE_STEP_ID);
+        assert_eq!(diagnostics[0].path, ["steps", "3", "id"]);
+        assert_eq!(
+            diagnostics[0].message,
+            "step ID `build` is already used by step 0"
+        );
+        // The first matching step wins.
+        assert!(matches!(
+            &job.step_by_id("build").unwrap().body,
+            super::StepBody::Run { run, .. } if run == "make"
+        ));
+    }
+
+    #[test]
+    fn test_output_references() {
+        let job = "
+runs-on: ubuntu-latest
+outputs:
+  digest: ${{ steps.build.outputs.digest }}
+  missing: ${{ steps.nope.outputs.value }}
+  constant: v1.2.3
+  combined: ${{ steps.build.outputs.name }}:${{ steps['tag'].outputs['v'] || 'latest' }}
+  unrelated: ${{ steps.build.outcome }}-${{ needs.other.outputs.foo }}
+steps:
+  - id: build
+    run: make
+  - id: tag
+    run: ./tag.sh
+";
+        let job = serde_yaml::from_str::<NormalJob>(job).unwrap();
+
+        let refs = job
+            .output_references()
+            .into_iter()
+            .map(|r| (r.output, r.step_id, r.name))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            refs,
+            [
+                ("digest", "build".into(), "digest".into()),
+                // References to nonexistent steps are returned for validation.
+                ("missing", "nope".into(), "value".into()),
+                ("combined", "build".into(), "name".into()),
+                ("combined", "tag".into(), "v".into()),
+            ]
+        );
+        assert!(job.step_by_id("nope").is_none());
+
+        assert_eq!(
+            job.output_parts("constant").unwrap(),
+            [TemplatePart::Literal("v1.2.3")]
+        );
+        assert_eq!(
+            job.output_parts("digest").unwrap(),
+            [TemplatePart::Expr("steps.build.outputs.digest")]
+        );
+        assert!(job.output_parts("nonexistent").is_none());
+    }
+
+    #[test]
+    fn test_effective_shell_and_working_directory() {
+        let workflow: Workflow = "
+on: push
+defaults:
+  run:
+    shell: pwsh
+    working-directory: workflow-dir
+jobs:
+  job-defaults:
+    runs-on: ubuntu-latest
+    defaults:
+      run:
+        shell: bash
+    steps:
+      - run: step
+        shell: python
+        working-directory: step-dir
+      - run: job
+      - uses: actions/checkout@v4
+  no-defaults:
+    runs-on: ubuntu-latest
+    steps:
+      - run: workflow
+"
+        .parse()
+        .unwrap();
+        let workflow_defaults = workflow.defaults.as_ref();
+
+        let job = workflow.get_normal_job("job-defaults").unwrap();
+        let [step, job_step, uses_step] = &job.steps[..] else {
+            panic!("expected three steps");
+        };
+
+        // The step's own settings win.
+        assert_eq!(
+            job.effective_shell(step, workflow_defaults),
+            Some(&Shell::Python)
+        );
+        assert_eq!(
+            job.effective_working_directory(step, workflow_defaults),
+            Some("step-dir")
+        );
+
+        // Then the job's defaults, then the workflow's, per setting.
+        assert_eq!(
+            job.effective_shell(job_step, workflow_defaults),
+            Some(&Shell::Bash)
+        );
+        assert_eq!(
+            job.effective_working_directory(job_step, workflow_defaults),
+            Some("workflow-dir")
+        );
+
+        // Only `run:` steps have a shell or working directory.
+        assert_eq!(job.effective_shell(uses_step, workflow_defaults), None);
+        assert_eq!(
+            job.effective_working_directory(uses_step, workflow_defaults),
+            None
+        );
+
+        let job = workflow.get_normal_job("no-defaults").unwrap();
+        let step = &job.steps[0];
+        assert_eq!(
+            job.effective_shell(step, workflow_defaults),
+            Some(&Shell::Pwsh)
+        );
+        assert_eq!(
+            job.effective_working_directory(step, workflow_defaults),
+            Some("workflow-dir")
+        );
+
+        // Nothing set at any level.
+        assert_eq!(job.effective_shell(step, None), None);
+        assert_eq!(job.effective_working_directory(step, None), None);
+    }
+
+    #[test]
+    fn test_step_ids_case() {
+        let job = "
+runs-on: ubuntu-latest
+steps:
+  - id: build
+    run: make
+  - id: Build
+    run: make again
+  - id: test
+    run: make test
+  - id: test
+    run: make test again
+  - id: test
+    run: make test once more
+";
+        let job = serde_yaml::from_str::<NormalJob>(job).unwrap();
+
+        // IDs that only differ by case don't collide.
+        assert_eq!(
+            job.duplicate_step_ids()
+                .into_iter()
+                .map(|d| (d.path.join("."), d.message))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "steps.3.id".into(),
+                    "step ID `test` is already used by step 2".into()
+                ),
+                (
+                    "steps.4.id".into(),
+                    "step ID `test` is already used by step 2".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_step_accessors() {
+        let step = serde_yaml::from_str::<Step>(
+            "uses: actions/checkout@v4\nwith:\n  fetch-depth: 0\nenv: ${{ fromJSON(inputs.env) }}",
+        )
+        .unwrap();
+        assert_eq!(step.uses().unwrap().to_string(), "actions/checkout@v4");
+        assert!(step.run().is_none());
+        assert_eq!(step.with().unwrap()["fetch-depth"], EnvValue::Number(0.0));
+        assert!(matches!(step.env(), LoE::Expr(_)));
+
+        let step = serde_yaml::from_str::<Step>("run: make\nenv:\n  CC: clang").unwrap();
+        assert!(step.uses().is_none());
+        assert_eq!(step.run(), Some("make"));
+        assert!(step.with().is_none());
+        assert!(
+            matches!(step.env(), LoE::Literal(env) if env["CC"] == EnvValue::String("clang".into()))
+        );
+    }
+
+    #[test]
+    fn test_step_body() {
+        let step = "run: make\nworking-directory: build\nshell: bash";
+        let Step {
+            body:
+                StepBody::Run {
+                    run,
+                    working_directory,
+                    shell,
+                    ..
+                },
+            ..
+        } = serde_yaml::from_str::<Step>(step).unwrap()
+        else {
+            panic!("expected run step");
+        };
+        assert_eq!(run, "make");
+        assert_eq!(working_directory.as_deref(), Some("build"));
+        assert_eq!(shell, Some(Shell::Bash));
+
+        let step = "uses: actions/checkout@v4\nwith:\n  persist-credentials: false";
+        let Step {
+            body: StepBody::Uses { with, .. },
+            ..
+        } = serde_yaml::from_str::<Step>(step).unwrap()
+        else {
+            panic!("expected uses step");
+        };
+        assert_eq!(with["persist-credentials"], EnvValue::Boolean(false));
+
+        let step = "uses: actions/checkout@v4\nenv:\n  GH_TOKEN: ${{ secrets.GITHUB_TOKEN }}";
+        let Step {
+            body: StepBody::Uses { with, env, .. },
+            ..
+        } = serde_yaml::from_str::<Step>(step).unwrap()
+        else {
+            panic!("expected uses step");
+        };
+        assert!(with.is_empty());
+        let LoE::Literal(env) = env else {
+            panic!("expected literal env");
+        };
+        assert_eq!(
+            env["GH_TOKEN"],
+            EnvValue::String("${{ secrets.GITHUB_TOKEN }}".into())
+        );
+
+        // `env:` alone doesn't change which variant a step is.
+        let step = "run: make\nenv:\n  FOO: bar";
+        assert!(matches!(
+            serde_yaml::from_str::<Step>(step).unwrap().body,
+            StepBody::Run { .. }
+        ));
+    }
+
+    #[test]
+    fn test_docker_with() {
+        let step = r#"
+uses: docker://alpine:3.20
+with:
+  entrypoint: /bin/sh
+  args: -c "echo hello"
+  verbose: true
+"#;
+        let step = serde_yaml::from_str::<Step>(step).unwrap();
+        let docker_with = step.body.docker_with().unwrap();
+        assert_eq!(docker_with.args.as_deref(), Some("-c \"echo hello\""));
+        assert_eq!(docker_with.entrypoint.as_deref(), Some("/bin/sh"));
+        assert_eq!(
+            docker_with.inputs,
+            IndexMap::from([("verbose", &EnvValue::Boolean(true))])
+        );
+
+        let step = serde_yaml::from_str::<Step>("uses: docker://alpine:3.20").unwrap();
+        assert_eq!(step.body.docker_with().unwrap(), Default::default());
+
+        // `args` is an ordinary input for non-Docker actions.
+        let step = "uses: example/action@v1\nwith:\n  args: --verbose";
+        let step = serde_yaml::from_str::<Step>(step).unwrap();
+        assert!(step.body.docker_with().is_none());
+        let StepBody::Uses { with, .. } = &step.body else {
+            panic!("expected uses step");
+        };
+        assert_eq!(with["args"], EnvValue::String("--verbose".into()));
+
+        let step = serde_yaml::from_str::<Step>("run: echo hello").unwrap();
+        assert!(step.body.docker_with().is_none());
+    }
+
+    #[test]
+    fn test_step_body_errors() {
+        let vectors = [
+            // Misspelled `uses:`.
+            (
+                "use: actions/checkout@v4\nwith: {}",
+                "step must contain either `uses:` or `run:`, found unrecognized keys: `use`",
+            ),
+            // Body keys without a body aren't unrecognized.
+            (
+                "with: {}\nworking-directory: src",
+                "step must contain either `uses:` or `run:`",
+            ),
+            // Empty step (no body at all).
+            ("name: empty", "step must contain either `uses:` or `run:`"),
+            // Both `uses:` and `run:`.
+            (
+                "uses: actions/checkout@v4\nrun: make",
+                "step cannot contain both `uses:` and `run:`",
+            ),
+            // `uses:` is present but malformed.
+            (
+                "uses: checkout",
+                "malformed `uses` ref: owner/repo slug is too short: checkout",
+            ),
+        ];
+
+        for (step, expected) in vectors {
+            let Err(err) = serde_yaml::from_str::<Step>(step) else {
+                panic!("expected {step:?} to fail");
+            };
+            let err = err.to_string();
+
+            assert_eq!(err, expected);
+            assert!(!err.contains("untagged"));
+        }
+    }
+
+    #[test]
+    fn test_runson_effective_labels() {
+        let runson = serde_yaml::from_str::<RunsOn>("[self-hosted, linux]").unwrap();
+        assert_eq!(runson.effective_labels(), ["self-hosted", "linux"]);
+        assert!(runson.contains_label("self-hosted"));
+        assert!(!runson.contains_label("ubuntu-latest"));
+
+        let runson = serde_yaml::from_str::<RunsOn>("ubuntu-latest").unwrap();
+        assert_eq!(runson.effective_labels(), ["ubuntu-latest"]);
+
+        let runson =
+            serde_yaml::from_str::<RunsOn>("group: ubuntu-runners\nlabels: ubuntu-20.04-16core")
+                .unwrap();
+        assert_eq!(runson.effective_labels(), ["ubuntu-20.04-16core"]);
+        assert!(runson.contains_label("ubuntu-20.04-16core"));
+
+        // Not constructable via deserialization, but handled anyways.
+        let runson = RunsOn::Group {
+            group: None,
+            labels: vec![],
+        };
+        assert!(runson.effective_labels().is_empty());
+        assert!(!runson.contains_label("self-hosted"));
+    }
+
+    #[test]
+    fn test_runson_runner_kind() {
+        let vectors = [
+            // Bare label.
+            ("ubuntu-latest", RunnerKind::GitHubHosted, None),
+            ("windows-2022", RunnerKind::GitHubHosted, None),
+            ("ubuntu-latest-16-cores", RunnerKind::GitHubHosted, None),
+            ("windows-2022-8-cores", RunnerKind::GitHubHosted, None),
+            ("ubuntu-22.04-4core", RunnerKind::GitHubHosted, None),
+            ("ubuntu-", RunnerKind::Unknown, None),
+            // Image-like labels that aren't GitHub-hosted runners.
+            ("ubuntu-nightly", RunnerKind::Unknown, None),
+            ("ubuntu-16-cores", RunnerKind::Unknown, None),
+            ("macos-latest-16-cores", RunnerKind::Unknown, None),
+            ("my-runner", RunnerKind::Unknown, None),
+            ("${{ matrix.os }}", RunnerKind::Unknown, None),
+            ("ubuntu-${{ matrix.version }}", RunnerKind::Unknown, None),
+            // List of labels.
+            ("[self-hosted, linux, x64]", RunnerKind::SelfHosted, None),
+            (
+                "[Self-Hosted, '${{ inputs.label }}']",
+                RunnerKind::SelfHosted,
+                None,
+            ),
+            (
+                "[macos-14, macos-14-xlarge]",
+                RunnerKind::GitHubHosted,
+                None,
+            ),
+            ("[ubuntu-latest, gpu]", RunnerKind::Unknown, None),
+            // Runner group.
+            (
+                "group: large-runners",
+                RunnerKind::SelfHosted,
+                Some("large-runners"),
+            ),
+            (
+                "group: ubuntu-runners\nlabels: ubuntu-20.04-16core",
+                RunnerKind::SelfHosted,
+                Some("ubuntu-runners"),
+            ),
+            ("labels: [linux]", RunnerKind::SelfHosted, None),
+        ];
+
+        for (runson, kind, group) in vectors {
+            let runson = serde_yaml::from_str::<RunsOn>(runson).unwrap();
+            assert_eq!(runson.runner_kind(), kind, "{runson:?}");
+            assert_eq!(runson.is_self_hosted(), kind == RunnerKind::SelfHosted);
+            assert_eq!(runson.is_github_hosted(), kind == RunnerKind::GitHubHosted);
+            assert_eq!(runson.group(), group);
+        }
+    }
+
+    #[test]
+    fn test_runson_classified_labels() {
+        use crate::common::expr::TemplatePart;
+
+        let runson = serde_yaml::from_str::<RunsOn>(
+            "[ubuntu-latest, '${{ matrix.os }}', 'ubuntu-${{ matrix.version }}-arm']",
+        )
+        .unwrap();
+        assert_eq!(
+            runson.classified_labels().collect::<Vec<_>>(),
+            [
+                RunnerLabel::Literal("ubuntu-latest"),
+                RunnerLabel::WholeExpression("matrix.os"),
+                RunnerLabel::Template {
+                    parts: vec![
+                        TemplatePart::Literal("ubuntu-"),
+                        TemplatePart::Expr("matrix.version"),
+                        TemplatePart::Literal("-arm"),
+                    ]
+                },
+            ]
+        );
+        assert!(runson.has_embedded_expressions());
+
+        let runson = serde_yaml::from_str::<RunsOn>("ubuntu-${{ matrix.version }}").unwrap();
+        assert!(runson.has_embedded_expressions());
+        assert!(matches!(
+            runson.classified_labels().next(),
+            Some(RunnerLabel::Template { .. })
+        ));
+
+        let runson = serde_yaml::from_str::<RunsOn>("[self-hosted, linux]").unwrap();
+        assert!(!runson.has_embedded_expressions());
+        assert!(runson
+            .classified_labels()
+            .all(|label| matches!(label, RunnerLabel::Literal(_))));
+
+        let runson =
+            serde_yaml::from_str::<RunsOn>("group: ${{ inputs.group }}\nlabels: [linux]").unwrap();
+        assert!(runson.has_embedded_expressions());
+        assert_eq!(
+            runson.classified_labels().collect::<Vec<_>>(),
+            [RunnerLabel::Literal("linux")]
+        );
+    }
+
+    #[test]
+    fn test_known_runner() {
+        use KnownRunner::*;
+
+        let vectors = [
+            (
+                "ubuntu-latest",
+                UbuntuLatest,
+                RunnerOs::Linux,
+                RunnerArch::X64,
+            ),
+            ("ubuntu-24.04", Ubuntu2404, RunnerOs::Linux, RunnerArch::X64),
+            ("ubuntu-22.04", Ubuntu2204, RunnerOs::Linux, RunnerArch::X64),
+            (
+                "ubuntu-24.04-arm",
+                Ubuntu2404Arm,
+                RunnerOs::Linux,
+                RunnerArch::Arm64,
+            ),
+            (
+                "ubuntu-22.04-arm",
+                Ubuntu2204Arm,
+                RunnerOs::Linux,
+                RunnerArch::Arm64,
+            ),
+            (
+                "windows-latest",
+                WindowsLatest,
+                RunnerOs::Windows,
+                RunnerArch::X64,
+            ),
+            (
+                "windows-2025",
+                Windows2025,
+                RunnerOs::Windows,
+                RunnerArch::X64,
+            ),
+            (
+                "windows-2022",
+                Windows2022,
+                RunnerOs::Windows,
+                RunnerArch::X64,
+            ),
+            (
+                "windows-11-arm",
+                Windows11Arm,
+                RunnerOs::Windows,
+                RunnerArch::Arm64,
+            ),
+            (
+                "macos-latest",
+                MacosLatest,
+                RunnerOs::MacOs,
+                RunnerArch::Arm64,
+            ),
+            ("macos-15", Macos15, RunnerOs::MacOs, RunnerArch::Arm64),
+            ("macos-14", Macos14, RunnerOs::MacOs, RunnerArch::Arm64),
+            ("macos-13", Macos13, RunnerOs::MacOs, RunnerArch::X64),
+            (
+                "macos-latest-large",
+                MacosLatestLarge,
+                RunnerOs::MacOs,
+                RunnerArch::X64,
+            ),
+            (
+                "macos-15-large",
+                Macos15Large,
+                RunnerOs::MacOs,
+                RunnerArch::X64,
+            ),
+            (
+                "macos-14-large",
+                Macos14Large,
+                RunnerOs::MacOs,
+                RunnerArch::X64,
+            ),
+            (
+                "macos-13-large",
+                Macos13Large,
+                RunnerOs::MacOs,
+                RunnerArch::X64,
+            ),
+            (
+                "macos-latest-xlarge",
+                MacosLatestXlarge,
+                RunnerOs::MacOs,
+                RunnerArch::Arm64,
+            ),
+            (
+                "macos-15-xlarge",
+                Macos15Xlarge,
+                RunnerOs::MacOs,
+                RunnerArch::Arm64,
+            ),
+            (
+                "macos-14-xlarge",
+                Macos14Xlarge,
+                RunnerOs::MacOs,
+                RunnerArch::Arm64,
+            ),
+            (
+                "macos-13-xlarge",
+                Macos13Xlarge,
+                RunnerOs::MacOs,
+                RunnerArch::Arm64,
+            ),
+        ];
+
+        for (label, runner, os, arch) in vectors {
+            let parsed = label.parse::<KnownRunner>().unwrap();
+            assert_eq!(parsed, runner);
+            assert_eq!(parsed.os(), Some(os), "{label}");
+            assert_eq!(parsed.arch(), Some(arch), "{label}");
+            assert_eq!(parsed.to_string(), label);
+        }
+
+        assert_eq!(
+            "Ubuntu-Latest".parse::<KnownRunner>().unwrap(),
+            UbuntuLatest
+        );
+
+        for label in [
+            "self-hosted",
+            "ubuntu-latest-16-cores",
+            "linux",
+            "${{ matrix.os }}",
+        ] {
+            let parsed = label.parse::<KnownRunner>().unwrap();
+            assert_eq!(parsed, Other(label.into()));
+            assert!(parsed.os().is_none());
+            assert!(parsed.arch().is_none());
+            assert_eq!(parsed.to_string(), label);
+        }
+
+        let runson = serde_yaml::from_str::<RunsOn>("[self-hosted, macos-14]").unwrap();
+        assert_eq!(
+            runson.known_runners().collect::<Vec<_>>(),
+            [Other("self-hosted".into()), Macos14]
+        );
+    }
+
+    #[test]
+    fn test_secret_placement_diagnostics() {
+        let job = serde_yaml::from_str::<ReusableWorkflowCallJob>(
+            r#"
+uses: octo-org/example/.github/workflows/deploy.yml@main
+with:
+  environment: production
+  api-key: ${{ secrets.API_KEY }}
+  token: ${{ secrets.GITHUB_TOKEN }}
+  github-token: ${{ github.token }}
+  retries: 3
+secrets:
+  deploy-key: ${{ secrets.DEPLOY_KEY }}
+  gh-token: ${{ github.token }}
+  region: ${{ vars.REGION }}
+  literal: hunter2
+"#,
+        )
+        .unwrap();
+
+        let diagnostics = job.secret_placement_diagnostics(&[]);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.code.code, d.path.join(".")))
+                .collect::<Vec<_>>(),
+            [
+                ("W104-secret-in-with", "with.api-key".into()),
+                ("W104-secret-in-with", "with.token".into()),
+                ("W105-non-secret-in-secrets", "secrets.region".into()),
+                ("W105-non-secret-in-secrets", "secrets.literal".into()),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "input `api-key` is passed `secrets.API_KEY`; secrets should be passed under \
+             `secrets:` instead"
+        );
+
+        // Callees that take a token as an input can be allowed explicitly,
+        // with case-insensitive matching.
+        let diagnostics = job.secret_placement_diagnostics(&["TOKEN"]);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| d.path.join("."))
+                .collect::<Vec<_>>(),
+            ["with.api-key", "secrets.region", "secrets.literal"]
+        );
+
+        let job = serde_yaml::from_str::<ReusableWorkflowCallJob>(
+            "uses: ./.github/workflows/ci.yml\nwith:\n  foo: bar\nsecrets: inherit",
+        )
+        .unwrap();
+        assert!(job.secret_placement_diagnostics(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_deployment_environment() {
+        let env = |env: &str| serde_yaml::from_str::<DeploymentEnvironment>(env).unwrap();
+
+        let bare = env("production");
+        assert!(
+            matches!(&bare, DeploymentEnvironment::Name(LoE::Literal(name)) if name == "production")
+        );
+        assert_eq!(bare.name(), "production");
+        assert_eq!(bare.url(), None);
+        assert!(!bare.is_dynamic());
+
+        let mapping =
+            env("name: pr-${{ github.event.number }}\nurl: ${{ steps.deploy.outputs.url }}");
+        let DeploymentEnvironment::NameURL {
+            name: LoE::Literal(_),
+            url: Some(LoE::Expr(url)),
+        } = &mapping
+        else {
+            panic!("expected mapping with expression URL");
+        };
+        assert_eq!(url.as_bare(), "steps.deploy.outputs.url");
+        assert_eq!(mapping.name(), "pr-${{ github.event.number }}");
+        assert_eq!(mapping.url(), Some("${{ steps.deploy.outputs.url }}"));
+        assert!(mapping.is_dynamic());
+
+        let dynamic = env("${{ github.ref_name }}");
+        let DeploymentEnvironment::Name(LoE::Expr(name)) = &dynamic else {
+            panic!("expected expression name");
+        };
+        assert_eq!(name.as_bare(), "github.ref_name");
+        assert_eq!(dynamic.name(), "${{ github.ref_name }}");
+        assert!(dynamic.is_dynamic());
+
+        let mapping = env("name: staging\nurl: https://staging.example.com");
+        assert!(!mapping.is_dynamic());
+        assert_eq!(mapping.url(), Some("https://staging.example.com"));
+    }
+
+    #[test]
+    fn test_job_environment() {
+        let job = |job: &str| serde_yaml::from_str::<NormalJob>(job).unwrap();
+
+        let named = job(
+            "runs-on: ubuntu-latest\nenvironment: production\npermissions:\n  contents: write\nsteps: []",
+        );
+        assert!(named.uses_environment());
+        assert_eq!(named.environment_name(), Some("production"));
+        assert!(named.has_write_permission("contents"));
+        assert!(!named.has_write_permission("id-token"));
+        assert!(named.uses_privileged_environment());
+
+        let mapping = job(
+            "runs-on: ubuntu-latest\nenvironment:\n  name: staging\n  url: https://staging.example.com\npermissions: read-all\nsteps: []",
+        );
+        assert!(mapping.uses_environment());
+        assert_eq!(mapping.environment_name(), Some("staging"));
+        assert!(!mapping.has_write_permission("contents"));
+        assert!(!mapping.uses_privileged_environment());
+
+        let none = job("runs-on: ubuntu-latest\npermissions: write-all\nsteps: []");
+        assert!(!none.uses_environment());
+        assert_eq!(none.environment_name(), None);
+        assert!(none.has_write_permission("contents"));
+        assert!(!none.uses_privileged_environment());
+    }
+
+    #[test]
+    fn test_container_ports() {
+        let services = r#"
+postgres:
+  image: postgres:15
+  ports:
+    - 5432:5432
+redis:
+  image: redis
+  ports: ["16379:6379", 6379, "53/udp", "8125:8125/udp"]
+"#;
+        let services = serde_yaml::from_str::<IndexMap<String, Container>>(services).unwrap();
+
+        let port = |host, container, protocol: Option<&str>| ContainerPort {
+            host,
+            container,
+            protocol: protocol.map(Into::into),
+        };
+        let container_ports = |name: &str| {
+            let Container::Container { ports, .. } = &services[name] else {
+                panic!("expected container");
+            };
+            ports
+                .iter()
+                .map(|port| port.container_port().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(container_ports("postgres"), [port(Some(5432), 5432, None)]);
+        assert_eq!(
+            container_ports("redis"),
+            [
+                port(Some(16379), 6379, None),
+                port(None, 6379, None),
+                port(None, 53, Some("udp")),
+                port(Some(8125), 8125, Some("udp")),
+            ]
+        );
+        assert_eq!(container_ports("redis")[3].to_string(), "8125:8125/udp");
+    }
+
+    #[test]
+    fn test_unmodeled_ports() {
+        let container = r#"
+image: postgres
+ports:
+  - 127.0.0.1:5432:5432
+  - 8000-8010:8000-8010
+  - ${{ matrix.port }}:5432
+"#;
+        let Container::Container { ports, .. } = serde_yaml::from_str(container).unwrap() else {
+            panic!("expected container");
+        };
+
+        assert_eq!(ports[0], Port::String("127.0.0.1:5432:5432".into()));
+        assert_eq!(ports[2].to_string(), "${{ matrix.port }}:5432");
+        assert!(ports.iter().all(|port| port.container_port().is_err()));
+    }
+
+    #[test]
+    fn test_port_errors() {
+        let vectors = [
+            (
+                "'1:2:3'",
+                "invalid port mapping `1:2:3`: expected `[host:]container[/protocol]`",
+            ),
+            ("'8080:http'", "port `http` is not in 1-65535"),
+            ("'80/'", "invalid port mapping `80/`: empty protocol"),
+            ("70000", "port `70000` is not in 1-65535"),
+            ("0", "port `0` is not in 1-65535"),
+            (
+                "[80]",
+                "invalid port: expected a number or a `host:container` string",
+            ),
+        ];
+
+        for (port, expected) in vectors {
+            let err = serde_yaml::from_str::<ContainerPort>(port)
+                .unwrap_err()
+                .to_string();
+            assert!(err.starts_with(expected), "{err:?} != {expected:?}");
+        }
+
+        let err: Box<dyn std::error::Error> = Port::String("8080:http".into())
+            .container_port()
+            .unwrap_err()
+            .into();
+        assert_eq!(err.to_string(), "port `http` is not in 1-65535");
+    }
+
+    #[test]
+    fn test_container_image_uses() {
+        let container = |image: &str| serde_yaml::from_str::<Container>(image).unwrap();
+
+        let uses = container("node:18").image_uses().unwrap().unwrap();
+        assert_eq!(
+            uses,
+            DockerUses {
+                registry: None,
+                image: "node".into(),
+                tag: Some("18".into()),
+                hash: None,
+            }
+        );
+
+        let uses = container("image: ghcr.io/octo-org/app:1.2.3")
+            .image_uses()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            uses,
+            DockerUses {
+                registry: Some("ghcr.io".into()),
+                image: "octo-org/app".into(),
+                tag: Some("1.2.3".into()),
+                hash: None,
+            }
+        );
+
+        let uses = container("image: postgres@sha256:abcd")
+            .image_uses()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            uses,
+            DockerUses {
+                registry: None,
+                image: "postgres".into(),
+                tag: None,
+                hash: Some("sha256:abcd".into()),
+            }
+        );
+
+        // Expression-valued images aren't validated.
+        for image in [
+            "image: ${{ matrix.container }}",
+            "node:${{ matrix.node }}",
+            "image: ghcr.io/${{ github.repository }}:latest",
+        ] {
+            let container = container(image);
+            assert!(container.image_is_expression());
+            assert!(container.image_uses().unwrap().is_none());
+        }
+
+        let matrix = container("image: ${{ matrix.container }}");
+        let Container::Container {
+            image: LoE::Expr(expr),
+            ..
+        } = &matrix
+        else {
+            panic!("expected expression image");
+        };
+        assert_eq!(expr.as_bare(), "matrix.container");
+        assert_eq!(matrix.image(), "${{ matrix.container }}");
+
+        assert!(!container("node:18").image_is_expression());
+    }
+
+    #[test]
+    fn test_container_env_expressions() {
+        let container = |container: &str| serde_yaml::from_str::<Container>(container).unwrap();
+
+        let postgres = container(
+            r#"
+image: postgres:15
+env:
+  POSTGRES_USER: ${{ secrets.DB_USER }}
+  POSTGRES_DB: app-${{ matrix.db }}-${{ github.run_id }}
+  POSTGRES_PORT: 5432
+  PLAIN: hello
+"#,
+        );
+        assert_eq!(
+            postgres.env_expressions(),
+            ["secrets.DB_USER", "matrix.db", "github.run_id"]
+        );
+
+        assert_eq!(
+            container("image: postgres:15\nenv: ${{ fromJSON(inputs.env) }}").env_expressions(),
+            ["fromJSON(inputs.env)"]
+        );
+        assert!(container("postgres:15").env_expressions().is_empty());
+    }
+
+    #[test]
+    fn test_matrix_static_combination_count() {
+        let count = |matrix: &str| {
+            serde_yaml::from_str::<Matrix>(matrix)
+                .unwrap()
+                .static_combination_count()
+        };
+
+        assert_eq!(count("os: [a, b]\npython: [1, 2, 3]"), Some(6));
+        assert_eq!(
+            count("os: [a, b]\npython: [1, 2]\nexclude:\n  - os: a\n    python: 1"),
+            Some(3)
+        );
+        // Extends an existing combination.
+        assert_eq!(
+            count("os: [a, b]\ninclude:\n  - os: a\n    extra: true"),
+            Some(2)
+        );
+        // Can't extend without overwriting, so adds a new combination.
+        assert_eq!(count("os: [a, b]\ninclude:\n  - os: c"), Some(3));
+        assert_eq!(count("include:\n  - os: a\n  - os: b"), Some(2));
+        assert_eq!(count("os: ${{ fromJSON(inputs.os) }}"), None);
+        assert_eq!(
+            count("os: [a]\ninclude: ${{ fromJSON(inputs.extra) }}"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_matrix_expand() {
+        let expand = |matrix: &str| serde_yaml::from_str::<Matrix>(matrix).unwrap().expand();
+        let combinations =
+            |yaml: &str| serde_yaml::from_str::<Vec<IndexMap<String, Value>>>(yaml).unwrap();
+
+        // The examples from GitHub's documentation.
+        let matrix = "
+fruit: [apple, pear]
+animal: [cat, dog]
+include:
+  - color: green
+  - color: pink
+    animal: cat
+  - fruit: apple
+    shape: circle
+  - fruit: banana
+  - fruit: banana
+    animal: cat
+";
+        assert_eq!(
+            expand(matrix).unwrap(),
+            combinations(
+                "
+- {fruit: apple, animal: cat, color: pink, shape: circle}
+- {fruit: apple, animal: dog, color: green, shape: circle}
+- {fruit: pear, animal: cat, color: pink}
+- {fruit: pear, animal: dog, color: green}
+- {fruit: banana}
+- {fruit: banana, animal: cat}
+"
+            )
+        );
+
+        let matrix = "
+os: [windows-latest, ubuntu-latest]
+node: [14, 16]
+include:
+  - os: windows-latest
+    node: 16
+    npm: 6
+";
+        assert_eq!(
+            expand(matrix).unwrap(),
+            combinations(
+                "
+- {os: windows-latest, node: 14}
+- {os: windows-latest, node: 16, npm: 6}
+- {os: ubuntu-latest, node: 14}
+- {os: ubuntu-latest, node: 16}
+"
+            )
+        );
+
+        // An include that matches nothing adds a new combination.
+        let matrix = "
+os: [windows-latest, ubuntu-latest]
+version: [10, 12]
+include:
+  - os: windows-latest
+    version: 17
+";
+        assert_eq!(
+            expand(matrix).unwrap(),
+            combinations(
+                "
+- {os: windows-latest, version: 10}
+- {os: windows-latest, version: 12}
+- {os: ubuntu-latest, version: 10}
+- {os: ubuntu-latest, version: 12}
+- {os: windows-latest, version: 17}
+"
+            )
+        );
+
+        let matrix = "
+os: [macos-latest, windows-latest]
+version: [12, 14, 16]
+environment: [staging, production]
+exclude:
+  - os: macos-latest
+    version: 12
+    environment: production
+  - os: windows-latest
+    version: 16
+";
+        let expanded = expand(matrix).unwrap();
+        assert_eq!(expanded.len(), 9);
+        assert!(!expanded
+            .iter()
+            .any(|c| c["os"] == "windows-latest" && c["version"] == 16));
+
+        // Only includes.
+        let matrix = "
+include:
+  - site: production
+    datacenter: site-a
+  - site: staging
+    datacenter: site-b
+";
+        assert_eq!(
+            expand(matrix).unwrap(),
+            combinations(
+                "
+- {site: production, datacenter: site-a}
+- {site: staging, datacenter: site-b}
+"
+            )
+        );
+
+        // Dynamic matrices can't be expanded.
+        assert_eq!(
+            expand("os: [a]\npython: ${{ fromJSON(inputs.python) }}").unwrap_err(),
+            MatrixError::DynamicDimension("python".into())
+        );
+        assert_eq!(
+            expand("os: [a]\ninclude: ${{ fromJSON(inputs.extra) }}").unwrap_err(),
+            MatrixError::DynamicInclude
+        );
+        assert_eq!(
+            expand("os: [a]\nexclude: ${{ fromJSON(inputs.skip) }}")
+                .unwrap_err()
+                .to_string(),
+            "matrix `exclude` is an expression"
+        );
+    }
+
+    #[test]
+    fn test_timeout_minutes() {
+        let timeout = |timeout: &str| {
+            serde_yaml::from_str::<Step>(&format!("run: make\ntimeout-minutes: {timeout}"))
+                .map(|step| step.timeout_minutes.unwrap())
+        };
+
+        for (raw, minutes) in [
+            ("10", 10.0),
+            ("4.5", 4.5),
+            ("'10'", 10.0),
+            ("\" 2.5 \"", 2.5),
+        ] {
+            let LoE::Literal(parsed) = timeout(raw).unwrap() else {
+                panic!("expected literal timeout for {raw}");
+            };
+            assert_eq!(parsed.as_f64(), minutes, "{raw}");
+        }
+
+        let LoE::Expr(expr) = timeout("${{ inputs.timeout }}").unwrap() else {
+            panic!("expected expression timeout");
+        };
+        assert_eq!(expr.as_bare(), "inputs.timeout");
+
+        for invalid in ["-1", "'-0.5'", "'soon'", ".nan", "[1]"] {
+            assert!(timeout(invalid).is_err(), "{invalid}");
+        }
+
+        let job = serde_yaml::from_str::<NormalJob>(
+            "runs-on: ubuntu-latest\ntimeout-minutes: 90.5\nsteps: []",
+        )
+        .unwrap();
+        assert!(
+            matches!(job.timeout_minutes, Some(LoE::Literal(m)) if m == Minutes::try_from(90.5).unwrap())
+        );
+
+        // Whole minutes serialize as integers.
+        assert_eq!(serde_yaml::to_string(&Minutes::from(10)).unwrap(), "10\n");
+        assert_eq!(
+            serde_yaml::to_string(&Minutes::try_from(4.5).unwrap()).unwrap(),
+            "4.5\n"
+        );
+    }
+
+    #[test]
+    fn test_strategy_defaults() {
+        let strategy = |strategy: &str| serde_yaml::from_str::<Strategy>(strategy).unwrap();
+
+        let absent = strategy("{}");
+        assert_eq!(absent.fail_fast(), Some(true));
+        assert_eq!(absent.max_parallel(), None);
+        assert_eq!(absent.job_count(), Some(1));
+
+        let literal =
+            strategy("fail-fast: false\nmax-parallel: 2\nmatrix:\n  os: [a, b]\n  python: [1, 2]");
+        assert_eq!(literal.fail_fast(), Some(false));
+        assert_eq!(literal.max_parallel(), Some(2));
+        assert_eq!(literal.job_count(), Some(4));
+
+        let expr = strategy(
+            "fail-fast: ${{ inputs.fail-fast }}\nmax-parallel: ${{ inputs.jobs }}\n\
+             matrix: ${{ fromJSON(inputs.matrix) }}",
+        );
+        assert_eq!(expr.fail_fast(), None);
+        assert_eq!(expr.max_parallel(), None);
+        assert!(matches!(expr.max_parallel, Some(LoE::Expr(_))));
+        assert_eq!(expr.job_count(), None);
+    }
+
+    #[test]
+    fn test_matrix_validate() {
+        let validate = |matrix: &str| serde_yaml::from_str::<Matrix>(matrix).unwrap().validate();
+
+        let summarize = |diagnostics: Vec<Diagnostic>| {
+            diagnostics
+                .into_iter()
+                .map(|d| (d.code, d.path.join("."), d.message))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(
+            validate("os: [a, b]\nexclude:\n  - os: a\ninclude:\n  - os: c\n    extra: 1")
+                .is_empty()
+        );
+        assert!(validate("include:\n  - os: a").is_empty());
+        assert!(validate(
+            "os: [a]\npython: ${{ fromJSON(inputs.python) }}\nexclude:\n  - python: 3"
+        )
+        .is_empty());
+
+        // Every problem is reported, not just the first.
+        assert_eq!(
+            summarize(validate(
+                "os: [a, b]\npython: []\nexclude:\n  - os: a\n    arch: arm64\n  - abi: x"
+            )),
+            [
+                (
+                    diagnostic::EMPTY_MATRIX_DIMENSION,
+                    "python".into(),
+                    "matrix dimension `python` has no values".into()
+                ),
+                (
+                    diagnostic::UNKNOWN_MATRIX_EXCLUDE_KEY,
+                    "exclude.0.arch".into(),
+                    "matrix `exclude` key `arch` isn't a dimension".into()
+                ),
+                (
+                    diagnostic::UNKNOWN_MATRIX_EXCLUDE_KEY,
+                    "exclude.1.abi".into(),
+                    "matrix `exclude` key `abi` isn't a dimension".into()
+                ),
+            ]
+        );
+
+        let mut matrix = serde_yaml::from_str::<Matrix>("os: [a]").unwrap();
+        let LoE::Literal(dimensions) = &mut matrix.dimensions else {
+            unreachable!();
+        };
+        dimensions.insert("include".into(), LoE::Literal(vec![Value::Null]));
+        assert_eq!(
+            summarize(matrix.validate()),
+            [(
+                diagnostic::RESERVED_MATRIX_DIMENSION,
+                "include".into(),
+                "matrix dimension can't be named `include`".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_job_container_forms() {
+        let job = |container: &str| {
+            serde_yaml::from_str::<NormalJob>(&format!(
+                "runs-on: ubuntu-latest\nsteps: []\n{container}"
+            ))
+            .unwrap()
+        };
+
+        let literal = job("container: node:18");
+        assert!(matches!(
+            literal.container,
+            Some(LoE::Literal(Container::Name(ref image))) if image == "node:18"
+        ));
+
+        let mapping = job("container:\n  image: node:18\n  options: --cpus 1");
+        assert!(matches!(
+            mapping.container,
+            Some(LoE::Literal(Container::Container {
+                image: LoE::Literal(ref image),
+                ..
+            })) if image == "node:18"
+        ));
+
+        let expr = job("container: ${{ matrix.container }}");
+        let Some(LoE::Expr(expr)) = &expr.container else {
+            panic!("expected expression container");
+        };
+        assert_eq!(expr.as_bare(), "matrix.container");
+
+        // A partial expression is still just an image name.
+        let embedded = job("container: node:${{ matrix.node }}");
+        assert!(matches!(
+            embedded.container,
+            Some(LoE::Literal(Container::Name(_)))
+        ));
+
+        let services = job(
+            "services:\n  db: ${{ fromJSON(inputs.db) }}\n  redis: redis\n  pg:\n    image: postgres",
+        )
+        .services;
+        assert!(matches!(services["db"], LoE::Expr(_)));
+        assert!(matches!(
+            services["redis"],
+            LoE::Literal(Container::Name(_))
+        ));
+        assert!(matches!(
+            services["pg"],
+            LoE::Literal(Container::Container { .. })
+        ));
+
+        // Errors within a container aren't collapsed into an untagged enum
+        // error.
+        let err = |container: &str| {
+            serde_yaml::from_str::<NormalJob>(&format!(
+                "runs-on: ubuntu-latest\nsteps: []\n{container}"
+            ))
+            .err()
+            .unwrap()
+            .to_string()
+        };
+        assert!(err("container:\n  options: --cpus 1").contains("missing field `image`"));
+        assert!(
+            err("services:\n  pg:\n    image: postgres\n    ports: [{}]")
+                .contains("invalid port: expected a number")
+        );
+        assert!(err("container: [node]").contains("expected an image name or a mapping"));
+    }
+
+    #[test]
+    fn test_docker_credentials() {
+        let credentials = |creds: &str| {
+            let container = format!("image: ghcr.io/octo-org/app\ncredentials:\n{creds}");
+            let Container::Container { credentials, .. } =
+                serde_yaml::from_str::<Container>(&container).unwrap()
+            else {
+                panic!("expected container");
+            };
+            credentials
+        };
+
+        let creds = credentials(
+            "  username: ${{ github.actor }}\n  password: ${{ secrets.REGISTRY_PASSWORD }}",
+        )
+        .unwrap();
+        assert!(matches!(creds.username, Some(LoE::Expr(_))));
+        let Some(LoE::Expr(password)) = &creds.password else {
+            panic!("expected expression password");
+        };
+        assert_eq!(password.as_bare(), "secrets.REGISTRY_PASSWORD");
+        assert!(!creds.has_static_password());
+
+        let creds = credentials("  username: octocat\n  password: hunter2").unwrap();
+        assert_eq!(creds.username, Some(LoE::Literal("octocat".into())));
+        assert_eq!(creds.password, Some(LoE::Literal("hunter2".into())));
+        assert!(creds.has_static_password());
+
+        let creds = credentials("  username: octocat").unwrap();
+        assert!(creds.password.is_none());
+        assert!(!creds.has_static_password());
+
+        let container = serde_yaml::from_str::<Container>("image: node:18").unwrap();
+        assert!(matches!(
+            container,
+            Container::Container {
+                credentials: None,
+                ..
+            }
+        ));
+    }
 }
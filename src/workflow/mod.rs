@@ -7,33 +7,961 @@
 //! [Workflow Syntax for GitHub Actions]: https://docs.github.com/en/actions/using-workflows/workflow-syntax-for-github-actions>
 //! [JSON Schema definition for workflows]: https://json.schemastore.org/github-workflow.json
 
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    ops::Range,
+    path::Path,
+    str::FromStr,
+};
+
 use indexmap::IndexMap;
-use serde::Deserialize;
+use serde::{de, Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
 
 use crate::common::{
-    expr::{BoE, LoE},
-    Env, Permissions,
+    diagnostic::{self, Diagnostic},
+    expr::{self, BoE, ContextTrust, LoE},
+    Env, IdentifierKind, If, Permissions, Shell, UnknownKey, Uses,
 };
 
 pub mod event;
+mod expressions;
+pub mod graph;
 pub mod job;
+pub mod visit;
+
+use graph::DependencyGraph;
 
 /// A single GitHub Actions workflow.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Workflow {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
-    pub run_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_name: Option<LoE<String>>,
     pub on: Trigger,
-    #[serde(default)]
-    pub permissions: Permissions,
-    #[serde(default)]
+    /// This workflow's `permissions`, or `None` if unset, in which case
+    /// the repository or organization's defaults apply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
+    #[serde(default, skip_serializing_if = "crate::common::is_default")]
     pub env: LoE<Env>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub defaults: Option<Defaults>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub concurrency: Option<Concurrency>,
     pub jobs: IndexMap<String, Job>,
+
+    /// Any other top-level keys.
+    ///
+    /// See [`Workflow::unknown_keys`] for collecting these from the whole
+    /// workflow.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
 }
 
+impl FromStr for Workflow {
+    type Err = crate::Error;
+
+    /// Parse a workflow from a YAML string.
+    ///
+    /// ```
+    /// use github_actions_models::workflow::Workflow;
+    ///
+    /// let workflow: Workflow = "
+    /// on: push
+    /// jobs:
+    ///   test:
+    ///     runs-on: ubuntu-latest
+    ///     steps:
+    ///       - run: make test
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// assert_eq!(workflow.jobs.len(), 1);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::common::from_yaml(s)
+    }
+}
+
+impl Workflow {
+    /// Parse a workflow from raw YAML bytes.
+    ///
+    /// The bytes are decoded as UTF-8 (with or without a BOM), or as
+    /// UTF-16LE/BE if they begin with the corresponding BOM. Invalid
+    /// sequences produce [`crate::Error::Encoding`] rather than being replaced.
+    pub fn from_slice(contents: &[u8]) -> Result<Self, crate::Error> {
+        crate::common::from_yaml_slice(contents)
+    }
+
+    /// Parse a workflow from a YAML string, like [`FromStr`], but also
+    /// reject keys that GitHub doesn't accept, like `timeout_minutes` in
+    /// place of `timeout-minutes`.
+    ///
+    /// The first of [`Self::unknown_keys`], if any, produces a
+    /// [`crate::Error::UnknownKey`] with its path and location.
+    ///
+    /// ```
+    /// use github_actions_models::workflow::Workflow;
+    ///
+    /// let workflow = "
+    /// on: push
+    /// jobs:
+    ///   test:
+    ///     runs-on: ubuntu-latest
+    ///     timeout_minutes: 10
+    ///     steps: []
+    /// ";
+    ///
+    /// assert!(workflow.parse::<Workflow>().is_ok());
+    /// let Err(err) = Workflow::from_str_strict(workflow) else { unreachable!() };
+    /// assert_eq!(err.path(), Some("jobs.test.timeout_minutes"));
+    /// ```
+    pub fn from_str_strict(s: &str) -> Result<Self, crate::Error> {
+        crate::common::strict::from_yaml_strict(s, Self::unknown_keys)
+    }
+
+    /// Load a workflow from the file at `path`.
+    ///
+    /// The file's contents are decoded as with [`Self::from_slice`].
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, crate::LoadError> {
+        crate::common::from_path(path.as_ref())
+    }
+
+    /// Returns the body of each expression in this workflow's `run-name`,
+    /// whether it's a whole expression or a template like
+    /// `Deploy by @${{ github.actor }}`.
+    pub fn run_name_expressions(&self) -> Vec<&str> {
+        self.run_name
+            .as_ref()
+            .map(|run_name| {
+                expr::interpolations(run_name.as_str())
+                    .into_iter()
+                    .map(|(_, body)| body)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Replace the job `job_id` with one parsed from `fragment`, which is
+    /// the job's body (i.e. everything beneath `jobs.<job_id>:`).
+    ///
+    /// This is intended for editors, where re-parsing one job is much
+    /// cheaper than re-parsing a large workflow. The rules are:
+    ///
+    /// * The new job keeps the old job's position in [`Workflow::jobs`].
+    ///   If there's no job `job_id`, the new job is appended.
+    /// * The fragment may change the job's kind, e.g. from a normal job
+    ///   to a reusable workflow call.
+    /// * If the fragment doesn't parse, the workflow is left unchanged.
+    ///
+    /// [`Workflow`] doesn't cache anything derived from its jobs, so
+    /// nothing else needs to be invalidated.
+    pub fn reparse_job(&mut self, job_id: &str, fragment: &str) -> Result<(), crate::Error> {
+        let job = crate::common::from_yaml::<Job>(fragment)?;
+
+        match self.jobs.get_mut(job_id) {
+            Some(existing) => *existing = job,
+            None => {
+                self.jobs.insert(job_id.into(), job);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace step `index` of the normal job `job_id` with one parsed
+    /// from `fragment`, which is the step's body (i.e. the mapping after
+    /// its `-`).
+    ///
+    /// Like [`Workflow::reparse_job`], the workflow is left unchanged if
+    /// the fragment doesn't parse. Unlike it, the step must already exist:
+    /// a missing job or step, or a reusable workflow call job (which has
+    /// no steps), produces [`crate::Error::MissingTarget`].
+    pub fn reparse_step(
+        &mut self,
+        job_id: &str,
+        index: usize,
+        fragment: &str,
+    ) -> Result<(), crate::Error> {
+        let missing = || crate::Error::MissingTarget {
+            job: job_id.into(),
+            step: Some(index),
+        };
+
+        let job = match self.jobs.get_mut(job_id) {
+            Some(Job::NormalJob(job)) => job,
+            Some(Job::ReusableWorkflowCallJob(_)) => return Err(missing()),
+            None => {
+                return Err(crate::Error::MissingTarget {
+                    job: job_id.into(),
+                    step: None,
+                })
+            }
+        };
+        let Some(existing) = job.steps.get_mut(index) else {
+            return Err(missing());
+        };
+
+        *existing = crate::common::from_yaml(fragment)?;
+        Ok(())
+    }
+
+    /// Check this workflow's job and `workflow_call` output names,
+    /// returning a [`Diagnostic`] for each problem found.
+    ///
+    /// These are diagnostics rather than parse errors, since GitHub
+    /// accepts the workflow; the affected outputs are just unusable:
+    ///
+    /// * An output whose name isn't an identifier (like `foo.bar`) can't
+    ///   be referenced with `needs.<job>.outputs.<name>`.
+    /// * A job that references such an output from a job it `needs` gets
+    ///   a specific diagnostic, rather than a generic undefined-output one.
+    ///
+    /// Only literal property references are checked; index references
+    /// like `needs.build.outputs['foo.bar']` are assumed to be fine.
+    pub fn output_diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        if let Trigger::Events(events) = &self.on {
+            if let event::OptionalBody::Body(call) = &events.workflow_call {
+                for name in call.unreachable_output_names() {
+                    diagnostics.push(Diagnostic::new(
+                        diagnostic::UNREACHABLE_OUTPUT_NAME,
+                        ["on", "workflow_call", "outputs", name],
+                        format!(
+                            "workflow_call output name `{name}` cannot be referenced from expressions"
+                        ),
+                    ));
+                }
+            }
+        }
+
+        for (id, job) in &self.jobs {
+            if let Job::NormalJob(job) = job {
+                for name in job.unreachable_output_names() {
+                    diagnostics.push(Diagnostic::new(
+                        diagnostic::UNREACHABLE_OUTPUT_NAME,
+                        ["jobs", id, "outputs", name],
+                        format!(
+                            "job `{id}` output name `{name}` cannot be referenced from expressions"
+                        ),
+                    ));
+                }
+            }
+        }
+
+        for (id, job) in &self.jobs {
+            let (needs, bodies) = match job {
+                Job::NormalJob(job) => (&job.needs, job.expression_bodies()),
+                Job::ReusableWorkflowCallJob(job) => (&job.needs, job.expression_bodies()),
+            };
+
+            for (needed, path) in bodies.into_iter().flat_map(needs_output_refs) {
+                // References to jobs that aren't in `needs` are a different
+                // problem; we also can't see the outputs of reusable workflows.
+                if !needs.iter().any(|n| n == needed) {
+                    continue;
+                }
+                let Some(Job::NormalJob(producer)) = self.jobs.get(needed) else {
+                    continue;
+                };
+
+                let name = path.split('.').next().unwrap_or(path);
+                if producer.outputs.contains_key(name) {
+                    continue;
+                }
+
+                let diagnostic = match producer.unreachable_output_names().find(|unreachable| {
+                    path.strip_prefix(unreachable)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+                }) {
+                    Some(unreachable) => Diagnostic::new(
+                        diagnostic::UNREACHABLE_OUTPUT_REFERENCE,
+                        ["jobs", id],
+                        format!(
+                            "job `{id}` references `needs.{needed}.outputs.{unreachable}`, \
+                             but output name `{unreachable}` cannot be referenced from expressions"
+                        ),
+                    ),
+                    None => Diagnostic::new(
+                        diagnostic::UNDEFINED_OUTPUT_REFERENCE,
+                        ["jobs", id],
+                        format!(
+                            "job `{id}` references undefined output `{name}` of job `{needed}`"
+                        ),
+                    ),
+                };
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Returns every event that triggers this workflow, regardless of
+    /// the form of its `on:`, in sorted order without duplicates.
+    ///
+    /// This includes [`event::EventKind::Schedule`], which has no
+    /// [`event::BareEvent`] equivalent.
+    pub fn triggers(&self) -> Vec<event::EventKind> {
+        self.on.normalized().into_iter().collect()
+    }
+
+    /// Returns whether this workflow is triggered by the given event.
+    ///
+    /// ```
+    /// use github_actions_models::workflow::{event::BareEvent, Workflow};
+    ///
+    /// let workflow: Workflow = "
+    /// on: [push, pull_request_target]
+    /// jobs: {}
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// assert!(workflow.has_trigger(BareEvent::PullRequestTarget));
+    /// assert!(!workflow.has_trigger(BareEvent::PullRequest));
+    /// ```
+    pub fn has_trigger(&self, event: impl Into<event::EventKind>) -> bool {
+        self.on.has_event(event.into())
+    }
+
+    /// Returns an iterator over this workflow's normal (i.e. non-reusable)
+    /// jobs and their IDs, in definition order.
+    pub fn normal_jobs(&self) -> impl Iterator<Item = (&str, &job::NormalJob)> {
+        self.jobs
+            .iter()
+            .filter_map(|(id, job)| Some((id.as_str(), job.as_normal()?)))
+    }
+
+    /// Returns an iterator over this workflow's reusable workflow call
+    /// jobs and their IDs, in definition order.
+    pub fn reusable_jobs(&self) -> impl Iterator<Item = (&str, &job::ReusableWorkflowCallJob)> {
+        self.jobs
+            .iter()
+            .filter_map(|(id, job)| Some((id.as_str(), job.as_reusable()?)))
+    }
+
+    /// Returns the normal job with the given `id`, if there is one.
+    ///
+    /// Returns `None` if `id` names a reusable workflow call job.
+    pub fn get_normal_job(&self, id: &str) -> Option<&job::NormalJob> {
+        self.jobs.get(id)?.as_normal()
+    }
+
+    /// Returns a [`StepHandle`] for every step in every normal job in this
+    /// workflow, in definition order.
+    ///
+    /// Reusable workflow call jobs have no steps, so they're skipped; see
+    /// [`Workflow::reusable_jobs`] to visit them.
+    pub fn steps(&self) -> impl Iterator<Item = StepHandle<'_>> {
+        self.normal_jobs().flat_map(|(job_id, job)| {
+            job.steps
+                .iter()
+                .enumerate()
+                .map(move |(index, step)| StepHandle {
+                    job_id,
+                    job,
+                    index,
+                    step,
+                })
+        })
+    }
+
+    /// Returns every `uses:` reference in this workflow, along with where
+    /// it appears: each reusable workflow call job's `uses:`, and each
+    /// step's `uses:`.
+    ///
+    /// References are yielded in definition order, by job and then by step.
+    ///
+    /// Job `container` and `services` images aren't included, since
+    /// they aren't `uses:` clauses; see
+    /// [`Container::image_uses`](job::Container::image_uses) for those.
+    pub fn uses(&self) -> impl Iterator<Item = (&Uses, UsesContext<'_>)> {
+        self.jobs.iter().flat_map(|(job_id, job)| {
+            let (call, steps) = match job {
+                Job::ReusableWorkflowCallJob(job) => (Some(&job.uses), [].as_slice()),
+                Job::NormalJob(job) => (None, job.steps.as_slice()),
+            };
+
+            let call = call.map(|uses| (uses, UsesContext::ReusableJob { job_id }));
+            let steps = steps.iter().enumerate().filter_map(move |(index, step)| {
+                Some((step.uses()?, UsesContext::Step { job_id, index }))
+            });
+
+            call.into_iter().chain(steps)
+        })
+    }
+
+    /// Returns an iterator over every step in every (normal) job in this
+    /// workflow, in definition order.
+    ///
+    /// Each item is `(job_id, step_id, step)`, where `step_id` is the
+    /// step's `id` if it has one, or its index within the job otherwise.
+    pub fn all_steps(&self) -> impl Iterator<Item = (&str, Cow<'_, str>, &job::Step)> {
+        self.jobs.iter().flat_map(|(job_id, job)| {
+            let steps = match job {
+                Job::NormalJob(job) => job.steps.as_slice(),
+                Job::ReusableWorkflowCallJob(_) => &[],
+            };
+
+            steps.iter().enumerate().map(move |(idx, step)| {
+                let step_id = match &step.id {
+                    Some(id) => Cow::Borrowed(id.as_str()),
+                    None => Cow::Owned(idx.to_string()),
+                };
+                (job_id.as_str(), step_id, step)
+            })
+        })
+    }
+
+    /// Returns an iterator over every `run:` step in this workflow.
+    pub fn all_run_steps(&self) -> impl Iterator<Item = &job::Step> {
+        self.all_steps()
+            .map(|(_, _, step)| step)
+            .filter(|step| matches!(step.body, job::StepBody::Run { .. }))
+    }
+
+    /// Returns an iterator over every `uses:` step in this workflow.
+    pub fn all_uses_steps(&self) -> impl Iterator<Item = &job::Step> {
+        self.all_steps()
+            .map(|(_, _, step)| step)
+            .filter(|step| matches!(step.body, job::StepBody::Uses { .. }))
+    }
+
+    /// Returns every expression interpolated into a `run:` script in this
+    /// workflow, along with the contexts it references and how far they
+    /// can be trusted.
+    ///
+    /// This is the primary query for finding template injection risks:
+    /// a [`ContextTrust::Untrusted`] interpolation means an external
+    /// attacker may be able to inject code into the script.
+    pub fn script_interpolations(&self) -> impl Iterator<Item = ScriptInterpolation<'_>> {
+        let mut findings = vec![];
+
+        for (job_id, job) in &self.jobs {
+            let Job::NormalJob(job) = job else {
+                continue;
+            };
+
+            for (step_index, step) in job.steps.iter().enumerate() {
+                let job::StepBody::Run { run, .. } = &step.body else {
+                    continue;
+                };
+
+                for (span, expression) in expr::interpolations(run) {
+                    let contexts = expr::context_paths(expression)
+                        .into_iter()
+                        .map(|context| (context, ContextTrust::of(context)))
+                        .collect::<Vec<_>>();
+                    let trust = contexts
+                        .iter()
+                        .map(|(_, trust)| *trust)
+                        .max()
+                        .unwrap_or(ContextTrust::Trusted);
+
+                    findings.push(ScriptInterpolation {
+                        job_id,
+                        step_index,
+                        step,
+                        span,
+                        expression,
+                        contexts,
+                        trust,
+                    });
+                }
+            }
+        }
+
+        findings.into_iter()
+    }
+
+    /// Returns every expression in this workflow, along with where it
+    /// was found.
+    ///
+    /// This includes expressions interpolated into longer strings, like
+    /// `run:` scripts, as well as `if:` conditions, which are expressions
+    /// even without `${{ }}`. Expressions are grouped by job and then by
+    /// step, after the workflow-level ones.
+    ///
+    /// Keys are spelled as in YAML, e.g. `timeout-minutes`. Fields that
+    /// GitHub never evaluates expressions in, like `needs`, `uses` and
+    /// most of `on:`, aren't searched.
+    pub fn expressions(&self) -> impl Iterator<Item = WorkflowExpression<'_>> {
+        expressions::Expressions::collect(self).into_iter()
+    }
+
+    /// Returns the IDs of the jobs that deploy to each environment in this
+    /// workflow, keyed by environment name.
+    ///
+    /// Environments are listed in order of first appearance. See
+    /// [`job::NormalJob::environment_gate`] for the per-job details.
+    pub fn environment_gates(&self) -> IndexMap<job::NameKind<'_>, Vec<&str>> {
+        let mut gates = IndexMap::<_, Vec<_>>::new();
+
+        for (job_id, job) in &self.jobs {
+            let Job::NormalJob(job) = job else {
+                continue;
+            };
+
+            if let Some(gate) = job.environment_gate() {
+                gates.entry(gate.name).or_default().push(job_id.as_str());
+            }
+        }
+
+        gates
+    }
+
+    /// Returns this workflow's jobs ordered so that each job comes after
+    /// every job it `needs`.
+    ///
+    /// Jobs with no `needs` come first, and ties are otherwise broken by
+    /// declaration order. `needs` entries that don't name a job in this
+    /// workflow are ignored.
+    ///
+    /// Returns a [`CycleError`] if the jobs' `needs` form a cycle.
+    pub fn jobs_in_topological_order(&self) -> Result<Vec<(&str, &Job)>, CycleError> {
+        Ok(self
+            .dependency_graph()
+            .topological_order()?
+            .into_iter()
+            .map(|id| (id, &self.jobs[id]))
+            .collect())
+    }
+
+    /// Check that each job's `needs` entries name other jobs in this
+    /// workflow, returning an [`UNKNOWN_NEED`](diagnostic::UNKNOWN_NEED) or
+    /// [`SELF_NEED`](diagnostic::SELF_NEED) diagnostic for each entry
+    /// that doesn't.
+    ///
+    /// Diagnostics are in job declaration order, and then `needs` order.
+    /// An unknown job's diagnostic suggests the closest job ID, if any is
+    /// close enough.
+    ///
+    /// ```
+    /// use github_actions_models::{common::diagnostic, workflow::Workflow};
+    ///
+    /// let workflow: Workflow = "
+    /// on: push
+    /// jobs:
+    ///   build:
+    ///     runs-on: ubuntu-latest
+    ///     steps: []
+    ///   test:
+    ///     needs: biuld
+    ///     runs-on: ubuntu-latest
+    ///     steps: []
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// let diagnostics = workflow.validate_needs();
+    /// assert_eq!(diagnostics[0].code, diagnostic::UNKNOWN_NEED);
+    /// assert_eq!(diagnostics[0].path, ["jobs", "test", "needs"]);
+    /// assert_eq!(
+    ///     diagnostics[0].message,
+    ///     "job `test` needs unknown job `biuld`; did you mean `build`?"
+    /// );
+    /// ```
+    pub fn validate_needs(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        for (job_id, job) in &self.jobs {
+            let path = ["jobs", job_id, "needs"];
+
+            for need in job.needs() {
+                let diagnostic = if need == job_id {
+                    Diagnostic::new(
+                        diagnostic::SELF_NEED,
+                        path,
+                        format!("job `{job_id}` needs itself"),
+                    )
+                } else if self.jobs.contains_key(need) {
+                    continue;
+                } else {
+                    let others = self.jobs.keys().filter(|id| *id != job_id);
+                    let message = match crate::common::closest_match(
+                        need,
+                        others.map(String::as_str),
+                    ) {
+                        Some(suggestion) => format!(
+                            "job `{job_id}` needs unknown job `{need}`; did you mean `{suggestion}`?"
+                        ),
+                        None => format!("job `{job_id}` needs unknown job `{need}`"),
+                    };
+                    Diagnostic::new(diagnostic::UNKNOWN_NEED, path, message)
+                };
+
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Check that each job ID and step `id` in this workflow is a valid
+    /// identifier (see [`is_identifier`](crate::common::is_identifier)),
+    /// returning an [`INVALID_IDENTIFIER`](diagnostic::INVALID_IDENTIFIER)
+    /// diagnostic for each one that isn't.
+    ///
+    /// Results are in declaration order: each job's ID comes before its
+    /// steps' IDs.
+    pub fn validate_identifiers(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        for (job_id, job) in &self.jobs {
+            diagnostics.extend(crate::common::invalid_identifiers(
+                IdentifierKind::Job,
+                [Some(job_id.as_str())],
+                None,
+            ));
+
+            if let Job::NormalJob(job) = job {
+                diagnostics.extend(crate::common::invalid_identifiers(
+                    IdentifierKind::Step,
+                    job.steps.iter().map(|step| step.id.as_deref()),
+                    Some(job_id),
+                ));
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Check each job in this workflow for duplicate step `id`s, as with
+    /// [`NormalJob::duplicate_step_ids`](job::NormalJob::duplicate_step_ids),
+    /// returning diagnostics whose paths are relative to the workflow.
+    ///
+    /// Diagnostics are in job declaration order, and then step order.
+    pub fn validate_step_ids(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        for (job_id, job) in &self.jobs {
+            let Job::NormalJob(job) = job else {
+                continue;
+            };
+
+            for mut diagnostic in job.duplicate_step_ids() {
+                diagnostic
+                    .path
+                    .splice(0..0, ["jobs".to_string(), job_id.clone()]);
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Returns every key that this workflow's models didn't recognize, such
+    /// as `time-out` in place of `timeout-minutes`.
+    ///
+    /// Unlike [`Self::from_str_strict`], this doesn't reject the workflow,
+    /// and returns all such keys rather than the first. Keys are collected
+    /// from the workflow's top level, the events in `on:` (including
+    /// `schedule` entries and `workflow_call`/`workflow_dispatch` inputs,
+    /// outputs and secrets), and each job and its steps, in that order.
+    ///
+    /// ```
+    /// use github_actions_models::workflow::Workflow;
+    ///
+    /// let workflow: Workflow = "
+    /// on: push
+    /// jobs:
+    ///   test:
+    ///     runs-on: ubuntu-latest
+    ///     time-out: 10
+    ///     steps: []
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// let unknown = workflow.unknown_keys();
+    /// assert_eq!(unknown.len(), 1);
+    /// assert_eq!(unknown[0].path, "jobs.test.time-out");
+    /// ```
+    pub fn unknown_keys(&self) -> Vec<UnknownKey<'_>> {
+        use crate::common::strict::child;
+
+        let mut unknown: Vec<_> = crate::common::unknown_keys("", &self.extra).collect();
+
+        if let Trigger::Events(events) = &self.on {
+            unknown.extend(events.unknown_keys("on"));
+        }
+
+        for (job_id, job) in &self.jobs {
+            let path = child("jobs", job_id);
+            match job {
+                Job::NormalJob(job) => {
+                    unknown.extend(crate::common::unknown_keys(&path, &job.extra));
+                    for (index, step) in job.steps.iter().enumerate() {
+                        let path = format!("{path}.steps[{index}]");
+                        unknown.extend(crate::common::unknown_keys(&path, &step.extra));
+                    }
+                }
+                Job::ReusableWorkflowCallJob(job) => {
+                    unknown.extend(crate::common::unknown_keys(&path, &job.extra));
+                }
+            }
+        }
+
+        unknown
+    }
+
+    /// Returns this workflow's job dependency graph, built from each job's
+    /// `needs`.
+    ///
+    /// ```
+    /// use github_actions_models::workflow::Workflow;
+    ///
+    /// let workflow: Workflow = "
+    /// on: push
+    /// jobs:
+    ///   build:
+    ///     runs-on: ubuntu-latest
+    ///     steps: []
+    ///   test:
+    ///     needs: [build, lint]
+    ///     runs-on: ubuntu-latest
+    ///     steps: []
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// let graph = workflow.dependency_graph();
+    /// assert_eq!(graph.roots(), ["build"]);
+    /// assert_eq!(graph.topological_order().unwrap(), ["build", "test"]);
+    /// assert_eq!(graph.unknown_needs()[0].need, "lint");
+    /// ```
+    pub fn dependency_graph(&self) -> DependencyGraph<'_> {
+        DependencyGraph::new(&self.jobs)
+    }
+
+    /// Render this workflow's job dependency graph as a Mermaid
+    /// flowchart (`graph TD`).
+    ///
+    /// Each job is a node labeled with its display name (or its ID, if
+    /// unnamed), with an edge from each of its `needs` to it. Reusable
+    /// workflow calls are drawn as subroutines and styled with the
+    /// `reusable` class, and matrix jobs are annotated with the number of
+    /// jobs they expand into when that's statically known.
+    ///
+    /// Node IDs are job IDs prefixed with `job_`, so that IDs like `end`
+    /// don't collide with Mermaid keywords. `needs` entries that don't name
+    /// a job in this workflow (see [`Workflow::validate_needs`]) aren't
+    /// drawn.
+    pub fn to_mermaid(&self) -> String {
+        // NOTE: Job IDs that GitHub accepts are already valid in Mermaid
+        // IDs; anything else is replaced, rather than breaking the chart.
+        fn node(id: &str) -> String {
+            let id = id.replace(
+                |c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_',
+                "_",
+            );
+            format!("job_{id}")
+        }
+
+        let mut out = String::from("graph TD\n");
+
+        for (id, job) in &self.jobs {
+            let mut label = job.name().unwrap_or(id).to_string();
+            let strategy = match job {
+                Job::NormalJob(job) => job.strategy.as_ref(),
+                Job::ReusableWorkflowCallJob(job) => job.strategy.as_ref(),
+            };
+            if let Some(LoE::Literal(matrix)) = strategy.and_then(|s| s.matrix.as_ref()) {
+                match matrix.static_combination_count() {
+                    Some(1) => label.push_str(" (matrix: 1 job)"),
+                    Some(count) => label.push_str(&format!(" (matrix: {count} jobs)")),
+                    None => label.push_str(" (matrix)"),
+                }
+            }
+            let label = label.replace('"', "#quot;");
+
+            let node = node(id);
+            match job {
+                Job::NormalJob(_) => out.push_str(&format!("    {node}[\"{label}\"]\n")),
+                Job::ReusableWorkflowCallJob(_) => {
+                    out.push_str(&format!("    {node}[[\"{label}\"]]\n"))
+                }
+            }
+        }
+
+        for (id, job) in &self.jobs {
+            let needs = match job {
+                Job::NormalJob(job) => &job.needs,
+                Job::ReusableWorkflowCallJob(job) => &job.needs,
+            };
+            for need in needs.iter().filter(|need| self.jobs.contains_key(*need)) {
+                out.push_str(&format!("    {} --> {}\n", node(need), node(id)));
+            }
+        }
+
+        let reusable = self
+            .jobs
+            .iter()
+            .filter(|(_, job)| matches!(job, Job::ReusableWorkflowCallJob(_)))
+            .map(|(id, _)| node(id))
+            .collect::<Vec<_>>();
+        if !reusable.is_empty() {
+            out.push_str("    classDef reusable stroke-dasharray: 5 5\n");
+            out.push_str(&format!("    class {} reusable\n", reusable.join(",")));
+        }
+
+        out
+    }
+
+    /// Merge workflow-level settings from `base` into this workflow,
+    /// with this workflow's own settings taking precedence.
+    ///
+    /// This is intended for templating systems, where `base` is a shared
+    /// fragment and `self` is a per-repository overlay. The rules are:
+    ///
+    /// * `on`: merged with [`Trigger::merge`].
+    /// * `env`: the union of both mappings, with this workflow's values
+    ///   winning on conflicts. If either side is an expression, it cannot
+    ///   be merged: this workflow's `env` is kept unless it's empty.
+    /// * `permissions`: this workflow's permissions replace `base`'s,
+    ///   unless this workflow doesn't specify any. An explicit
+    ///   `permissions:` always wins, even if it matches the default.
+    /// * `defaults`: merged per-field, with this workflow's
+    ///   `defaults.run.shell` and `defaults.run.working-directory` winning.
+    /// * `concurrency`: this workflow's concurrency replaces `base`'s,
+    ///   unless this workflow doesn't specify one.
+    ///
+    /// Jobs and other fields are left untouched.
+    pub fn merge_defaults(&mut self, base: &Workflow) {
+        self.on = Trigger::merge(&base.on, &self.on);
+
+        match (&base.env, &mut self.env) {
+            (LoE::Literal(base_env), LoE::Literal(env)) => {
+                let mut merged = base_env.clone();
+                merged.extend(env.drain(..));
+                *env = merged;
+            }
+            (_, LoE::Literal(env)) if env.is_empty() => self.env = base.env.clone(),
+            _ => {}
+        }
+
+        if self.permissions.is_none() {
+            self.permissions = base.permissions.clone();
+        }
+
+        self.defaults = match (&base.defaults, self.defaults.take()) {
+            (Some(base), Some(defaults)) => Some(Defaults::merge(base, defaults)),
+            (base, defaults) => defaults.or_else(|| base.clone()),
+        };
+
+        if self.concurrency.is_none() {
+            self.concurrency = base.concurrency.clone();
+        }
+    }
+}
+
+/// Where a `uses:` reference appears in a workflow, as returned by
+/// [`Workflow::uses`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UsesContext<'a> {
+    /// A step's `uses:`.
+    Step {
+        /// The ID of the job containing the step.
+        job_id: &'a str,
+        /// The index of the step within its job.
+        index: usize,
+    },
+    /// A reusable workflow call job's `uses:`.
+    ReusableJob {
+        /// The ID of the job.
+        job_id: &'a str,
+    },
+}
+
+/// A step, along with the job that contains it, as returned by
+/// [`Workflow::steps`].
+#[derive(Clone, Copy)]
+pub struct StepHandle<'a> {
+    /// The ID of the job containing the step.
+    pub job_id: &'a str,
+    /// The job containing the step, e.g. for its `permissions` or `env`.
+    pub job: &'a job::NormalJob,
+    /// The index of the step within its job.
+    pub index: usize,
+    /// The step itself.
+    pub step: &'a job::Step,
+}
+
+/// An expression interpolated into a `run:` script, as returned by
+/// [`Workflow::script_interpolations`].
+#[derive(Clone)]
+pub struct ScriptInterpolation<'a> {
+    /// The ID of the job containing the step.
+    pub job_id: &'a str,
+    /// The index of the step within its job.
+    pub step_index: usize,
+    /// The step itself.
+    pub step: &'a job::Step,
+    /// The byte span of the entire `${{ ... }}` fragment within the
+    /// step's `run:` script.
+    pub span: Range<usize>,
+    /// The expression's body, e.g. `github.event.issue.title`.
+    pub expression: &'a str,
+    /// Each context referenced by the expression, with its trust level.
+    pub contexts: Vec<(&'a str, ContextTrust)>,
+    /// The least trustworthy of the referenced contexts, or
+    /// [`ContextTrust::Trusted`] if the expression references none.
+    pub trust: ContextTrust,
+}
+
+/// An expression found in a workflow, as returned by
+/// [`Workflow::expressions`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkflowExpression<'a> {
+    /// Where the expression was found.
+    pub location: ExpressionLocation<'a>,
+    /// The expression's body, e.g. `matrix.os` for `${{ matrix.os }}`.
+    pub expression: String,
+}
+
+/// Where an expression appears in a workflow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpressionLocation<'a> {
+    /// The ID of the job containing the expression, or `None` for
+    /// workflow-level keys like `run-name`.
+    pub job_id: Option<&'a str>,
+    /// The index of the step containing the expression within its job,
+    /// or `None` for job-level keys.
+    pub step_index: Option<usize>,
+    /// The path to the expression's key, relative to the step, job, or
+    /// workflow, e.g. `with.version` or `strategy.matrix.include[0].os`.
+    pub key: String,
+}
+
+/// A cycle in a workflow's job dependencies, as returned by
+/// [`Workflow::jobs_in_topological_order`] and [`DependencyGraph::cycles`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CycleError {
+    /// The IDs of the jobs in the cycle, each of which `needs` the next
+    /// (and the last of which `needs` the first).
+    pub jobs: Vec<String>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "job dependency cycle: ")?;
+        for id in &self.jobs {
+            write!(f, "{id} -> ")?;
+        }
+        write!(f, "{}", self.jobs[0])
+    }
+}
+
+impl std::error::Error for CycleError {}
+
 /// The triggering condition or conditions for a workflow.
 ///
 /// Workflow triggers take three forms:
@@ -57,7 +985,7 @@ pub struct Workflow {
 ///         branches: [main]
 ///       pull_request:
 ///     ```
-#[derive(Deserialize)]
+#[derive(Clone, Serialize)]
 #[serde(rename_all = "snake_case", untagged)]
 pub enum Trigger {
     BareEvent(event::BareEvent),
@@ -65,37 +993,249 @@ pub enum Trigger {
     Events(Box<event::Events>),
 }
 
-#[derive(Deserialize)]
+impl<'de> Deserialize<'de> for Trigger {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // NOTE: Like `Job`, we discriminate on the shape of `on:` ourselves
+        // rather than using `#[serde(untagged)]`, so that errors within
+        // the selected form (like a malformed event body) are surfaced.
+        match Value::deserialize(deserializer)? {
+            trigger @ Value::String(_) => {
+                event::BareEvent::deserialize(trigger).map(Self::BareEvent)
+            }
+            trigger @ Value::Sequence(_) => {
+                Vec::<event::BareEvent>::deserialize(trigger).map(Self::BareEvents)
+            }
+            trigger @ Value::Mapping(_) => {
+                // NOTE: `Events` has an inherent `deserialize` (from `remote = "Self"`)
+                // that bypasses its validation, so we call the trait method explicitly.
+                <event::Events as Deserialize>::deserialize(trigger)
+                    .map(|events| Self::Events(Box::new(events)))
+            }
+            _ => {
+                return Err(de::Error::custom(
+                    "invalid `on`: expected an event, a list of events, or a mapping of events",
+                ))
+            }
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+impl Trigger {
+    /// Returns these triggers in their rich [`event::Events`] form.
+    ///
+    /// Bare events become events with an empty body, i.e. `on: push`
+    /// is equivalent to `on: { push: }`.
+    pub fn to_events(&self) -> event::Events {
+        match self {
+            Trigger::BareEvent(event) => {
+                let mut events = event::Events::default();
+                events.set_default(*event);
+                events
+            }
+            Trigger::BareEvents(bare) => {
+                let mut events = event::Events::default();
+                for event in bare {
+                    events.set_default(*event);
+                }
+                events
+            }
+            Trigger::Events(events) => events.as_ref().clone(),
+        }
+    }
+
+    /// Returns the deduplicated, ordered set of events that these triggers
+    /// fire on.
+    ///
+    /// Two triggers that fire on the same events compare equal under this
+    /// view, regardless of their syntactic form or order, e.g.
+    /// `on: [push, pull_request]` and `on: { pull_request:, push: }`.
+    pub fn normalized(&self) -> BTreeSet<event::EventKind> {
+        match self {
+            Trigger::BareEvent(event) => BTreeSet::from([(*event).into()]),
+            Trigger::BareEvents(events) => events.iter().map(|e| (*e).into()).collect(),
+            Trigger::Events(events) => events.kinds(),
+        }
+    }
+
+    /// Returns whether these triggers fire on the given kind of event.
+    pub fn has_event(&self, kind: event::EventKind) -> bool {
+        self.normalized().contains(&kind)
+    }
+
+    /// Check these triggers for events that are listed more than once,
+    /// e.g. `push` in `on: [push, pull_request, push]`, returning a
+    /// [`DUPLICATE_EVENT`](diagnostic::DUPLICATE_EVENT) diagnostic for each
+    /// repeated entry.
+    ///
+    /// GitHub accepts duplicated events, but they're redundant and usually
+    /// indicate a mistake. Only the list form can contain duplicates,
+    /// since duplicate keys are rejected in the mapping form.
+    ///
+    /// Each diagnostic's path is relative to the workflow, e.g.
+    /// `["on", "2"]`.
+    pub fn duplicate_events(&self) -> Vec<Diagnostic> {
+        let Trigger::BareEvents(events) = self else {
+            return vec![];
+        };
+
+        let mut first_index = HashMap::new();
+        let mut diagnostics = vec![];
+        for (index, event) in events.iter().enumerate() {
+            if let Some(first) = first_index.get(event) {
+                diagnostics.push(Diagnostic::new(
+                    diagnostic::DUPLICATE_EVENT,
+                    ["on".to_string(), index.to_string()],
+                    format!("event `{event}` is already listed at index {first}"),
+                ));
+            } else {
+                first_index.insert(*event, index);
+            }
+        }
+        diagnostics
+    }
+
+    /// Merge two triggers, with `overlay` taking precedence.
+    ///
+    /// Both triggers are first normalized into their rich form (see
+    /// [`Trigger::to_events`]), so the result is always [`Trigger::Events`]
+    /// regardless of the syntactic forms of the inputs.
+    ///
+    /// The result triggers on the union of both triggers' events. For each
+    /// event present in both, `overlay`'s configuration replaces `base`'s
+    /// wholesale, including when `overlay` specifies the event in bare form
+    /// or with an empty body. See [`event::Events::merge`].
+    pub fn merge(base: &Trigger, overlay: &Trigger) -> Trigger {
+        Trigger::Events(Box::new(event::Events::merge(
+            &base.to_events(),
+            &overlay.to_events(),
+        )))
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Defaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub run: Option<RunDefaults>,
 }
 
-#[derive(Deserialize)]
+impl Defaults {
+    fn merge(base: &Defaults, overlay: Defaults) -> Defaults {
+        let run = match (&base.run, overlay.run) {
+            (Some(base), Some(run)) => Some(RunDefaults {
+                shell: run.shell.or_else(|| base.shell.clone()),
+                working_directory: run
+                    .working_directory
+                    .or_else(|| base.working_directory.clone()),
+            }),
+            (base, run) => run.or_else(|| base.clone()),
+        };
+
+        Defaults { run }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct RunDefaults {
-    pub shell: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<Shell>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub working_directory: Option<String>,
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case", untagged)]
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", rename_all_fields = "kebab-case", untagged)]
 pub enum Concurrency {
-    Bare(String),
+    Bare(LoE<String>),
     Rich {
-        group: String,
-        #[serde(default)]
+        group: LoE<String>,
+        #[serde(default, skip_serializing_if = "crate::common::is_default")]
         cancel_in_progress: BoE,
     },
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case", untagged)]
+impl Concurrency {
+    /// Returns this concurrency group's name, exactly as written,
+    /// regardless of form.
+    pub fn group(&self) -> &str {
+        match self {
+            Concurrency::Bare(group) => group.as_str(),
+            Concurrency::Rich { group, .. } => group.as_str(),
+        }
+    }
+
+    /// Returns the body of each expression in this concurrency group's
+    /// name, whether the name is a whole expression or a template like
+    /// `${{ github.workflow }}-${{ github.ref }}`.
+    pub fn group_expressions(&self) -> Vec<&str> {
+        expr::interpolations(self.group())
+            .into_iter()
+            .map(|(_, body)| body)
+            .collect()
+    }
+
+    /// Returns this concurrency group's `cancel-in-progress` setting,
+    /// or `None` if it's a bare group name.
+    pub fn cancel_in_progress(&self) -> Option<&BoE> {
+        match self {
+            Concurrency::Bare(_) => None,
+            Concurrency::Rich {
+                cancel_in_progress, ..
+            } => Some(cancel_in_progress),
+        }
+    }
+}
+
+/// A single workflow job.
+///
+/// A job is a reusable workflow call if and only if it has a `uses:` key;
+/// otherwise, it's a "normal" job.
+#[derive(Clone)]
 pub enum Job {
     NormalJob(Box<job::NormalJob>),
     ReusableWorkflowCallJob(Box<job::ReusableWorkflowCallJob>),
 }
 
+impl<'de> Deserialize<'de> for Job {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // NOTE: We could use `#[serde(untagged)]` here, but doing so
+        // produces useless errors: any mistake in a job's body becomes
+        // "data did not match any variant of untagged enum Job".
+        // Instead, we discriminate on the presence of `uses:` ourselves
+        // and surface the selected variant's error directly.
+        let job = Mapping::deserialize(deserializer)?;
+
+        if job.contains_key("uses") {
+            crate::common::from_buffered(Value::Mapping(job))
+                .map(|job| Self::ReusableWorkflowCallJob(Box::new(job)))
+        } else {
+            crate::common::from_buffered(Value::Mapping(job))
+                .map(|job| Self::NormalJob(Box::new(job)))
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Job {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Job::NormalJob(job) => job.serialize(serializer),
+            Job::ReusableWorkflowCallJob(job) => job.serialize(serializer),
+        }
+    }
+}
+
 impl Job {
     /// Returns the optional `name` field common to both reusable and normal
     /// job definitions.
@@ -105,29 +1245,359 @@ impl Job {
             Self::ReusableWorkflowCallJob(job) => job.name.as_deref(),
         }
     }
+
+    /// Returns the `permissions` field common to both reusable and normal
+    /// job definitions, or `None` if the job inherits the workflow's.
+    pub fn permissions(&self) -> Option<&Permissions> {
+        match self {
+            Self::NormalJob(job) => job.permissions.as_ref(),
+            Self::ReusableWorkflowCallJob(job) => job.permissions.as_ref(),
+        }
+    }
+
+    /// Returns the `needs` field common to both reusable and normal
+    /// job definitions.
+    pub fn needs(&self) -> &[String] {
+        match self {
+            Self::NormalJob(job) => &job.needs,
+            Self::ReusableWorkflowCallJob(job) => &job.needs,
+        }
+    }
+
+    /// Returns the optional `if` field common to both reusable and normal
+    /// job definitions.
+    pub fn condition(&self) -> Option<&If> {
+        match self {
+            Self::NormalJob(job) => job.r#if.as_ref(),
+            Self::ReusableWorkflowCallJob(job) => job.r#if.as_ref(),
+        }
+    }
+
+    /// Returns the optional `if` field common to both reusable and normal
+    /// job definitions.
+    ///
+    /// This is the same as [`Job::condition`], named after the field.
+    pub fn r#if(&self) -> Option<&If> {
+        self.condition()
+    }
+
+    /// Returns the optional `concurrency` field common to both reusable and
+    /// normal job definitions.
+    pub fn concurrency(&self) -> Option<&Concurrency> {
+        match self {
+            Self::NormalJob(job) => job.concurrency.as_ref(),
+            Self::ReusableWorkflowCallJob(job) => job.concurrency.as_ref(),
+        }
+    }
+
+    /// Returns whether this is a normal job.
+    pub fn is_normal(&self) -> bool {
+        matches!(self, Self::NormalJob(_))
+    }
+
+    /// Returns whether this is a reusable workflow call job.
+    pub fn is_reusable(&self) -> bool {
+        matches!(self, Self::ReusableWorkflowCallJob(_))
+    }
+
+    /// Returns this job as a normal job, if it is one.
+    pub fn as_normal(&self) -> Option<&job::NormalJob> {
+        match self {
+            Self::NormalJob(job) => Some(job),
+            Self::ReusableWorkflowCallJob(_) => None,
+        }
+    }
+
+    /// Returns this job as a reusable workflow call job, if it is one.
+    pub fn as_reusable(&self) -> Option<&job::ReusableWorkflowCallJob> {
+        match self {
+            Self::NormalJob(_) => None,
+            Self::ReusableWorkflowCallJob(job) => Some(job),
+        }
+    }
+}
+
+/// Find each `needs.<job>.outputs.<path>` property reference in `s`,
+/// returning the `<job>` and (possibly dotted) `<path>`.
+fn needs_output_refs(s: &str) -> Vec<(&str, &str)> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let mut refs = vec![];
+
+    for (idx, _) in s.match_indices("needs.") {
+        // Skip matches in the middle of another identifier or
+        // property access, e.g. `foo.needs.bar`.
+        if s[..idx].ends_with(|c: char| is_ident(c) || c == '.') {
+            continue;
+        }
+
+        let rest = &s[idx + "needs.".len()..];
+        let job_len = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+        let (job, rest) = rest.split_at(job_len);
+        let Some(rest) = rest.strip_prefix(".outputs.") else {
+            continue;
+        };
+        let path_len = rest
+            .find(|c: char| !is_ident(c) && c != '.')
+            .unwrap_or(rest.len());
+        let path = rest[..path_len].trim_end_matches('.');
+
+        if !job.is_empty() && !path.is_empty() {
+            refs.push((job, path));
+        }
+    }
+
+    refs
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::workflow::event::{OptionalBody, WorkflowCall, WorkflowDispatch};
+    use crate::{
+        common::{
+            diagnostic,
+            expr::{BoE, LoE},
+            BasePermission, EnvValue, If, Permissions, Shell,
+        },
+        workflow::event::{
+            BareEvent, BranchFilters, EventKind, OptionalBody, WorkflowCall, WorkflowDispatch,
+        },
+    };
 
-    use super::{Concurrency, Trigger};
+    use super::{job, needs_output_refs, Concurrency, CycleError, Job, Trigger, Workflow};
 
     #[test]
     fn test_concurrency() {
         let bare = "foo";
         let concurrency: Concurrency = serde_yaml::from_str(bare).unwrap();
-        assert!(matches!(concurrency, Concurrency::Bare(_)));
+        assert!(matches!(concurrency, Concurrency::Bare(LoE::Literal(_))));
+        assert_eq!(concurrency.group(), "foo");
+        assert!(concurrency.group_expressions().is_empty());
+        assert!(concurrency.cancel_in_progress().is_none());
+
+        let bare = "${{ github.workflow }}";
+        let concurrency: Concurrency = serde_yaml::from_str(bare).unwrap();
+        assert!(matches!(concurrency, Concurrency::Bare(LoE::Expr(_))));
+        assert_eq!(concurrency.group_expressions(), ["github.workflow"]);
 
         let rich = "group: foo\ncancel-in-progress: true";
         let concurrency: Concurrency = serde_yaml::from_str(rich).unwrap();
         assert!(matches!(
             concurrency,
             Concurrency::Rich {
-                group: _,
-                cancel_in_progress: _
+                group: LoE::Literal(_),
+                cancel_in_progress: BoE::Literal(true)
             }
         ));
+        assert_eq!(concurrency.group(), "foo");
+        assert_eq!(concurrency.cancel_in_progress(), Some(&BoE::Literal(true)));
+
+        let rich = "group: ${{ github.workflow }}-${{ github.ref }}";
+        let concurrency: Concurrency = serde_yaml::from_str(rich).unwrap();
+        assert_eq!(
+            concurrency.group(),
+            "${{ github.workflow }}-${{ github.ref }}"
+        );
+        assert!(matches!(
+            concurrency,
+            Concurrency::Rich {
+                group: LoE::Literal(_),
+                ..
+            }
+        ));
+        assert_eq!(
+            concurrency.group_expressions(),
+            ["github.workflow", "github.ref"]
+        );
+        assert_eq!(concurrency.cancel_in_progress(), Some(&BoE::Literal(false)));
+
+        let rich = "group: ${{ github.head_ref || github.run_id }}";
+        let concurrency: Concurrency = serde_yaml::from_str(rich).unwrap();
+        let Concurrency::Rich {
+            group: LoE::Expr(group),
+            ..
+        } = &concurrency
+        else {
+            panic!("expected expression group");
+        };
+        assert_eq!(group.as_bare(), "github.head_ref || github.run_id");
+        assert_eq!(
+            concurrency.group_expressions(),
+            ["github.head_ref || github.run_id"]
+        );
+    }
+
+    #[test]
+    fn test_run_name() {
+        let workflow = |run_name: &str| -> Workflow {
+            serde_yaml::from_str(&format!("run-name: {run_name}\non: push\njobs: {{}}")).unwrap()
+        };
+
+        let plain = workflow("Nightly build");
+        assert!(matches!(plain.run_name, Some(LoE::Literal(_))));
+        assert!(plain.run_name_expressions().is_empty());
+
+        let whole = workflow("${{ github.event.head_commit.message }}");
+        let Some(LoE::Expr(expr)) = &whole.run_name else {
+            panic!("expected expression run-name");
+        };
+        assert_eq!(expr.as_bare(), "github.event.head_commit.message");
+        assert_eq!(
+            whole.run_name_expressions(),
+            ["github.event.head_commit.message"]
+        );
+
+        let template = workflow("Deploy ${{ inputs.env }} by @${{ github.actor }}");
+        let run_name = template.run_name.as_ref().unwrap();
+        assert!(matches!(run_name, LoE::Literal(_)));
+        assert_eq!(
+            run_name.as_str(),
+            "Deploy ${{ inputs.env }} by @${{ github.actor }}"
+        );
+        assert_eq!(
+            template.run_name_expressions(),
+            ["inputs.env", "github.actor"]
+        );
+
+        let none: Workflow = serde_yaml::from_str("on: push\njobs: {}").unwrap();
+        assert!(none.run_name_expressions().is_empty());
+    }
+
+    #[test]
+    fn test_jobs_in_topological_order() {
+        let order = |jobs: &str| -> Result<Vec<String>, CycleError> {
+            let workflow: Workflow =
+                serde_yaml::from_str(&format!("on: push\njobs:\n{jobs}")).unwrap();
+            workflow
+                .jobs_in_topological_order()
+                .map(|jobs| jobs.into_iter().map(|(id, _)| id.to_string()).collect())
+        };
+        let job = |id: &str, needs: &str| {
+            format!("  {id}:\n    runs-on: ubuntu-latest\n    needs: [{needs}]\n    steps: []\n")
+        };
+
+        // No dependencies: declaration order.
+        let jobs = [job("c", ""), job("a", ""), job("b", "")].concat();
+        assert_eq!(order(&jobs).unwrap(), ["c", "a", "b"]);
+
+        // Linear chain, declared backwards.
+        let jobs = [
+            job("deploy", "test"),
+            job("test", "build"),
+            job("build", ""),
+        ]
+        .concat();
+        assert_eq!(order(&jobs).unwrap(), ["build", "test", "deploy"]);
+
+        // Diamond, with an unrelated root that must come first too.
+        let jobs = [
+            job("d", "b, c"),
+            job("b", "a"),
+            job("c", "a"),
+            job("a", ""),
+            job("lint", ""),
+        ]
+        .concat();
+        assert_eq!(order(&jobs).unwrap(), ["a", "lint", "b", "c", "d"]);
+
+        // Unknown needs are ignored.
+        let jobs = [job("a", "missing"), job("b", "a")].concat();
+        assert_eq!(order(&jobs).unwrap(), ["a", "b"]);
+
+        // Cycle, with jobs upstream and downstream of it.
+        let jobs = [
+            job("setup", ""),
+            job("a", "setup, c"),
+            job("b", "a"),
+            job("c", "b"),
+            job("after", "c"),
+        ]
+        .concat();
+        let err = order(&jobs).unwrap_err();
+        assert_eq!(err.jobs, ["a", "c", "b"]);
+        assert_eq!(err.to_string(), "job dependency cycle: a -> c -> b -> a");
+
+        // Self-dependency.
+        let err = order(&job("a", "a")).unwrap_err();
+        assert_eq!(err.jobs, ["a"]);
+    }
+
+    #[test]
+    fn test_reparse() {
+        let mut workflow: Workflow = "
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make
+      - run: make test
+  deploy:
+    needs: build
+    uses: ./.github/workflows/deploy.yml
+"
+        .parse()
+        .unwrap();
+
+        // Replacing a job keeps its position.
+        workflow
+            .reparse_job("build", "runs-on: macos-latest\nsteps:\n  - run: make")
+            .unwrap();
+        assert_eq!(
+            workflow.jobs.keys().collect::<Vec<_>>(),
+            ["build", "deploy"]
+        );
+        let Job::NormalJob(build) = &workflow.jobs["build"] else {
+            panic!("expected normal job");
+        };
+        assert_eq!(build.steps.len(), 1);
+
+        // The job's kind can change.
+        workflow
+            .reparse_job("deploy", "runs-on: ubuntu-latest\nneeds: build\nsteps: []")
+            .unwrap();
+        assert!(workflow.jobs["deploy"].is_normal());
+        workflow
+            .reparse_job("deploy", "uses: ./.github/workflows/deploy.yml")
+            .unwrap();
+        assert!(workflow.jobs["deploy"].is_reusable());
+
+        // New jobs are appended.
+        workflow
+            .reparse_job("lint", "runs-on: ubuntu-latest\nsteps: []")
+            .unwrap();
+        assert_eq!(
+            workflow.jobs.keys().collect::<Vec<_>>(),
+            ["build", "deploy", "lint"]
+        );
+
+        // Invalid fragments leave the workflow unchanged.
+        assert!(workflow.reparse_job("build", "runs-on: [").is_err());
+        assert!(workflow.reparse_job("build", "steps: []").is_err());
+        assert!(workflow.jobs["build"].is_normal());
+
+        workflow
+            .reparse_step("build", 0, "uses: actions/checkout@v4")
+            .unwrap();
+        let Job::NormalJob(build) = &workflow.jobs["build"] else {
+            panic!("expected normal job");
+        };
+        assert!(matches!(build.steps[0].body, job::StepBody::Uses { .. }));
+
+        assert!(workflow.reparse_step("build", 0, "name: nothing").is_err());
+        for (job_id, index, step, message) in [
+            ("missing", 0, None, "no job `missing`"),
+            ("build", 1, Some(1), "job `build` has no step 1"),
+            // Reusable workflow calls have no steps.
+            ("deploy", 0, Some(0), "job `deploy` has no step 0"),
+        ] {
+            let err = workflow
+                .reparse_step(job_id, index, "run: make")
+                .unwrap_err();
+            let crate::Error::MissingTarget { job, step: target } = &err else {
+                panic!("expected a missing target, got {err}");
+            };
+            assert_eq!((job.as_str(), *target), (job_id, step));
+            assert_eq!(err.to_string(), message);
+        }
     }
 
     #[test]
@@ -161,4 +1631,594 @@ mod tests {
         ));
         assert!(matches!(events.pull_request_target, OptionalBody::Default));
     }
+
+    #[test]
+    fn test_bare_deployment_protection_rule() {
+        let trigger: Trigger = serde_yaml::from_str("deployment_protection_rule").unwrap();
+        assert!(matches!(
+            trigger,
+            Trigger::BareEvent(BareEvent::DeploymentProtectionRule)
+        ));
+    }
+
+    #[test]
+    fn test_trigger_normalized() {
+        let trigger = |on: &str| serde_yaml::from_str::<Trigger>(on).unwrap();
+
+        let dupes = trigger("[push, push, pull_request]");
+        assert_eq!(
+            dupes.normalized(),
+            [EventKind::PullRequest, EventKind::Push].into()
+        );
+        let diagnostics = dupes.duplicate_events();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, diagnostic::DUPLICATE_EVENT);
+        assert_eq!(diagnostics[0].path, ["on", "1"]);
+        assert_eq!(
+            diagnostics[0].message,
+            "event `push` is already listed at index 0"
+        );
+
+        // Order and form don't matter after normalization.
+        let reordered = trigger("[pull_request, push]");
+        let rich = trigger("pull_request:\npush:\n  branches: [main]");
+        assert_eq!(dupes.normalized(), reordered.normalized());
+        assert_eq!(dupes.normalized(), rich.normalized());
+        assert!(reordered.duplicate_events().is_empty());
+        assert!(rich.duplicate_events().is_empty());
+
+        let bare = trigger("push");
+        assert_eq!(bare.normalized(), [EventKind::Push].into());
+        assert!(bare.has_event(EventKind::Push));
+        assert!(!bare.has_event(EventKind::PullRequest));
+
+        let scheduled = trigger("schedule:\n  - cron: '0 0 * * *'\nworkflow_dispatch:");
+        assert!(scheduled.has_event(EventKind::Schedule));
+        assert!(scheduled.has_event(EventKind::WorkflowDispatch));
+        assert!(!scheduled.has_event(EventKind::Push));
+
+        let many = trigger("[push, pull_request, push, pull_request, push]");
+        let paths = many
+            .duplicate_events()
+            .into_iter()
+            .map(|d| d.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, [["on", "2"], ["on", "3"], ["on", "4"]]);
+    }
+
+    #[test]
+    fn test_trigger_merge_bare_and_rich() {
+        let base: Trigger = serde_yaml::from_str("[push, fork]").unwrap();
+        let overlay: Trigger = serde_yaml::from_str(
+            "
+pull_request:
+  branches: [main]
+",
+        )
+        .unwrap();
+
+        let Trigger::Events(merged) = Trigger::merge(&base, &overlay) else {
+            panic!("expected merge to normalize to events");
+        };
+
+        assert_eq!(merged.count(), 3);
+        assert!(matches!(merged.push, OptionalBody::Default));
+        assert!(matches!(merged.fork, OptionalBody::Default));
+        assert!(matches!(
+            &merged.pull_request,
+            OptionalBody::Body(pr) if matches!(&pr.branch_filters, Some(BranchFilters::Branches(b)) if b == &["main"])
+        ));
+    }
+
+    #[test]
+    fn test_trigger_merge_conflicting_bodies() {
+        let base: Trigger = serde_yaml::from_str(
+            "
+push:
+  branches: [main]
+pull_request:
+  branches: [main]
+workflow_dispatch:
+",
+        )
+        .unwrap();
+        let overlay: Trigger = serde_yaml::from_str(
+            "
+push:
+  branches: [release]
+pull_request:
+",
+        )
+        .unwrap();
+
+        let Trigger::Events(merged) = Trigger::merge(&base, &overlay) else {
+            panic!("expected merge to normalize to events");
+        };
+
+        assert_eq!(merged.count(), 3);
+        // Overlay bodies win wholesale, including empty bodies.
+        assert!(matches!(
+            &merged.push,
+            OptionalBody::Body(push) if matches!(&push.branch_filters, Some(BranchFilters::Branches(b)) if b == &["release"])
+        ));
+        assert!(matches!(merged.pull_request, OptionalBody::Default));
+        // Base-only events are preserved.
+        assert!(matches!(merged.workflow_dispatch, OptionalBody::Default));
+    }
+
+    #[test]
+    fn test_workflow_merge_defaults() {
+        let base: Workflow = serde_yaml::from_str(
+            "
+on: push
+permissions: read-all
+env:
+  FOO: base
+  BAR: base
+defaults:
+  run:
+    shell: bash
+    working-directory: base
+concurrency: base
+jobs: {}
+",
+        )
+        .unwrap();
+        let mut overlay: Workflow = serde_yaml::from_str(
+            "
+on: pull_request
+env:
+  BAR: overlay
+defaults:
+  run:
+    working-directory: overlay
+jobs: {}
+",
+        )
+        .unwrap();
+
+        overlay.merge_defaults(&base);
+
+        assert_eq!(overlay.on.to_events().count(), 2);
+        assert_eq!(
+            overlay.permissions,
+            Some(Permissions::Base(BasePermission::ReadAll))
+        );
+
+        let LoE::Literal(env) = &overlay.env else {
+            panic!("expected literal env");
+        };
+        assert_eq!(env["FOO"], EnvValue::String("base".into()));
+        assert_eq!(env["BAR"], EnvValue::String("overlay".into()));
+
+        let run = overlay.defaults.unwrap().run.unwrap();
+        assert_eq!(run.shell, Some(Shell::Bash));
+        assert_eq!(run.working_directory.as_deref(), Some("overlay"));
+
+        assert!(
+            matches!(overlay.concurrency, Some(Concurrency::Bare(LoE::Literal(group))) if group == "base")
+        );
+    }
+
+    #[test]
+    fn test_workflow_merge_defaults_explicit_permissions() {
+        let base: Workflow =
+            serde_yaml::from_str("on: push\npermissions: write-all\njobs: {}").unwrap();
+        let mut overlay: Workflow =
+            serde_yaml::from_str("on: push\npermissions: default\njobs: {}").unwrap();
+
+        overlay.merge_defaults(&base);
+
+        // An explicit `permissions:` is kept, even when it's the default.
+        assert_eq!(overlay.permissions, Some(Permissions::default()));
+    }
+
+    #[test]
+    fn test_bare_event_to_events() {
+        let trigger: Trigger = serde_yaml::from_str("[push, status]").unwrap();
+        let events = trigger.to_events();
+        assert_eq!(events.count(), 2);
+        assert!(matches!(events.status, OptionalBody::Default));
+    }
+
+    #[test]
+    fn test_bodyless_events_normalize() {
+        let bare: Trigger = serde_yaml::from_str(
+            "[create, delete, deployment, deployment_status, fork, gollum, page_build, public, status]",
+        )
+        .unwrap();
+        let mapping: Trigger = serde_yaml::from_str(
+            "
+create:
+delete:
+deployment:
+deployment_status:
+fork:
+gollum:
+page_build:
+public:
+status:
+",
+        )
+        .unwrap();
+
+        assert_eq!(bare.to_events().count(), 9);
+        assert_eq!(bare.normalized().len(), 9);
+        assert_eq!(bare.normalized(), mapping.normalized());
+        let Trigger::Events(events) = &mapping else {
+            panic!("expected events");
+        };
+        assert!(events.extra.is_empty());
+    }
+
+    #[test]
+    fn test_job_discriminates() {
+        let normal = "runs-on: ubuntu-latest\nsteps: []";
+        assert!(matches!(
+            serde_yaml::from_str::<Job>(normal).unwrap(),
+            Job::NormalJob(_)
+        ));
+
+        let reusable = "uses: octo-org/example/.github/workflows/ci.yml@main";
+        assert!(matches!(
+            serde_yaml::from_str::<Job>(reusable).unwrap(),
+            Job::ReusableWorkflowCallJob(_)
+        ));
+
+        // `strategy:` is shared by both kinds of job.
+        let normal = "runs-on: ubuntu-latest\nstrategy:\n  matrix:\n    os: [a, b]\nsteps: []";
+        assert!(matches!(
+            serde_yaml::from_str::<Job>(normal).unwrap(),
+            Job::NormalJob(job) if job.strategy.is_some()
+        ));
+
+        let reusable = "uses: octo-org/example/.github/workflows/ci.yml@main\nstrategy:\n  matrix:\n    os: [a, b]";
+        assert!(matches!(
+            serde_yaml::from_str::<Job>(reusable).unwrap(),
+            Job::ReusableWorkflowCallJob(job) if job.strategy.is_some()
+        ));
+    }
+
+    #[test]
+    fn test_job_accessors() {
+        let normal = serde_yaml::from_str::<Job>(
+            "runs-on: ubuntu-latest\nneeds: build\nif: success()\npermissions: read-all\nsteps: []",
+        )
+        .unwrap();
+        assert!(normal.is_normal());
+        assert!(!normal.is_reusable());
+        assert!(normal.as_normal().is_some());
+        assert!(normal.as_reusable().is_none());
+        assert_eq!(normal.needs(), ["build"]);
+        assert_eq!(normal.condition(), Some(&If::Expr("success()".into())));
+        assert_eq!(normal.r#if(), normal.condition());
+        assert_eq!(
+            normal.permissions(),
+            Some(&Permissions::Base(BasePermission::ReadAll))
+        );
+        assert!(normal.concurrency().is_none());
+
+        let reusable = serde_yaml::from_str::<Job>(
+            "uses: octo-org/example/.github/workflows/ci.yml@main\nneeds: [a, b]\nconcurrency: ci",
+        )
+        .unwrap();
+        assert!(!reusable.is_normal());
+        assert!(reusable.is_reusable());
+        assert!(reusable.as_normal().is_none());
+        assert!(reusable.as_reusable().is_some());
+        assert_eq!(reusable.needs(), ["a", "b"]);
+        assert_eq!(reusable.condition(), None);
+        assert_eq!(reusable.r#if(), None);
+        assert_eq!(reusable.permissions(), None);
+        assert_eq!(reusable.concurrency().unwrap().group(), "ci");
+    }
+
+    #[test]
+    fn test_job_errors() {
+        let vectors = [
+            // Typo'd `runs-on`.
+            (
+                "run-on: ubuntu-latest\nsteps: []",
+                "missing field `runs-on`",
+            ),
+            // Missing `steps`.
+            ("runs-on: ubuntu-latest", "missing field `steps`"),
+            // Reusable workflow with an invalid `uses`.
+            (
+                "uses: octo-org/example/.github/workflows/ci.yml",
+                "repo action must have `@<ref> in reusable workflow",
+            ),
+            // Reusable workflow with a malformed `with`.
+            (
+                "uses: ./.github/workflows/ci.yml\nwith: [foo]",
+                "invalid type: sequence, expected a map",
+            ),
+        ];
+
+        for (job, expected) in vectors {
+            let Err(err) = serde_yaml::from_str::<Job>(job) else {
+                panic!("expected {job:?} to fail");
+            };
+            let err = err.to_string();
+
+            assert!(err.contains(expected), "{err:?} missing {expected:?}");
+            assert!(!err.contains("untagged"));
+        }
+    }
+
+    #[test]
+    fn test_validate_needs() {
+        let workflow: Workflow = r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps: []
+  test:
+    needs: biuld
+    runs-on: ubuntu-latest
+    steps: []
+  lint:
+    needs: [build, lint, Test, deploy-everything-now]
+    runs-on: ubuntu-latest
+    steps: []
+  call:
+    needs: [tset]
+    uses: ./.github/workflows/reusable.yml
+"#
+        .parse()
+        .unwrap();
+
+        let diagnostics = workflow.validate_needs();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.code, d.path.join("."), d.message.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    diagnostic::UNKNOWN_NEED,
+                    "jobs.test.needs".into(),
+                    "job `test` needs unknown job `biuld`; did you mean `build`?"
+                ),
+                (
+                    diagnostic::SELF_NEED,
+                    "jobs.lint.needs".into(),
+                    "job `lint` needs itself"
+                ),
+                // Matching is exact, but suggestions ignore case.
+                (
+                    diagnostic::UNKNOWN_NEED,
+                    "jobs.lint.needs".into(),
+                    "job `lint` needs unknown job `Test`; did you mean `test`?"
+                ),
+                (
+                    diagnostic::UNKNOWN_NEED,
+                    "jobs.lint.needs".into(),
+                    "job `lint` needs unknown job `deploy-everything-now`"
+                ),
+                (
+                    diagnostic::UNKNOWN_NEED,
+                    "jobs.call.needs".into(),
+                    "job `call` needs unknown job `tset`; did you mean `test`?"
+                ),
+            ]
+        );
+
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "error[E102-self-need]: job `lint` needs itself"
+        );
+
+        let workflow: Workflow = "
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps: []
+  test:
+    needs: build
+    runs-on: ubuntu-latest
+    steps: []
+  call:
+    needs: [build, test]
+    uses: ./.github/workflows/reusable.yml
+"
+        .parse()
+        .unwrap();
+        assert!(workflow.validate_needs().is_empty());
+    }
+
+    #[test]
+    fn test_validate_identifiers() {
+        let workflow: Workflow = r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make
+      - id: 2nd-step
+        run: make again
+      - id: ok_step
+        run: make check
+  1build:
+    runs-on: ubuntu-latest
+    steps: []
+  "build all":
+    uses: ./.github/workflows/reusable.yml
+  ビルド:
+    runs-on: ubuntu-latest
+    steps:
+      - id: has space
+        run: echo
+"#
+        .parse()
+        .unwrap();
+
+        let diagnostics = workflow.validate_identifiers();
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.code == diagnostic::INVALID_IDENTIFIER));
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| d.path.join("."))
+                .collect::<Vec<_>>(),
+            [
+                "jobs.build.steps.1.id",
+                "jobs.1build",
+                "jobs.build all",
+                "jobs.ビルド",
+                "jobs.ビルド.steps.0.id",
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "step ID `2nd-step` must start with a letter or `_` and contain only \
+             alphanumeric characters, `-`, or `_`"
+        );
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "error[E103-invalid-identifier]: job ID `1build` must start with a letter or `_` \
+             and contain only alphanumeric characters, `-`, or `_`"
+        );
+
+        let workflow: Workflow = "
+on: push
+jobs:
+  _build-all:
+    runs-on: ubuntu-latest
+    steps:
+      - id: Step_1
+        run: make
+"
+        .parse()
+        .unwrap();
+        assert!(workflow.validate_identifiers().is_empty());
+    }
+
+    #[test]
+    fn test_validate_step_ids() {
+        let workflow: Workflow = "
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - id: build
+        run: make
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - id: test
+        run: make test
+      - id: test
+        run: make test again
+  call:
+    uses: ./.github/workflows/reusable.yml
+"
+        .parse()
+        .unwrap();
+
+        let diagnostics = workflow.validate_step_ids();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, ["jobs", "test", "steps", "1", "id"]);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "error[E108-duplicate-step-id]: step ID `test` is already used by step 0"
+        );
+    }
+
+    #[test]
+    fn test_output_diagnostics() {
+        let workflow = r#"
+on:
+  workflow_call:
+    outputs:
+      "release.tag":
+        value: ${{ jobs.build.outputs.version }}
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    outputs:
+      version: ${{ steps.v.outputs.version }}
+      "foo.bar": ${{ steps.v.outputs.foo }}
+    steps:
+      - id: v
+        run: echo "version=1" >> $GITHUB_OUTPUT
+  test:
+    needs: build
+    if: needs.build.outputs.foo.bar == 'x'
+    runs-on: ubuntu-latest
+    env:
+      VERSION: ${{ needs.build.outputs.version }}
+      INDEXED: ${{ needs.build.outputs['foo.bar'] }}
+    steps:
+      - run: echo ${{ needs.build.outputs.missing }}
+      - uses: ./.github/actions/x
+        with:
+          unrelated: ${{ needs.other.outputs.foo }}
+      # Only expressions are checked, not plain text.
+      - name: mentions needs.build.outputs.prose
+        run: echo needs.build.outputs.prose
+  call:
+    needs: [build]
+    uses: ./.github/workflows/reusable.yml
+    with:
+      value: ${{ needs.build.outputs.foo.bar }}
+      text: needs.build.outputs.prose
+"#;
+        let workflow: Workflow = serde_yaml::from_str(workflow).unwrap();
+
+        let diagnostics = workflow.output_diagnostics();
+        assert!(diagnostics
+            .iter()
+            .all(|d| diagnostic::CODES.contains(&d.code)));
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.code.code, d.path.join("/")))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "W101-unreachable-output-name",
+                    "on/workflow_call/outputs/release.tag".into()
+                ),
+                (
+                    "W101-unreachable-output-name",
+                    "jobs/build/outputs/foo.bar".into()
+                ),
+                ("W102-unreachable-output-reference", "jobs/test".into()),
+                ("W103-undefined-output-reference", "jobs/test".into()),
+                ("W102-unreachable-output-reference", "jobs/call".into()),
+            ]
+        );
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| d.message.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "workflow_call output name `release.tag` cannot be referenced from expressions",
+                "job `build` output name `foo.bar` cannot be referenced from expressions",
+                "job `test` references `needs.build.outputs.foo.bar`, \
+                 but output name `foo.bar` cannot be referenced from expressions",
+                "job `test` references undefined output `missing` of job `build`",
+                "job `call` references `needs.build.outputs.foo.bar`, \
+                 but output name `foo.bar` cannot be referenced from expressions",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_needs_output_refs() {
+        assert_eq!(
+            needs_output_refs("${{ needs.a-b.outputs.c }} && needs.d.outputs.e.f."),
+            [("a-b", "c"), ("d", "e.f")]
+        );
+        assert!(needs_output_refs("foo.needs.a.outputs.b").is_empty());
+        assert!(needs_output_refs("needs.a.result").is_empty());
+        assert!(needs_output_refs("needs.a.outputs['b.c']").is_empty());
+    }
 }
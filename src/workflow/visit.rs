@@ -0,0 +1,83 @@
+//! Pre-order traversal of workflows.
+
+use crate::common::Uses;
+
+use super::{Job, StepHandle, UsesContext, Workflow, WorkflowExpression};
+
+/// A visitor over the parts of a workflow, driven by [`walk_workflow`].
+///
+/// Every method does nothing by default, so implementations only need to
+/// override the ones they care about.
+pub trait WorkflowVisitor<'a> {
+    /// Called once, before anything else.
+    fn visit_workflow(&mut self, _workflow: &'a Workflow) {}
+
+    /// Called for each job, before anything within it.
+    fn visit_job(&mut self, _job_id: &'a str, _job: &'a Job) {}
+
+    /// Called for each step in a normal job, before anything within it.
+    fn visit_step(&mut self, _step: StepHandle<'a>) {}
+
+    /// Called for a step's or reusable workflow call job's `uses:`.
+    fn visit_uses(&mut self, _uses: &'a Uses, _context: UsesContext<'a>) {}
+
+    /// Called for a step's `run:` script.
+    fn visit_run(&mut self, _step: StepHandle<'a>, _run: &'a str) {}
+
+    /// Called for each expression, as found by [`Workflow::expressions`].
+    fn visit_expression(&mut self, _expression: &WorkflowExpression<'a>) {}
+}
+
+/// Walk `workflow` in pre-order, calling `visitor` for each part of it.
+///
+/// Each job is visited (in declaration order) after the workflow-level
+/// expressions, and then its `uses:` (for reusable workflow calls), its
+/// job-level expressions, and each of its steps. Each step is visited
+/// before its `uses:` or `run:`, followed by its expressions.
+pub fn walk_workflow<'a>(workflow: &'a Workflow, visitor: &mut impl WorkflowVisitor<'a>) {
+    // NOTE: `Workflow::expressions` groups by job and then step, in the
+    // same order as this traversal.
+    let mut expressions = workflow.expressions().peekable();
+    let mut visit_expressions = |visitor: &mut _, job_id, step_index| {
+        while let Some(expression) = expressions.next_if(|expression| {
+            expression.location.job_id == job_id && expression.location.step_index == step_index
+        }) {
+            WorkflowVisitor::visit_expression(visitor, &expression);
+        }
+    };
+
+    visitor.visit_workflow(workflow);
+    visit_expressions(visitor, None, None);
+
+    for (job_id, job) in &workflow.jobs {
+        visitor.visit_job(job_id, job);
+
+        let job = match job {
+            Job::NormalJob(job) => job,
+            Job::ReusableWorkflowCallJob(job) => {
+                visitor.visit_uses(&job.uses, UsesContext::ReusableJob { job_id });
+                visit_expressions(visitor, Some(job_id), None);
+                continue;
+            }
+        };
+        visit_expressions(visitor, Some(job_id), None);
+
+        for (index, step) in job.steps.iter().enumerate() {
+            let handle = StepHandle {
+                job_id,
+                job,
+                index,
+                step,
+            };
+
+            visitor.visit_step(handle);
+            if let Some(uses) = step.uses() {
+                visitor.visit_uses(uses, UsesContext::Step { job_id, index });
+            }
+            if let Some(run) = step.run() {
+                visitor.visit_run(handle, run);
+            }
+            visit_expressions(visitor, Some(job_id), Some(index));
+        }
+    }
+}
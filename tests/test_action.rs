@@ -1,16 +1,19 @@
-use std::{env, path::Path};
+use std::{env, path::Path, str::FromStr};
 
 use github_actions_models::{
-    action::{Action, Runs},
-    common::If,
+    action::{
+        visit::{walk_action, ActionVisitor},
+        Action, BrandingColor, NodeRuntime, Runs, Step, StepBody,
+    },
+    common::{diagnostic, expr::LoE, EnvValue, If, Uses},
+    LoadError,
 };
 
 fn load_action(name: &str) -> Action {
     let action_path = Path::new(env!("CARGO_MANIFEST_DIR"))
         .join("tests/sample-actions")
         .join(name);
-    let action_contents = std::fs::read_to_string(action_path).unwrap();
-    serde_yaml::from_str(&action_contents).unwrap()
+    Action::from_path(action_path).unwrap()
 }
 
 #[test]
@@ -18,12 +21,28 @@ fn test_load_all() {
     let sample_actions = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-actions");
 
     for sample_action in std::fs::read_dir(sample_actions).unwrap() {
-        let sample_action = sample_action.unwrap().path();
-        let action_contents = std::fs::read_to_string(sample_action).unwrap();
-        serde_yaml::from_str::<Action>(&action_contents).unwrap();
+        Action::from_path(sample_action.unwrap().path()).unwrap();
     }
 }
 
+#[test]
+fn test_from_path_errors() {
+    let sample_actions = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-actions");
+
+    let missing = sample_actions.join("nonexistent.yml");
+    let Err(err) = Action::from_path(&missing) else {
+        panic!("expected an error for {missing:?}");
+    };
+    assert!(matches!(err, LoadError::Io { .. }));
+    assert_eq!(err.path(), missing);
+    assert!(err.location().is_none());
+
+    let Err(err) = Action::from_str("name: test\ndescription: \"unterminated\n") else {
+        panic!("expected an error for invalid YAML");
+    };
+    assert_eq!(err.location(), Some((3, 1)));
+}
+
 #[test]
 fn test_setup_python() {
     let setup_python = load_action("setup-python.yml");
@@ -41,8 +60,506 @@ fn test_setup_python() {
     let Runs::JavaScript(runs) = setup_python.runs else {
         unreachable!();
     };
-    assert_eq!(runs.using, "node20");
+    assert_eq!(runs.using, NodeRuntime::Node20);
+    assert_eq!(runs.using.to_string(), "node20");
+    assert!(!runs.using.is_deprecated());
     assert_eq!(runs.main, "dist/setup/index.js");
     assert_eq!(runs.post.unwrap(), "dist/cache-save/index.js");
     assert_eq!(runs.post_if.unwrap(), If::Expr("success()".into()));
 }
+
+#[test]
+fn test_composite_step_body() {
+    let step = "run: make\nshell: bash\nworking-directory: build";
+    let Step {
+        body: StepBody::Run {
+            working_directory, ..
+        },
+        ..
+    } = serde_yaml::from_str::<Step>(step).unwrap()
+    else {
+        panic!("expected run step");
+    };
+    assert_eq!(working_directory.as_deref(), Some("build"));
+
+    let step = "uses: actions/checkout@v4\nenv:\n  GH_TOKEN: ${{ github.token }}";
+    let Step {
+        body: StepBody::Uses {
+            env: LoE::Literal(env),
+            ..
+        },
+        ..
+    } = serde_yaml::from_str::<Step>(step).unwrap()
+    else {
+        panic!("expected uses step with literal env");
+    };
+    assert_eq!(
+        env["GH_TOKEN"],
+        EnvValue::String("${{ github.token }}".into())
+    );
+
+    let vectors = [
+        (
+            "name: typo\nrn: make\nshell: bash",
+            "step must contain either `uses:` or `run:`, found unrecognized keys: `rn`",
+        ),
+        ("{}", "step must contain either `uses:` or `run:`"),
+        (
+            "uses: ./foo@",
+            "malformed `uses` ref: local uses is missing git ref after '@': ./foo@",
+        ),
+        // `env:` doesn't make a step body on its own.
+        ("env: {}", "step must contain either `uses:` or `run:`"),
+        // Composite `run:` steps require a `shell:`.
+        ("run: make", "missing field `shell`"),
+    ];
+
+    for (step, expected) in vectors {
+        let Err(err) = serde_yaml::from_str::<Step>(step) else {
+            panic!("expected {step:?} to fail");
+        };
+        let err = err.to_string();
+        assert_eq!(err, expected);
+    }
+}
+
+#[test]
+fn test_steps() {
+    let action = load_action("gh-action-pip-audit.yml");
+    assert!(action.is_composite());
+    assert_eq!(
+        action
+            .steps()
+            .iter()
+            .map(|step| step.name.as_deref().unwrap())
+            .collect::<Vec<_>>(),
+        ["Set up pip-audit", "Run pip-audit"]
+    );
+
+    assert!(load_action("setup-python.yml").steps().is_empty());
+}
+
+#[test]
+fn test_composite_step_accessors() {
+    let step = serde_yaml::from_str::<Step>(
+        "uses: actions/setup-python@v5\nwith:\n  python-version: '3.12'",
+    )
+    .unwrap();
+    assert_eq!(step.uses().unwrap().to_string(), "actions/setup-python@v5");
+    assert!(step.run().is_none());
+    assert_eq!(
+        step.with().unwrap()["python-version"],
+        EnvValue::String("3.12".into())
+    );
+    assert!(matches!(step.env(), LoE::Literal(env) if env.is_empty()));
+
+    let step = serde_yaml::from_str::<Step>("run: make\nshell: bash\nenv:\n  CC: clang").unwrap();
+    assert!(step.uses().is_none());
+    assert_eq!(step.run(), Some("make"));
+    assert!(step.with().is_none());
+    assert!(
+        matches!(step.env(), LoE::Literal(env) if env["CC"] == EnvValue::String("clang".into()))
+    );
+}
+
+#[test]
+fn test_runs_errors() {
+    let vectors = [
+        (
+            r#"
+name: broken-composite
+runs:
+  using: composite
+  steps:
+    - run: echo hello
+"#,
+            "missing field `shell`",
+        ),
+        (
+            r#"
+name: unknown-runtime
+runs:
+  using: node99
+  main: index.js
+"#,
+            "unknown `using` value `node99`, expected one of: \
+             `composite`, `docker`, `node12`, `node16`, `node20`, `node24`",
+        ),
+        (
+            r#"
+name: missing-using
+runs:
+  main: index.js
+"#,
+            "missing field `using`",
+        ),
+    ];
+
+    for (action, expected) in vectors {
+        let Err(err) = serde_yaml::from_str::<Action>(action) else {
+            panic!("expected {action:?} to fail");
+        };
+        let err = err.to_string();
+        assert!(err.contains(expected), "{err:?} missing {expected:?}");
+        assert!(!err.contains("untagged"));
+    }
+}
+
+#[cfg(feature = "error-paths")]
+#[test]
+fn test_error_paths() {
+    let Err(err) = Action::from_str(
+        "
+name: broken-step
+runs:
+  using: composite
+  steps:
+    - run: echo hello
+      shell: bash
+    - uses: [actions/checkout@v4]
+",
+    ) else {
+        panic!("expected a broken composite step to fail");
+    };
+    assert_eq!(err.path(), Some("runs.steps[1].uses"));
+    assert!(err.location().is_some());
+
+    let Err(err) = Action::from_str(
+        "
+name: broken-main
+runs:
+  using: node20
+  main: [index.js]
+",
+    ) else {
+        panic!("expected a broken entrypoint to fail");
+    };
+    assert_eq!(err.path(), Some("runs.main"));
+}
+
+#[test]
+fn test_node_runtime() {
+    for (using, runtime, deprecated) in [
+        ("node12", NodeRuntime::Node12, true),
+        ("node16", NodeRuntime::Node16, true),
+        ("node20", NodeRuntime::Node20, false),
+        ("node24", NodeRuntime::Node24, false),
+    ] {
+        let parsed = serde_yaml::from_str::<NodeRuntime>(using).unwrap();
+        assert_eq!(parsed, runtime);
+        assert_eq!(parsed.is_deprecated(), deprecated);
+        assert_eq!(parsed.to_string(), using);
+    }
+
+    for invalid in ["Node20", "node", "node18", "20"] {
+        assert!(serde_yaml::from_str::<NodeRuntime>(invalid).is_err());
+    }
+}
+
+#[test]
+fn test_branding() {
+    let action = load_action("branding-gray-dark.yml");
+    let branding = action.branding.unwrap();
+    assert_eq!(branding.icon.as_deref(), Some("shield"));
+    assert_eq!(branding.color, Some(BrandingColor::GrayDark));
+
+    let branding = load_action("setup-python.yml").branding.unwrap();
+    assert_eq!(branding.icon.as_deref(), Some("code"));
+    assert_eq!(branding.color, Some(BrandingColor::Yellow));
+
+    let action = load_action("gh-action-pip-audit.yml");
+    assert!(action.branding.is_none());
+
+    let invalid = "name: bad\nbranding:\n  color: pink\nruns:\n  using: node20\n  main: index.js";
+    assert!(serde_yaml::from_str::<Action>(invalid).is_err());
+}
+
+#[test]
+fn test_from_str() {
+    let action = "name: hello\nruns:\n  using: node20\n  main: index.js"
+        .parse::<Action>()
+        .unwrap();
+    assert_eq!(action.name, "hello");
+
+    let Err(err) = "name: [hello\n".parse::<Action>() else {
+        panic!("expected malformed YAML to fail");
+    };
+    let (github_actions_models::Error::Yaml(inner)
+    | github_actions_models::Error::Field { source: inner, .. }) = &err
+    else {
+        panic!("expected YAML error");
+    };
+    assert!(inner.location().is_some());
+
+    let Err(err) = "name: hello".parse::<Action>() else {
+        panic!("expected missing runs to fail");
+    };
+    assert!(err.to_string().contains("missing field `runs`"));
+}
+
+#[test]
+fn test_runs_kind_and_required_inputs() {
+    let setup_python = load_action("setup-python.yml");
+    assert!(setup_python.is_javascript());
+    assert!(!setup_python.is_composite());
+    assert!(!setup_python.is_docker());
+
+    assert_eq!(
+        setup_python
+            .required_inputs()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>(),
+        [
+            "python-version",
+            "python-version-file",
+            "architecture",
+            "cache-dependency-path"
+        ]
+    );
+    assert!(!setup_python
+        .inputs
+        .values()
+        .any(|input| input.is_required()));
+
+    let action = r#"
+name: example
+inputs:
+  token:
+    description: a token
+    required: true
+  level:
+    description: a level
+    required: true
+    default: info
+  verbose:
+    description: verbosity
+    required: false
+runs:
+  using: docker
+  image: Dockerfile
+"#
+    .parse::<Action>()
+    .unwrap();
+    assert!(action.is_docker());
+    assert_eq!(
+        action
+            .required_inputs()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>(),
+        ["token", "level"]
+    );
+    assert!(action.inputs["token"].is_required());
+    assert!(!action.inputs["level"].is_required());
+    assert!(!action.inputs["verbose"].is_required());
+
+    let action = "name: c\nruns:\n  using: composite\n  steps: []"
+        .parse::<Action>()
+        .unwrap();
+    assert!(action.is_composite());
+    assert_eq!(action.required_inputs().count(), 0);
+}
+
+#[test]
+fn test_validate_identifiers() {
+    let action = r#"
+name: example
+inputs:
+  token:
+    description: a token
+  2fa-code:
+    description: a code
+outputs:
+  "digest value":
+    description: the digest
+    value: ${{ steps.build.outputs.digest }}
+runs:
+  using: composite
+  steps:
+    - id: build
+      run: ./build.sh
+      shell: bash
+    - run: echo
+      shell: bash
+    - id: ünïcode
+      run: echo
+      shell: bash
+"#
+    .parse::<Action>()
+    .unwrap();
+
+    let diagnostics = action.validate_identifiers();
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.code == diagnostic::INVALID_IDENTIFIER));
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| d.path.join("."))
+            .collect::<Vec<_>>(),
+        ["inputs.2fa-code", "outputs.digest value", "runs.steps.2.id"]
+    );
+    assert_eq!(
+        diagnostics[2].message,
+        "step ID `ünïcode` must start with a letter or `_` and contain only \
+         alphanumeric characters, `-`, or `_`"
+    );
+
+    assert!(load_action("setup-python.yml")
+        .validate_identifiers()
+        .is_empty());
+}
+
+#[test]
+fn test_visitor() {
+    #[derive(Default)]
+    struct Counter<'a> {
+        steps: usize,
+        uses: Vec<(usize, &'a Uses)>,
+        runs: usize,
+        expressions: Vec<(Option<usize>, &'a str)>,
+    }
+
+    impl<'a> ActionVisitor<'a> for Counter<'a> {
+        fn visit_step(&mut self, _index: usize, _step: &'a Step) {
+            self.steps += 1;
+        }
+
+        fn visit_uses(&mut self, index: usize, uses: &'a Uses) {
+            self.uses.push((index, uses));
+        }
+
+        fn visit_run(&mut self, _index: usize, _run: &'a str) {
+            self.runs += 1;
+        }
+
+        fn visit_expression(&mut self, step_index: Option<usize>, expression: &'a str) {
+            self.expressions.push((step_index, expression));
+        }
+    }
+
+    let action = load_action("gh-action-sigstore-python.yml");
+    let mut counter = Counter::default();
+    walk_action(&action, &mut counter);
+    assert_eq!(counter.steps, action.steps().len());
+    assert_eq!(
+        counter
+            .uses
+            .iter()
+            .map(|(_, uses)| uses.to_string())
+            .collect::<Vec<_>>(),
+        [
+            "actions/upload-artifact@v4",
+            "softprops/action-gh-release@v1"
+        ]
+    );
+    assert_eq!(counter.runs, 2);
+
+    let action = Action::from_str(
+        r#"
+name: greet
+inputs:
+  who:
+    description: who to greet
+    default: ${{ github.actor }}
+runs:
+  using: composite
+  steps:
+    - if: inputs.who != ''
+      run: echo "hello ${{ inputs.who }}"
+      shell: bash
+      env:
+        GREETING: ${{ env.GREETING }}
+"#,
+    )
+    .unwrap();
+    let mut counter = Counter::default();
+    walk_action(&action, &mut counter);
+    assert_eq!(
+        counter.expressions,
+        [
+            (None, "github.actor"),
+            (Some(0), "inputs.who != ''"),
+            (Some(0), "inputs.who"),
+            (Some(0), "env.GREETING"),
+        ]
+    );
+}
+
+#[test]
+fn test_from_str_strict() {
+    let sample_actions = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-actions");
+    for sample_action in std::fs::read_dir(sample_actions).unwrap() {
+        let sample_action = sample_action.unwrap().path();
+        let contents = std::fs::read_to_string(&sample_action).unwrap();
+        if let Err(e) = Action::from_str_strict(&contents) {
+            panic!("failed to strictly parse {sample_action:?}: {e}");
+        }
+    }
+
+    let Err(err) = Action::from_str_strict(
+        "
+name: hello
+runs:
+  using: composite
+  steps:
+    - run: echo hi
+      shell: bash
+      working_directory: src
+",
+    ) else {
+        panic!("expected misspelled step key to fail");
+    };
+    assert_eq!(err.path(), Some("runs.steps[0].working_directory"));
+    assert_eq!(err.location(), Some((8, 7)));
+}
+
+#[test]
+fn test_unknown_keys() {
+    let sample_actions = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-actions");
+    for sample_action in std::fs::read_dir(sample_actions).unwrap() {
+        let sample_action = sample_action.unwrap().path();
+        let action = Action::from_path(&sample_action).unwrap();
+        let unknown = action.unknown_keys();
+        assert!(
+            unknown.is_empty(),
+            "unexpected unknown keys in {sample_action:?}: {unknown:?}"
+        );
+    }
+
+    let contents = "
+name: hello
+descripton: says hello
+input:
+  who: {}
+outputs:
+  greeting:
+    description: the greeting
+    vaule: hi
+runs:
+  using: node20
+  main: index.js
+  pre_if: always()
+branding:
+  colour: blue
+";
+    let action: Action = contents.parse().unwrap();
+
+    let unknown = action.unknown_keys();
+    assert_eq!(
+        unknown.iter().map(|u| u.path.as_str()).collect::<Vec<_>>(),
+        [
+            "descripton",
+            "input",
+            "outputs.greeting.vaule",
+            "runs.pre_if",
+            "branding.colour"
+        ]
+    );
+    assert_eq!(unknown[0].value.as_str(), Some("says hello"));
+    assert_eq!(unknown[0].location(contents), Some((3, 1)));
+    assert_eq!(unknown[2].location(contents), Some((9, 5)));
+    // A key that isn't in the document can't be located.
+    assert_eq!(unknown[0].location("name: hello\n"), None);
+    assert!(action.description.is_none());
+    assert!(action.inputs.is_empty());
+}
@@ -0,0 +1,136 @@
+use std::path::Path;
+
+use github_actions_models::{
+    common::{expr::LoE, Shell},
+    compat::v0_22,
+    workflow::{
+        event::{OptionalBody, PullRequestActivityType},
+        job::{self, StepBody},
+        Concurrency, Trigger, Workflow,
+    },
+};
+use serde_yaml::Value;
+
+fn load_v0_22(name: &str) -> (String, Value) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/sample-compat/v0_22")
+        .join(name);
+    let contents = std::fs::read_to_string(path).unwrap();
+    let doc = serde_yaml::from_str(&contents).unwrap();
+    (contents, doc)
+}
+
+/// Deserialize the part of a 0.22 document at `value` under an old shape.
+fn old<T: serde::de::DeserializeOwned>(value: &Value) -> T {
+    serde_yaml::from_value(value.clone()).unwrap()
+}
+
+#[test]
+fn test_v0_22_ci() {
+    let (contents, doc) = load_v0_22("ci.yml");
+    // The fixture is also a valid current workflow, so each conversion can
+    // be checked against the current models' own parse.
+    let current = Workflow::from_str_strict(&contents).unwrap();
+
+    let on = Trigger::try_from(old::<v0_22::Trigger>(&doc["on"])).unwrap();
+    assert_eq!(on.normalized(), current.on.normalized());
+    let Trigger::Events(events) = &on else {
+        panic!("expected events");
+    };
+    let OptionalBody::Body(pull_request) = &events.pull_request else {
+        panic!("expected pull_request body");
+    };
+    assert_eq!(
+        pull_request.types,
+        [
+            PullRequestActivityType::Opened,
+            PullRequestActivityType::Synchronize,
+            PullRequestActivityType::Reopened,
+        ]
+    );
+    let OptionalBody::Body(dispatch) = &events.repository_dispatch else {
+        panic!("expected repository_dispatch body");
+    };
+    assert_eq!(dispatch.all_event_types().collect::<Vec<_>>(), ["deploy"]);
+    let OptionalBody::Body(schedule) = &events.schedule else {
+        panic!("expected schedule body");
+    };
+    assert!(schedule[0].is_valid_schedule());
+
+    let permissions = old::<v0_22::Permissions>(&doc["permissions"]);
+    assert_eq!(Some(permissions), current.permissions);
+
+    let concurrency = Concurrency::from(old::<v0_22::Concurrency>(&doc["concurrency"]));
+    let Concurrency::Rich {
+        group: LoE::Literal(group),
+        cancel_in_progress: LoE::Literal(true),
+    } = &concurrency
+    else {
+        panic!("expected rich concurrency with a templated group");
+    };
+    assert_eq!(group, "ci-${{ github.ref }}");
+    assert_eq!(
+        concurrency.group(),
+        current.concurrency.as_ref().unwrap().group()
+    );
+
+    let deploy = &doc["jobs"]["deploy"];
+    let current_deploy = current.get_normal_job("deploy").unwrap();
+
+    let environment = job::DeploymentEnvironment::from(old::<v0_22::DeploymentEnvironment>(
+        &deploy["environment"],
+    ));
+    let current_environment = current_deploy.environment.as_ref().unwrap();
+    assert_eq!(environment.name(), current_environment.name());
+    assert_eq!(environment.url(), current_environment.url());
+    assert!(matches!(
+        environment,
+        job::DeploymentEnvironment::NameURL {
+            name: LoE::Literal(_),
+            url: Some(LoE::Expr(_)),
+        }
+    ));
+
+    let steps = deploy["steps"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .map(|step| job::Step::from(old::<v0_22::Step>(step)))
+        .collect::<Vec<_>>();
+    assert_eq!(steps.len(), current_deploy.steps.len());
+
+    for (step, current_step) in steps.iter().zip(&current_deploy.steps) {
+        assert_eq!(step.id, current_step.id);
+        assert_eq!(step.uses(), current_step.uses());
+        assert_eq!(step.run(), current_step.run());
+        assert_eq!(step.timeout_minutes, current_step.timeout_minutes);
+    }
+
+    // 0.22 didn't model `env:` on `uses:` steps, so it's injected as empty.
+    assert_eq!(steps[0].env(), &LoE::default());
+    assert_eq!(steps[0].uses().unwrap().to_string(), "actions/checkout@v4");
+
+    let StepBody::Run { shell, env, .. } = &steps[1].body else {
+        panic!("expected run step");
+    };
+    assert_eq!(shell, &Some(Shell::Bash));
+    assert_eq!(env, current_deploy.steps[1].env());
+}
+
+#[test]
+fn test_v0_22_unknown_activity_type() {
+    let (contents, doc) = load_v0_22("unknown-activity-type.yml");
+    // The current models reject the unknown activity type outright...
+    assert!(Workflow::from_str_strict(&contents).is_err());
+
+    // ...while 0.22 accepted it, so only the conversion fails.
+    let old = old::<v0_22::Trigger>(&doc["on"]);
+    let Err(err) = Trigger::try_from(old) else {
+        panic!("expected unknown activity type to fail");
+    };
+    assert_eq!(err, v0_22::UnknownActivityType("opend".into()));
+    assert_eq!(
+        err.to_string(),
+        "unknown pull request activity type `opend`"
+    );
+}
@@ -1,16 +1,17 @@
-use std::path::Path;
+use std::{path::Path, str::FromStr};
 
-use github_actions_models::dependabot::v2::{
-    Dependabot, Interval, PackageEcosystem, RebaseStrategy,
+use github_actions_models::{
+    common::diagnostic,
+    dependabot::v2::{Dependabot, Interval, PackageEcosystem, RebaseStrategy, Registry},
+    LoadError,
 };
 use indexmap::IndexSet;
 
 fn load_dependabot(name: &str) -> Dependabot {
-    let workflow_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+    let dependabot_path = Path::new(env!("CARGO_MANIFEST_DIR"))
         .join("tests/sample-dependabot/v2")
         .join(name);
-    let dependabot_contents = std::fs::read_to_string(workflow_path).unwrap();
-    serde_yaml::from_str(&dependabot_contents).unwrap()
+    Dependabot::from_path(dependabot_path).unwrap()
 }
 
 #[test]
@@ -18,12 +19,28 @@ fn test_load_all() {
     let sample_configs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-dependabot/v2");
 
     for sample_config in std::fs::read_dir(sample_configs).unwrap() {
-        let sample_workflow = sample_config.unwrap().path();
-        let contents = std::fs::read_to_string(sample_workflow).unwrap();
-        serde_yaml::from_str::<Dependabot>(&contents).unwrap();
+        Dependabot::from_path(sample_config.unwrap().path()).unwrap();
     }
 }
 
+#[test]
+fn test_from_path_errors() {
+    let sample_configs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-dependabot/v2");
+
+    let missing = sample_configs.join("nonexistent.yml");
+    let Err(err) = Dependabot::from_path(&missing) else {
+        panic!("expected an error for {missing:?}");
+    };
+    assert!(matches!(err, LoadError::Io { .. }));
+    assert_eq!(err.path(), missing);
+    assert!(err.location().is_none());
+
+    let Err(err) = Dependabot::from_str("version: 2\nupdates: \"unterminated\n") else {
+        panic!("expected an error for invalid YAML");
+    };
+    assert_eq!(err.location(), Some((3, 1)));
+}
+
 #[test]
 fn test_contents() {
     let dependabot = load_dependabot("sigstore-python.yml");
@@ -65,3 +82,292 @@ fn test_contents() {
         IndexSet::from(["*".to_string()])
     );
 }
+
+#[test]
+fn test_from_str() {
+    let dependabot = "version: 2\nupdates: []".parse::<Dependabot>().unwrap();
+    assert!(dependabot.updates.is_empty());
+
+    let Err(err) = "version: 2\nupdates: {\n".parse::<Dependabot>() else {
+        panic!("expected malformed YAML to fail");
+    };
+    let (github_actions_models::Error::Yaml(inner)
+    | github_actions_models::Error::Field { source: inner, .. }) = &err
+    else {
+        panic!("expected YAML error");
+    };
+    assert!(inner.location().is_some());
+
+    let Err(err) = "updates: []".parse::<Dependabot>() else {
+        panic!("expected missing version to fail");
+    };
+    assert!(err.to_string().contains("missing field `version`"));
+}
+
+#[test]
+fn test_pull_request_branch_name() {
+    let dependabot = load_dependabot("branch-name-separator.yml");
+
+    let branch_name = dependabot.updates[0]
+        .pull_request_branch_name
+        .as_ref()
+        .unwrap();
+    assert_eq!(branch_name.separator.as_deref(), Some("_"));
+    assert_eq!(branch_name.separator(), "_");
+
+    // An empty configuration gets the default separator.
+    let branch_name = dependabot.updates[1]
+        .pull_request_branch_name
+        .as_ref()
+        .unwrap();
+    assert!(branch_name.separator.is_none());
+    assert_eq!(branch_name.separator(), "/");
+
+    assert!(dependabot.updates[2].pull_request_branch_name.is_none());
+}
+
+#[test]
+fn test_updates_for_ecosystem() {
+    let dependabot = load_dependabot("sigstore-python.yml");
+
+    assert_eq!(
+        dependabot
+            .updates_for_ecosystem(&PackageEcosystem::Pip)
+            .len(),
+        1
+    );
+
+    let github_actions = dependabot.updates_for_ecosystem(&PackageEcosystem::GithubActions);
+    assert_eq!(github_actions.len(), 2);
+    assert_eq!(github_actions[0].directory, "/");
+    assert_eq!(
+        github_actions[1].directory,
+        ".github/actions/upload-coverage/"
+    );
+
+    assert!(dependabot
+        .updates_for_ecosystem(&PackageEcosystem::Cargo)
+        .is_empty());
+
+    assert_eq!(
+        dependabot.ecosystems().collect::<Vec<_>>(),
+        [
+            &PackageEcosystem::Pip,
+            &PackageEcosystem::GithubActions,
+            &PackageEcosystem::GithubActions,
+        ]
+    );
+}
+
+#[test]
+fn test_resolve_registries() {
+    let dependabot = load_dependabot("registries.yml");
+
+    // One known registry and one unknown one.
+    let npm = &dependabot.updates[0];
+    let registries = npm.resolve_registries(&dependabot);
+    assert_eq!(registries.len(), 1);
+    assert!(matches!(
+        registries[0],
+        Registry::NpmRegistry { url, .. } if url == "https://npm.example.com"
+    ));
+    assert!(npm.has_unknown_registries(&dependabot));
+
+    // `*` means every registry.
+    let docker = &dependabot.updates[1];
+    assert_eq!(docker.resolve_registries(&dependabot).len(), 2);
+    assert!(!docker.has_unknown_registries(&dependabot));
+
+    let github_actions = &dependabot.updates[2];
+    assert!(github_actions.resolve_registries(&dependabot).is_empty());
+    assert!(!github_actions.has_unknown_registries(&dependabot));
+
+    let diagnostics = dependabot.validate_registries();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, diagnostic::UNKNOWN_REGISTRY);
+    assert_eq!(diagnostics[0].path, ["updates", "0", "registries", "1"]);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "error[E107-unknown-registry]: update uses unknown registry `npm-missing`"
+    );
+}
+
+#[test]
+fn test_ignore_matching() {
+    let dependabot = load_dependabot("ignore.yml");
+
+    let npm = &dependabot.updates[0].ignore;
+    let (express, lodash, aws_sdk, types) = (&npm[0], &npm[1], &npm[2], &npm[3]);
+
+    assert!(express.applies("express", "4.18.2"));
+    assert!(express.applies("Express", "5.0.0"));
+    assert!(!express.applies("express", "3.4.0"));
+    assert!(!express.applies("express-session", "4.18.2"));
+
+    // No `versions` means every version.
+    assert!(lodash.applies("lodash", "4.17.21"));
+    assert!(!lodash.matches_dependency("lodash.merge"));
+    assert!(aws_sdk.applies("aws-sdk", "2.1.0"));
+
+    assert!(types.matches_dependency("@types/node"));
+    assert!(!types.matches_dependency("types/node"));
+    assert!(types.applies("@types/node", "18.0.0"));
+    assert!(types.applies("@types/node", "19.11.2"));
+    assert!(!types.applies("@types/node", "20.1.0"));
+    assert!(!types.applies("@types/node", "17.9.9"));
+
+    let bundler = &dependabot.updates[1].ignore;
+    let (rails, rubocop) = (&bundler[0], &bundler[1]);
+
+    assert!(rails.applies("rails", "7.1.0"));
+    assert!(rails.applies("rails", "7.2.3"));
+    assert!(!rails.applies("rails", "8.0.0"));
+    assert!(!rails.applies("rails", "7.0.8"));
+
+    assert!(rubocop.applies("rubocop", "1.57.2"));
+    assert!(rubocop.applies("rubocop-rails", "1.57.2"));
+    assert!(!rubocop.applies("rubocop", "1.57.3"));
+    assert!(!rubocop.applies("standard", "1.57.2"));
+}
+
+#[test]
+fn test_package_ecosystem_names() {
+    assert_eq!(
+        PackageEcosystem::GithubActions.to_string(),
+        "github-actions"
+    );
+    assert_eq!(PackageEcosystem::Cargo.to_string(), "cargo");
+    assert_eq!(
+        PackageEcosystem::Gitsubmodule.canonical_name(),
+        "gitsubmodule"
+    );
+
+    // Every name round-trips through deserialization.
+    for name in [
+        "bundler",
+        "cargo",
+        "composer",
+        "docker",
+        "elm",
+        "gitsubmodule",
+        "github-actions",
+        "gomod",
+        "gradle",
+        "maven",
+        "mix",
+        "npm",
+        "nuget",
+        "pip",
+        "pub",
+        "swift",
+        "terraform",
+    ] {
+        let ecosystem: PackageEcosystem = serde_yaml::from_str(name).unwrap();
+        assert_eq!(ecosystem.canonical_name(), name);
+    }
+
+    assert!(PackageEcosystem::Cargo.is_language_ecosystem());
+    assert!(PackageEcosystem::Pip.is_language_ecosystem());
+    for ecosystem in [
+        PackageEcosystem::Docker,
+        PackageEcosystem::GithubActions,
+        PackageEcosystem::Gitsubmodule,
+        PackageEcosystem::Terraform,
+    ] {
+        assert!(!ecosystem.is_language_ecosystem(), "{ecosystem}");
+    }
+}
+
+#[cfg(feature = "error-paths")]
+#[test]
+fn test_error_paths() {
+    let Err(err) = Dependabot::from_str(
+        "
+version: 2
+updates:
+  - package-ecosystem: pip
+    directory: /
+    schedule:
+      interval: daily
+  - package-ecosystem: npm
+    directory: /
+    schedule:
+      interval: hourly
+",
+    ) else {
+        panic!("expected a broken update to fail");
+    };
+    assert_eq!(err.path(), Some("updates[1].schedule.interval"));
+    assert_eq!(err.location(), Some((11, 17)));
+}
+
+#[test]
+fn test_from_str_strict() {
+    let sample_configs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-dependabot/v2");
+    for sample_config in std::fs::read_dir(sample_configs).unwrap() {
+        let sample_config = sample_config.unwrap().path();
+        let contents = std::fs::read_to_string(&sample_config).unwrap();
+        if let Err(e) = Dependabot::from_str_strict(&contents) {
+            panic!("failed to strictly parse {sample_config:?}: {e}");
+        }
+    }
+
+    let Err(err) = Dependabot::from_str_strict(
+        "
+version: 2
+updates:
+  - package-ecosystem: pip
+    directory: /
+    schedule:
+      interval: daily
+    open-pull-request-limit: 10
+",
+    ) else {
+        panic!("expected misspelled update key to fail");
+    };
+    assert_eq!(err.path(), Some("updates[0].open-pull-request-limit"));
+    assert_eq!(
+        err.to_string(),
+        "unknown key `updates[0].open-pull-request-limit` at line 8 column 5"
+    );
+}
+
+#[test]
+fn test_unknown_keys() {
+    let sample_configs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-dependabot/v2");
+    for sample_config in std::fs::read_dir(sample_configs).unwrap() {
+        let sample_config = sample_config.unwrap().path();
+        let dependabot = Dependabot::from_path(&sample_config).unwrap();
+        let unknown = dependabot.unknown_keys();
+        assert!(
+            unknown.is_empty(),
+            "unexpected unknown keys in {sample_config:?}: {unknown:?}"
+        );
+    }
+
+    let contents = "
+version: 2
+updates:
+  - package-ecosystem: pip
+    directory: /
+    schedule:
+      interval: daily
+  - package-ecosystem: cargo
+    directory: /
+    schedule:
+      interval: weekly
+    open-pull-request-limit: 10
+regsitries: {}
+";
+    let dependabot: Dependabot = contents.parse().unwrap();
+
+    let unknown = dependabot.unknown_keys();
+    assert_eq!(
+        unknown.iter().map(|u| u.path.as_str()).collect::<Vec<_>>(),
+        ["regsitries", "updates[1].open-pull-request-limit"]
+    );
+    assert_eq!(unknown[1].key, "open-pull-request-limit");
+    assert_eq!(unknown[0].location(contents), Some((13, 1)));
+    assert_eq!(unknown[1].location(contents), Some((12, 5)));
+    assert_eq!(dependabot.updates[1].open_pull_requests_limit, 5);
+}
@@ -0,0 +1,93 @@
+use std::{collections::HashSet, path::Path};
+
+use github_actions_models::{
+    action::Action,
+    common::{
+        diagnostic::{Diagnostic, CODES},
+        expr::LoE,
+    },
+    dependabot::v2::Dependabot,
+    workflow::{Job, Workflow},
+};
+
+fn fixtures(dir: &str) -> impl Iterator<Item = std::path::PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(dir);
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "yml" || ext == "yaml")
+        })
+}
+
+fn workflow_diagnostics(workflow: &Workflow) -> Vec<Diagnostic> {
+    let mut diagnostics = workflow.on.duplicate_events();
+    diagnostics.extend(workflow.validate_needs());
+    diagnostics.extend(workflow.validate_identifiers());
+    diagnostics.extend(workflow.validate_step_ids());
+    diagnostics.extend(workflow.output_diagnostics());
+
+    for job in workflow.jobs.values() {
+        match job {
+            Job::NormalJob(job) => {
+                if let Some(LoE::Literal(matrix)) =
+                    job.strategy.as_ref().and_then(|s| s.matrix.as_ref())
+                {
+                    diagnostics.extend(matrix.validate());
+                }
+            }
+            Job::ReusableWorkflowCallJob(job) => {
+                diagnostics.extend(job.secret_placement_diagnostics(&[]));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Every diagnostic emitted for any fixture uses a registered code.
+#[test]
+fn test_diagnostics_use_registered_codes() {
+    let mut diagnostics = vec![];
+
+    for path in fixtures("sample-workflows").chain(fixtures("regression-workflows")) {
+        // Some regression workflows are deliberately invalid.
+        if let Ok(workflow) = Workflow::from_path(&path) {
+            diagnostics.extend(workflow_diagnostics(&workflow));
+        }
+    }
+    for path in fixtures("sample-actions") {
+        diagnostics.extend(Action::from_path(&path).unwrap().validate_identifiers());
+    }
+    for path in fixtures("sample-dependabot/v2") {
+        diagnostics.extend(Dependabot::from_path(&path).unwrap().validate_registries());
+    }
+
+    for diagnostic in &diagnostics {
+        assert!(
+            CODES.contains(&diagnostic.code),
+            "unregistered code in {diagnostic}"
+        );
+    }
+
+    // Each validator emits something for at least one fixture, so the
+    // check above isn't vacuous.
+    let emitted = diagnostics
+        .iter()
+        .map(|d| d.code.code)
+        .collect::<HashSet<_>>();
+    for code in CODES {
+        if code.code == "E104-reserved-matrix-dimension" {
+            // Only possible for a matrix constructed directly.
+            continue;
+        }
+        assert!(
+            emitted.contains(code.code),
+            "no fixture emits {}",
+            code.code
+        );
+    }
+}
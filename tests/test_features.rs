@@ -0,0 +1,44 @@
+//! Exercises each model family's entry points behind its feature, so
+//! that every feature combination in CI compiles and runs something.
+
+use std::str::FromStr;
+
+use github_actions_models::common::{diagnostic::CODES, is_identifier, Uses};
+
+#[test]
+fn test_common() {
+    // `common` has no feature of its own, and is always available.
+    assert!(is_identifier("build"));
+    assert!(Uses::from_str("actions/checkout@v4").is_ok());
+    assert!(!CODES.is_empty());
+}
+
+#[cfg(feature = "workflow")]
+#[test]
+fn test_workflow() {
+    use github_actions_models::workflow::Workflow;
+
+    let workflow = Workflow::from_str("on: push\njobs: {}").unwrap();
+    assert!(workflow.jobs.is_empty());
+    assert!(Workflow::from_str_strict("on: push\njobs: {}\nname2: x").is_err());
+}
+
+#[cfg(feature = "action")]
+#[test]
+fn test_action() {
+    use github_actions_models::action::Action;
+
+    let action = "name: a\nruns:\n  using: composite\n  steps:\n    - run: echo\n      shell: bash";
+    assert_eq!(Action::from_str(action).unwrap().steps().len(), 1);
+    assert!(Action::from_str_strict(&format!("{action}\nbrand: x")).is_err());
+}
+
+#[cfg(feature = "dependabot")]
+#[test]
+fn test_dependabot() {
+    use github_actions_models::dependabot::v2::Dependabot;
+
+    let dependabot = "version: 2\nupdates: []";
+    assert!(Dependabot::from_str(dependabot).unwrap().updates.is_empty());
+    assert!(Dependabot::from_str_strict(&format!("{dependabot}\nregsitries: {{}}")).is_err());
+}
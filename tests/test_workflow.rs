@@ -1,36 +1,95 @@
-use std::{env, path::Path, str::FromStr};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use github_actions_models::{
     common::{
-        expr::{ExplicitExpr, LoE},
+        expr::{BoE, ContextTrust, ExplicitExpr, LoE},
         Uses,
     },
     workflow::{
-        event::OptionalBody,
-        job::{RunsOn, StepBody},
-        Job, Trigger, Workflow,
+        event::{BareEvent, Cron, EventKind, OptionalBody},
+        job::{Container, ContainerPort, NameKind, RunsOn, StepBody},
+        visit::{walk_workflow, WorkflowVisitor},
+        Concurrency, Job, StepHandle, Trigger, UsesContext, Workflow, WorkflowExpression,
     },
+    LoadError,
 };
 
 fn load_workflow(name: &str) -> Workflow {
     let workflow_path = Path::new(env!("CARGO_MANIFEST_DIR"))
         .join("tests/sample-workflows")
         .join(name);
-    let workflow_contents = std::fs::read_to_string(workflow_path).unwrap();
-    serde_yaml::from_str(&workflow_contents).unwrap()
+    Workflow::from_path(workflow_path).unwrap()
+}
+
+/// Returns every workflow that's expected to parse: each one in
+/// `tests/sample-workflows`, and each `ok` case in the regression corpus
+/// (see [`test_regression_corpus`]).
+fn valid_workflows() -> Vec<PathBuf> {
+    let tests = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+
+    let samples = std::fs::read_dir(tests.join("sample-workflows"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path());
+    let regressions = std::fs::read_dir(tests.join("regression-workflows"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext != "expected")
+                && std::fs::read_to_string(path.with_extension("expected"))
+                    .is_ok_and(|expected| expected.trim() == "ok")
+        });
+
+    samples.chain(regressions).collect()
 }
 
 #[test]
 fn test_load_all() {
+    for sample_workflow in valid_workflows() {
+        let wf = Workflow::from_path(&sample_workflow);
+        assert!(wf.is_ok(), "failed to parse {sample_workflow:?}");
+    }
+}
+
+#[test]
+fn test_from_path_errors() {
     let sample_workflows = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-workflows");
 
-    for sample_workflow in std::fs::read_dir(sample_workflows).unwrap() {
-        let sample_workflow = sample_workflow.unwrap().path();
-        let workflow_contents = std::fs::read_to_string(&sample_workflow).unwrap();
+    let missing = sample_workflows.join("nonexistent.yml");
+    let Err(err) = Workflow::from_path(&missing) else {
+        panic!("expected an error for {missing:?}");
+    };
+    assert!(matches!(err, LoadError::Io { .. }));
+    assert_eq!(err.path(), missing);
+    assert!(err
+        .to_string()
+        .starts_with(&format!("couldn't read {}: ", missing.display())));
 
-        let wf = serde_yaml::from_str::<Workflow>(&workflow_contents);
-        assert!(wf.is_ok(), "failed to parse {sample_workflow:?}");
+    // A dependabot config is valid YAML, but not a valid workflow.
+    let dependabot = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/sample-dependabot/v2/sigstore-python.yml");
+    let Err(err) = Workflow::from_path(&dependabot) else {
+        panic!("expected an error for {dependabot:?}");
+    };
+    assert!(matches!(err, LoadError::Parse { .. }));
+    assert!(err
+        .to_string()
+        .starts_with(&format!("couldn't load {}: ", dependabot.display())));
+    assert!(err.location().is_some());
+    assert!(LoadError::Io {
+        path: missing,
+        source: std::io::ErrorKind::NotFound.into()
     }
+    .location()
+    .is_none());
+
+    let Err(err) = Workflow::from_str("on: push\nname: \"unterminated\n") else {
+        panic!("expected an error for invalid YAML");
+    };
+    assert_eq!(err.location(), Some((3, 1)));
 }
 
 #[test]
@@ -53,13 +112,13 @@ fn test_pip_audit_ci() {
     );
     assert_eq!(test_job.steps.len(), 3);
 
-    let StepBody::Uses { uses, with } = &test_job.steps[0].body else {
+    let StepBody::Uses { uses, with, .. } = &test_job.steps[0].body else {
         panic!("expected uses step");
     };
     assert_eq!(uses, &Uses::from_str("actions/checkout@v4.1.1").unwrap());
     assert!(with.is_empty());
 
-    let StepBody::Uses { uses, with } = &test_job.steps[1].body else {
+    let StepBody::Uses { uses, with, .. } = &test_job.steps[1].body else {
         panic!("expected uses step");
     };
     assert_eq!(uses, &Uses::from_str("actions/setup-python@v5").unwrap());
@@ -94,3 +153,1199 @@ fn test_runs_on_expr() {
         LoE::Expr(ExplicitExpr::from_curly("${{ matrix.runner }}").unwrap())
     );
 }
+
+#[test]
+fn test_deployment_protection_rule() {
+    let workflow = load_workflow("deployment-protection-rule.yml");
+
+    let Trigger::Events(events) = workflow.on else {
+        panic!("expected events trigger");
+    };
+    assert_eq!(events.count(), 1);
+
+    let OptionalBody::Body(body) = &events.deployment_protection_rule else {
+        panic!("expected deployment_protection_rule body");
+    };
+    assert_eq!(body.types, ["requested"]);
+}
+
+#[test]
+fn test_repository_dispatch() {
+    let workflow = load_workflow("repository-dispatch.yml");
+
+    let Trigger::Events(events) = workflow.on else {
+        panic!("expected events trigger");
+    };
+    assert_eq!(events.count(), 1);
+
+    let OptionalBody::Body(body) = &events.repository_dispatch else {
+        panic!("expected repository_dispatch body");
+    };
+    assert_eq!(body.types, ["on-demand-test"]);
+    assert_eq!(body.event_types, ["deploy", "rollback"]);
+    assert_eq!(
+        body.all_event_types().collect::<Vec<_>>(),
+        ["on-demand-test", "deploy", "rollback"]
+    );
+}
+
+#[test]
+fn test_workflow_call_secrets() {
+    let workflow = load_workflow("workflow-call-secrets.yml");
+
+    let Trigger::Events(events) = workflow.on else {
+        panic!("expected events trigger");
+    };
+    let OptionalBody::Body(call) = &events.workflow_call else {
+        panic!("expected workflow_call body");
+    };
+
+    assert_eq!(
+        call.secret_names().collect::<Vec<_>>(),
+        [
+            "registry-token",
+            "signing-key",
+            "slack-webhook",
+            "deploy-key"
+        ]
+    );
+
+    let required = call.required_secrets().collect::<Vec<_>>();
+    assert_eq!(
+        required.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+        ["registry-token", "deploy-key"]
+    );
+    assert_eq!(
+        required[0].1.description.as_deref(),
+        Some("Token for pushing images")
+    );
+
+    let optional = call.optional_secrets().collect::<Vec<_>>();
+    assert_eq!(optional.len(), 2);
+    assert_eq!(optional[0].0, "signing-key");
+    assert!(optional[0].1.is_some_and(|secret| !secret.required));
+    assert_eq!(optional[1].0, "slack-webhook");
+    assert!(optional[1].1.is_none());
+}
+
+#[test]
+fn test_schedule_crons() {
+    let workflow = load_workflow("schedule-crons.yml");
+
+    let Trigger::Events(events) = workflow.on else {
+        panic!("expected events trigger");
+    };
+    let OptionalBody::Body(crons) = &events.schedule else {
+        panic!("expected schedule body");
+    };
+    assert_eq!(crons.len(), 4);
+    assert!(crons.iter().all(|cron| cron.is_valid_schedule()));
+
+    let invalid = r#"
+on:
+  schedule:
+    - cron: "99 * * * *"
+jobs: {}
+"#;
+    assert!(serde_yaml::from_str::<Workflow>(invalid).is_err());
+
+    let cron = |cron: &str| serde_yaml::from_str::<Cron>(&format!("cron: '{cron}'")).unwrap();
+
+    // A step of 1 still means "through the field's maximum".
+    assert_eq!(
+        cron("0 5/1 * * *").hours_utc(),
+        Some((5..=23).collect::<Vec<_>>())
+    );
+    assert_eq!(
+        cron("0 */1 * * *").hours_utc(),
+        Some((0..=23).collect::<Vec<_>>())
+    );
+    assert_eq!(
+        cron("5/1 * * * *").shifted(0).unwrap().utc_times.len(),
+        55 * 24
+    );
+    assert_eq!(cron("0 5 * * *").hours_utc(), Some(vec![5]));
+    assert!(serde_yaml::from_str::<Cron>("cron: '0 5/0 * * *'").is_err());
+}
+
+/// Parses each workflow in `tests/regression-workflows`, checking it against
+/// its `.expected` sidecar.
+///
+/// Each sidecar contains either `ok` (the workflow must parse) or
+/// `error: <message>` (the workflow must fail to parse, with an error
+/// containing `<message>`). To add a new regression case, drop a
+/// `<name>.yml` and `<name>.expected` pair into the directory.
+#[test]
+fn test_regression_corpus() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/regression-workflows");
+
+    for entry in std::fs::read_dir(corpus).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "expected") {
+            continue;
+        }
+
+        let expected_path = path.with_extension("expected");
+        let expected = std::fs::read_to_string(&expected_path)
+            .unwrap_or_else(|_| panic!("missing sidecar {expected_path:?}"));
+        let expected = expected.trim();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let result = serde_yaml::from_str::<Workflow>(&contents);
+
+        match (expected, result) {
+            ("ok", Ok(_)) => {}
+            ("ok", Err(e)) => panic!("expected {path:?} to parse, got: {e}"),
+            (expected, Ok(_)) => panic!("expected {path:?} to fail with {expected:?}"),
+            (expected, Err(e)) => {
+                let Some(message) = expected.strip_prefix("error: ") else {
+                    panic!("malformed sidecar {expected_path:?}: {expected:?}");
+                };
+                assert!(
+                    e.to_string().contains(message),
+                    "expected {path:?} to fail with {message:?}, got: {e}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_reusable_workflow_matrix() {
+    let workflow = load_workflow("reusable-workflow-matrix.yml");
+
+    let Job::ReusableWorkflowCallJob(build) = &workflow.jobs["build"] else {
+        panic!("expected reusable workflow job");
+    };
+
+    let strategy = build.strategy.as_ref().unwrap();
+    assert_eq!(strategy.max_parallel(), Some(2));
+    let Some(LoE::Literal(matrix)) = &strategy.matrix else {
+        panic!("expected literal matrix");
+    };
+    let LoE::Literal(dimensions) = &matrix.dimensions else {
+        panic!("expected literal dimensions");
+    };
+    assert_eq!(dimensions.keys().collect::<Vec<_>>(), ["target", "python"]);
+    assert_eq!(build.with["target"].to_string(), "${{ matrix.target }}");
+
+    assert_eq!(strategy.matrix_keys(), ["target", "python"]);
+    // 3 targets x 2 pythons, plus an include that adds a new python.
+    assert_eq!(strategy.combination_count(), Some(7));
+}
+
+#[test]
+fn test_reusable_workflow_callees() {
+    let workflow = load_workflow("reusable-workflow-callees.yml");
+
+    let Job::ReusableWorkflowCallJob(remote) = &workflow.jobs["remote"] else {
+        panic!("expected reusable workflow job");
+    };
+    assert_eq!(
+        remote.callee_ref().unwrap().full_slug(),
+        "octo-org/example-repo"
+    );
+    assert_eq!(remote.callee_owner(), Some("octo-org"));
+    assert_eq!(remote.callee_repo(), Some("example-repo"));
+    assert_eq!(
+        remote.callee_workflow_path(),
+        Some(".github/workflows/build.yml")
+    );
+    assert_eq!(remote.callee_git_ref(), Some("v1"));
+
+    let Job::ReusableWorkflowCallJob(local) = &workflow.jobs["local"] else {
+        panic!("expected reusable workflow job");
+    };
+    assert!(local.callee_ref().is_none());
+    assert_eq!(local.callee_owner(), None);
+    assert_eq!(local.callee_repo(), None);
+    assert_eq!(local.callee_workflow_path(), None);
+    assert_eq!(local.callee_git_ref(), None);
+}
+
+#[test]
+fn test_strategy_matrix_helpers() {
+    let vectors: &[(&str, &str, &[&str], Option<usize>)] = &[
+        ("pip-audit-ci.yml", "test", &["python"], Some(5)),
+        (
+            "gh-action-sigstore-python-selftest.yml",
+            "selftest",
+            &["os"],
+            Some(3),
+        ),
+        // The whole matrix is an expression.
+        ("pip-api-test.yml", "test", &[], None),
+        // A single dimension is an expression.
+        ("openbao-openbao-test-go.yml", "test-go", &["id"], None),
+    ];
+
+    for (workflow, job, keys, count) in vectors {
+        let workflow = load_workflow(workflow);
+        let Job::NormalJob(job) = &workflow.jobs[*job] else {
+            panic!("expected normal job");
+        };
+        let strategy = job.strategy.as_ref().unwrap();
+        assert_eq!(&strategy.matrix_keys(), keys);
+        assert_eq!(strategy.combination_count(), *count);
+    }
+}
+
+#[test]
+fn test_reusable_workflow_concurrency() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/regression-workflows/reusable-workflow-concurrency.yml");
+    let workflow = Workflow::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+    let Job::ReusableWorkflowCallJob(deploy) = &workflow.jobs["deploy"] else {
+        panic!("expected reusable workflow job");
+    };
+    let Some(Concurrency::Rich {
+        group,
+        cancel_in_progress,
+    }) = &deploy.concurrency
+    else {
+        panic!("expected rich concurrency");
+    };
+    assert_eq!(group.as_str(), "deploy-${{ github.ref }}");
+    assert_eq!(cancel_in_progress, &BoE::Literal(true));
+}
+
+#[test]
+fn test_from_str() {
+    let workflow = Workflow::from_str("on: push\njobs: {}").unwrap();
+    assert!(matches!(workflow.on, Trigger::BareEvent(_)));
+    assert!(workflow.jobs.is_empty());
+
+    // Malformed YAML: the underlying parser error is preserved.
+    let Err(err) = Workflow::from_str("on: push\njobs: [\n") else {
+        panic!("expected malformed YAML to fail");
+    };
+    let (github_actions_models::Error::Yaml(inner)
+    | github_actions_models::Error::Field { source: inner, .. }) = &err
+    else {
+        panic!("expected YAML error");
+    };
+    assert_eq!(err.to_string(), inner.to_string());
+    assert!(inner.location().is_some());
+    assert!(std::error::Error::source(&err).is_some());
+
+    // Well-formed YAML that isn't a workflow.
+    let Err(err) = Workflow::from_str("on: push") else {
+        panic!("expected missing jobs to fail");
+    };
+    assert!(err.to_string().contains("missing field `jobs`"));
+}
+
+#[test]
+fn test_round_trip() {
+    for sample_workflow in valid_workflows() {
+        let workflow_contents = std::fs::read_to_string(&sample_workflow).unwrap();
+        let workflow = Workflow::from_str(&workflow_contents).unwrap();
+
+        // Parse -> emit -> parse again, and compare the two parsed models
+        // via their serialized forms.
+        let emitted = serde_yaml::to_string(&workflow).unwrap();
+        let reparsed = Workflow::from_str(&emitted)
+            .unwrap_or_else(|e| panic!("{sample_workflow:?} failed to re-parse: {e}\n{emitted}"));
+
+        assert_eq!(
+            serde_yaml::to_value(&workflow).unwrap(),
+            serde_yaml::to_value(&reparsed).unwrap(),
+            "{sample_workflow:?} did not round-trip"
+        );
+    }
+}
+
+#[test]
+fn test_to_mermaid() {
+    let workflow = load_workflow("job-graph.yml");
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/job-graph.mmd");
+
+    assert_eq!(
+        workflow.to_mermaid(),
+        std::fs::read_to_string(golden).unwrap()
+    );
+
+    // `end` is a Mermaid keyword, and `missing` isn't a job.
+    let workflow = Workflow::from_str(
+        "
+on: push
+jobs:
+  start:
+    runs-on: ubuntu-latest
+    steps: []
+  end:
+    needs: [start, missing]
+    runs-on: ubuntu-latest
+    steps: []
+",
+    )
+    .unwrap();
+    assert_eq!(
+        workflow.to_mermaid(),
+        "graph TD\n    job_start[\"start\"]\n    job_end[\"end\"]\n    job_start --> job_end\n"
+    );
+}
+
+#[test]
+fn test_all_steps() {
+    let workflow = load_workflow("pip-audit-ci.yml");
+    let steps = workflow
+        .all_steps()
+        .map(|(job, step_id, _)| (job, step_id.into_owned()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        steps,
+        [
+            ("test", "0".into()),
+            ("test", "1".into()),
+            ("test", "2".into())
+        ]
+    );
+    assert_eq!(workflow.all_run_steps().count(), 1);
+    assert_eq!(workflow.all_uses_steps().count(), 2);
+
+    let workflow = load_workflow("pip-api-test.yml");
+    assert_eq!(workflow.all_steps().count(), 18);
+    assert_eq!(workflow.all_run_steps().count(), 9);
+    assert_eq!(workflow.all_uses_steps().count(), 9);
+
+    let (job, step_id, step) = workflow
+        .all_steps()
+        .find(|(_, step_id, _)| step_id == "set-matrix")
+        .unwrap();
+    assert_eq!(job, "build-matrix");
+    assert_eq!(step_id, "set-matrix");
+    assert!(matches!(step.body, StepBody::Run { .. }));
+
+    // Reusable workflow calls have no steps.
+    let workflow = load_workflow("job-graph.yml");
+    let jobs = workflow
+        .all_steps()
+        .map(|(job, _, _)| job)
+        .collect::<Vec<_>>();
+    assert_eq!(jobs, ["lint", "test", "build"]);
+}
+
+#[test]
+fn test_jobs_by_kind() {
+    let workflow = load_workflow("job-graph.yml");
+
+    assert_eq!(
+        workflow
+            .normal_jobs()
+            .map(|(id, job)| (id, job.steps.len()))
+            .collect::<Vec<_>>(),
+        [("lint", 1), ("test", 1), ("build", 1)]
+    );
+
+    let reusable = workflow.reusable_jobs().collect::<Vec<_>>();
+    assert_eq!(reusable.len(), 1);
+    assert_eq!(reusable[0].0, "publish");
+    assert_eq!(
+        reusable[0].1.uses.to_string(),
+        "octo-org/release-workflows/.github/workflows/publish.yml@v2"
+    );
+
+    assert_eq!(
+        workflow.get_normal_job("test").unwrap().name.as_deref(),
+        Some("Test \"all\" platforms")
+    );
+    assert!(workflow.get_normal_job("publish").is_none());
+    assert!(workflow.get_normal_job("missing").is_none());
+
+    // Each job is yielded by exactly one of the iterators.
+    assert_eq!(
+        workflow.normal_jobs().count() + workflow.reusable_jobs().count(),
+        workflow.jobs.len()
+    );
+}
+
+#[test]
+fn test_uses() {
+    let workflow: Workflow = "
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - run: make test
+      - uses: ./.github/actions/setup
+      - uses: docker://alpine:3.8
+  call:
+    uses: octo-org/example/.github/workflows/ci.yml@main
+  lint:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/setup-python@v5
+"
+    .parse()
+    .unwrap();
+
+    let uses = workflow
+        .uses()
+        .map(|(uses, context)| (uses.to_string(), context))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        uses,
+        [
+            (
+                "actions/checkout@v4".into(),
+                UsesContext::Step {
+                    job_id: "test",
+                    index: 0
+                }
+            ),
+            (
+                "./.github/actions/setup".into(),
+                UsesContext::Step {
+                    job_id: "test",
+                    index: 2
+                }
+            ),
+            (
+                "docker://alpine:3.8".into(),
+                UsesContext::Step {
+                    job_id: "test",
+                    index: 3
+                }
+            ),
+            (
+                "octo-org/example/.github/workflows/ci.yml@main".into(),
+                UsesContext::ReusableJob { job_id: "call" }
+            ),
+            (
+                "actions/setup-python@v5".into(),
+                UsesContext::Step {
+                    job_id: "lint",
+                    index: 0
+                }
+            ),
+        ]
+    );
+
+    assert!(matches!(workflow.uses().nth(1), Some((Uses::Local(_), _))));
+    assert!(matches!(workflow.uses().nth(2), Some((Uses::Docker(_), _))));
+}
+
+#[test]
+fn test_clone() {
+    let workflow = load_workflow("pip-api-test.yml");
+    let mut cloned = workflow.clone();
+
+    let (id, Job::NormalJob(job)) = cloned.jobs.get_index_mut(0).unwrap() else {
+        panic!("expected a normal job");
+    };
+    let id = id.clone();
+    job.name = Some("renamed".into());
+
+    assert_eq!(cloned.jobs[&id].name(), Some("renamed"));
+    assert_ne!(workflow.jobs[&id].name(), Some("renamed"));
+    assert_eq!(
+        serde_yaml::to_string(&workflow.jobs.values().nth(1).unwrap()).unwrap(),
+        serde_yaml::to_string(&cloned.jobs.values().nth(1).unwrap()).unwrap()
+    );
+}
+
+#[test]
+fn test_steps() {
+    let workflow = load_workflow("job-graph.yml");
+
+    // `publish` is a reusable workflow call, so it has no steps.
+    let steps = workflow
+        .steps()
+        .map(|handle| (handle.job_id, handle.index, handle.step.run().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        steps,
+        [
+            ("lint", 0, "make lint"),
+            ("test", 0, "make test"),
+            ("build", 0, "make dist")
+        ]
+    );
+
+    let workflow = load_workflow("pip-audit-ci.yml");
+    for handle in workflow.steps() {
+        assert_eq!(handle.job_id, "test");
+        assert!(std::ptr::eq(
+            handle.job,
+            workflow.get_normal_job("test").unwrap()
+        ));
+        assert!(std::ptr::eq(handle.step, &handle.job.steps[handle.index]));
+    }
+    assert_eq!(
+        workflow
+            .steps()
+            .map(|handle| handle.index)
+            .collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+}
+
+#[test]
+fn test_service_ports() {
+    let workflow = load_workflow("service-ports.yml");
+    let Job::NormalJob(job) = &workflow.jobs["integration"] else {
+        panic!("expected normal job");
+    };
+
+    let Some(LoE::Literal(Container::Container { ports, .. })) = &job.container else {
+        panic!("expected container");
+    };
+    assert_eq!(
+        ports[0].container_port().unwrap(),
+        ContainerPort {
+            host: Some(8080),
+            container: 80,
+            protocol: None,
+        }
+    );
+
+    let LoE::Literal(Container::Container { ports, .. }) = &job.services["statsd"] else {
+        panic!("expected container");
+    };
+    assert_eq!(
+        ports[0].container_port().unwrap().protocol.as_deref(),
+        Some("udp")
+    );
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/regression-workflows/service-port-forms.yml");
+    let workflow = Workflow::from_path(path).unwrap();
+    let job = workflow.get_normal_job("integration").unwrap();
+    let LoE::Literal(Container::Container { ports, .. }) = &job.services["postgres"] else {
+        panic!("expected container");
+    };
+    assert_eq!(
+        ports
+            .iter()
+            .map(|port| port.to_string())
+            .collect::<Vec<_>>(),
+        [
+            "127.0.0.1:5432:5432",
+            "8000-8010:8000-8010",
+            "${{ matrix.port }}:5432"
+        ]
+    );
+    assert!(ports.iter().all(|port| port.container_port().is_err()));
+}
+
+#[test]
+fn test_script_interpolations() {
+    let workflow = load_workflow("script-injection.yml");
+    let findings = workflow.script_interpolations().collect::<Vec<_>>();
+    assert_eq!(findings.len(), 3);
+
+    // A benign matrix interpolation.
+    let matrix = &findings[0];
+    assert_eq!((matrix.job_id, matrix.step_index), ("triage", 0));
+    assert_eq!(matrix.expression, "matrix.os");
+    assert_eq!(matrix.contexts, [("matrix.os", ContextTrust::Trusted)]);
+    assert_eq!(matrix.trust, ContextTrust::Trusted);
+
+    // An attacker-controlled PR title, quoted via its span.
+    let title = &findings[1];
+    assert_eq!(title.step.name.as_deref(), Some("greet"));
+    assert_eq!(title.trust, ContextTrust::Untrusted);
+    let StepBody::Run { run, .. } = &title.step.body else {
+        panic!("expected run step");
+    };
+    assert_eq!(
+        &run[title.span.clone()],
+        "${{ github.event.pull_request.title }}"
+    );
+
+    // Only the script is checked, not `env:` (which is the safe pattern).
+    let input = &findings[2];
+    assert_eq!(input.step_index, 2);
+    assert_eq!(input.expression, "inputs.label || 'triage'");
+    assert_eq!(input.contexts, [("inputs.label", ContextTrust::Unknown)]);
+    assert_eq!(input.trust, ContextTrust::Unknown);
+}
+
+#[test]
+fn test_from_slice() {
+    let sample_encodings = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-encodings");
+
+    for name in ["utf8-bom.yml", "utf16le.yml", "utf16be.yml"] {
+        let contents = std::fs::read(sample_encodings.join(name)).unwrap();
+        let workflow = Workflow::from_slice(&contents).unwrap();
+        assert_eq!(workflow.name.as_deref(), Some("encoded"));
+
+        let Job::NormalJob(job) = &workflow.jobs["test"] else {
+            panic!("expected normal job");
+        };
+        let StepBody::Run { run, .. } = &job.steps[0].body else {
+            panic!("expected run step");
+        };
+        assert_eq!(run, "echo \"héllo ✓\"");
+    }
+
+    let contents = std::fs::read(sample_encodings.join("invalid-utf8.yml")).unwrap();
+    let Err(err) = Workflow::from_slice(&contents) else {
+        panic!("expected invalid UTF-8 to fail");
+    };
+    assert!(matches!(
+        err,
+        github_actions_models::Error::Encoding {
+            encoding: "UTF-8",
+            offset: 141
+        }
+    ));
+    assert_eq!(err.to_string(), "invalid UTF-8 sequence at byte offset 141");
+}
+
+#[test]
+fn test_environment_gates() {
+    let workflow = load_workflow("environment-gates.yml");
+    let gate = |id: &str| {
+        let Job::NormalJob(job) = &workflow.jobs[id] else {
+            panic!("expected normal job");
+        };
+        job.environment_gate()
+    };
+
+    assert!(gate("build").is_none());
+
+    let staging = gate("staging").unwrap();
+    assert_eq!(staging.name, NameKind::Literal("staging"));
+    assert!(staging.url.is_none());
+    assert!(staging.required_refs.is_empty());
+
+    let production = gate("production").unwrap();
+    assert_eq!(production.name, NameKind::Literal("production"));
+    assert_eq!(production.url, Some("https://example.com"));
+    assert_eq!(production.required_refs, ["refs/heads/main"]);
+
+    let dynamic = gate("dynamic").unwrap();
+    assert_eq!(dynamic.name, NameKind::Expression("${{ inputs.target }}"));
+    assert_eq!(dynamic.required_refs, ["refs/heads/release"]);
+
+    let gates = workflow.environment_gates();
+    assert_eq!(
+        gates.into_iter().collect::<Vec<_>>(),
+        [
+            (
+                NameKind::Literal("staging"),
+                vec!["staging", "staging-smoke"]
+            ),
+            (NameKind::Literal("production"), vec!["production"]),
+            (
+                NameKind::Expression("${{ inputs.target }}"),
+                vec!["dynamic"]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_triggers() {
+    let workflow = load_workflow("pip-audit-ci.yml");
+    assert_eq!(
+        workflow.triggers(),
+        [EventKind::PullRequest, EventKind::Push, EventKind::Schedule]
+    );
+    assert!(workflow.has_trigger(BareEvent::Push));
+    assert!(workflow.has_trigger(EventKind::Schedule));
+    assert!(!workflow.has_trigger(BareEvent::PullRequestTarget));
+
+    let workflow = Workflow::from_str(
+        r#"
+on:
+  pull_request_target:
+    types: [opened]
+  workflow_dispatch:
+  issue_comment:
+jobs: {}
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        workflow.triggers(),
+        [
+            EventKind::IssueComment,
+            EventKind::PullRequestTarget,
+            EventKind::WorkflowDispatch
+        ]
+    );
+    assert!(workflow.has_trigger(BareEvent::PullRequestTarget));
+    assert!(!workflow.has_trigger(BareEvent::Push));
+
+    let workflow = Workflow::from_str("on: [push, push, release]\njobs: {}").unwrap();
+    assert_eq!(workflow.triggers(), [EventKind::Push, EventKind::Release]);
+
+    let workflow = Workflow::from_str("on: workflow_call\njobs: {}").unwrap();
+    assert_eq!(workflow.triggers(), [EventKind::WorkflowCall]);
+}
+
+#[test]
+fn test_expressions() {
+    let workflow = load_workflow("expressions.yml");
+
+    let expressions = workflow
+        .expressions()
+        .map(|found| {
+            (
+                found.location.job_id,
+                found.location.step_index,
+                found.location.key,
+                found.expression,
+            )
+        })
+        .collect::<Vec<_>>();
+    let expected = [
+        (None, None, "run-name", "inputs.target"),
+        (None, None, "run-name", "github.actor"),
+        (None, None, "env.TARGET", "inputs.target"),
+        (None, None, "concurrency.group", "github.ref"),
+        (
+            Some("test"),
+            None,
+            "if",
+            "github.event_name == 'workflow_dispatch'",
+        ),
+        (
+            Some("test"),
+            None,
+            "outputs.version",
+            "steps.version.outputs.version",
+        ),
+        (Some("test"), Some(1), "run", "matrix.python"),
+        (Some("test"), Some(2), "if", "success()"),
+        (
+            Some("test"),
+            Some(2),
+            "with.python-version",
+            "matrix.python",
+        ),
+        (Some("test"), Some(2), "env.TOKEN", "secrets.TOKEN"),
+        (
+            Some("deploy"),
+            None,
+            "with.version",
+            "needs.test.outputs.version",
+        ),
+    ];
+
+    assert_eq!(expressions.len(), expected.len());
+    for (found, expected) in expressions.iter().zip(expected) {
+        assert_eq!(
+            (found.0, found.1, found.2.as_str(), found.3.as_str()),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_expressions_typed_fields() {
+    let workflow = Workflow::from_str(
+        r#"
+on:
+  workflow_call:
+    outputs:
+      tag:
+        value: ${{ jobs.build.outputs.tag }}
+jobs:
+  build:
+    runs-on: ${{ inputs.runner }}
+    strategy:
+      matrix:
+        python: ["${{ inputs.python }}"]
+        include:
+          - os: ${{ vars.OS }}
+    container:
+      image: python
+      credentials:
+        password: ${{ secrets.PASSWORD }}
+    services:
+      db:
+        image: postgres
+        ports: ["${{ inputs.port }}:5432"]
+    steps: []
+  call:
+    uses: ./.github/workflows/reusable.yml
+    with:
+      ref: ${{ github.ref }}
+    secrets:
+      token: ${{ secrets.TOKEN }}
+"#,
+    )
+    .unwrap();
+
+    let expressions = workflow
+        .expressions()
+        .map(|found| (found.location.job_id, found.location.key, found.expression))
+        .collect::<Vec<_>>();
+    let expected = [
+        (
+            None,
+            "on.workflow_call.outputs.tag.value",
+            "jobs.build.outputs.tag",
+        ),
+        (Some("build"), "runs-on", "inputs.runner"),
+        (Some("build"), "strategy.matrix.include[0].os", "vars.OS"),
+        (Some("build"), "strategy.matrix.python[0]", "inputs.python"),
+        (
+            Some("build"),
+            "container.credentials.password",
+            "secrets.PASSWORD",
+        ),
+        (Some("build"), "services.db.ports[0]", "inputs.port"),
+        (Some("call"), "with.ref", "github.ref"),
+        (Some("call"), "secrets.token", "secrets.TOKEN"),
+    ];
+
+    assert_eq!(expressions.len(), expected.len(), "{expressions:?}");
+    for (found, expected) in expressions.iter().zip(expected) {
+        assert_eq!((found.0, found.1.as_str(), found.2.as_str()), expected);
+    }
+}
+
+#[cfg(feature = "error-paths")]
+#[test]
+fn test_error_paths() {
+    let Err(err) = Workflow::from_str(
+        "
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make test
+      - uses: actions/setup-python@v5
+        with: [3.12]
+",
+    ) else {
+        panic!("expected a broken step body to fail");
+    };
+    assert_eq!(err.path(), Some("jobs.test.steps[1].with"));
+    assert!(err.location().is_some());
+
+    let Err(err) = Workflow::from_str(
+        "
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make test
+        shell: [x]
+",
+    ) else {
+        panic!("expected a broken shell to fail");
+    };
+    assert_eq!(err.path(), Some("jobs.test.steps[0].shell"));
+
+    let Err(err) = Workflow::from_str(
+        "
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    services:
+      redis:
+        image: redis
+        ports: [{}]
+    steps: []
+",
+    ) else {
+        panic!("expected a broken service to fail");
+    };
+    assert_eq!(err.path(), Some("jobs.test.services.redis.ports[0]"));
+
+    // A step without `uses:` or `run:` is reported at the step itself.
+    let Err(err) = Workflow::from_str(
+        "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - name: x\n",
+    ) else {
+        panic!("expected an empty step to fail");
+    };
+    assert_eq!(err.path(), Some("jobs.test.steps[0]"));
+
+    let Err(err) =
+        Workflow::from_str("on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps: {}\n")
+    else {
+        panic!("expected non-sequence steps to fail");
+    };
+    assert_eq!(err.path(), Some("jobs.test.steps"));
+
+    // Errors at the document's root have no path.
+    let Err(err) = Workflow::from_str("on: push") else {
+        panic!("expected missing jobs to fail");
+    };
+    assert_eq!(err.path(), None);
+}
+
+#[test]
+fn test_visitor() {
+    #[derive(Default)]
+    struct Counter {
+        jobs: usize,
+        steps: usize,
+        uses_steps: usize,
+        reusable_calls: usize,
+        runs: usize,
+        expressions: usize,
+    }
+
+    impl WorkflowVisitor<'_> for Counter {
+        fn visit_job(&mut self, _job_id: &str, _job: &Job) {
+            self.jobs += 1;
+        }
+
+        fn visit_step(&mut self, _step: StepHandle<'_>) {
+            self.steps += 1;
+        }
+
+        fn visit_uses(&mut self, _uses: &Uses, context: UsesContext<'_>) {
+            match context {
+                UsesContext::Step { .. } => self.uses_steps += 1,
+                UsesContext::ReusableJob { .. } => self.reusable_calls += 1,
+            }
+        }
+
+        fn visit_run(&mut self, _step: StepHandle<'_>, _run: &str) {
+            self.runs += 1;
+        }
+
+        fn visit_expression(&mut self, _expression: &WorkflowExpression<'_>) {
+            self.expressions += 1;
+        }
+    }
+
+    let workflow = load_workflow("pip-audit-ci.yml");
+    let mut counter = Counter::default();
+    walk_workflow(&workflow, &mut counter);
+    assert_eq!(counter.uses_steps, 2);
+    assert_eq!(counter.runs, 1);
+    assert_eq!(counter.steps, 3);
+
+    let workflow = load_workflow("expressions.yml");
+    let mut counter = Counter::default();
+    walk_workflow(&workflow, &mut counter);
+    assert_eq!(counter.jobs, 2);
+    assert_eq!(counter.steps, 3);
+    assert_eq!(counter.uses_steps, 2);
+    assert_eq!(counter.reusable_calls, 1);
+    assert_eq!(counter.runs, 1);
+    assert_eq!(counter.expressions, workflow.expressions().count());
+
+    // Visits happen in pre-order.
+    #[derive(Default)]
+    struct Order(Vec<String>);
+
+    impl<'a> WorkflowVisitor<'a> for Order {
+        fn visit_job(&mut self, job_id: &'a str, _job: &'a Job) {
+            self.0.push(format!("job {job_id}"));
+        }
+
+        fn visit_step(&mut self, step: StepHandle<'a>) {
+            self.0.push(format!("step {}", step.index));
+        }
+
+        fn visit_expression(&mut self, expression: &WorkflowExpression<'a>) {
+            self.0.push(expression.location.key.clone());
+        }
+    }
+
+    let mut order = Order::default();
+    walk_workflow(&workflow, &mut order);
+    assert_eq!(
+        order.0,
+        [
+            "run-name",
+            "run-name",
+            "env.TARGET",
+            "concurrency.group",
+            "job test",
+            "if",
+            "outputs.version",
+            "step 0",
+            "step 1",
+            "run",
+            "step 2",
+            "if",
+            "with.python-version",
+            "env.TOKEN",
+            "job deploy",
+            "with.version",
+        ]
+    );
+}
+
+#[test]
+fn test_from_str_strict() {
+    let sample_workflows = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-workflows");
+    for sample_workflow in std::fs::read_dir(sample_workflows).unwrap() {
+        let sample_workflow = sample_workflow.unwrap().path();
+        let contents = std::fs::read_to_string(&sample_workflow).unwrap();
+        if let Err(e) = Workflow::from_str_strict(&contents) {
+            panic!("failed to strictly parse {sample_workflow:?}: {e}");
+        }
+    }
+
+    let Err(err) = Workflow::from_str_strict(
+        "
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    timeout_minutes: 10
+    steps: []
+",
+    ) else {
+        panic!("expected misspelled job key to fail");
+    };
+    assert_eq!(err.path(), Some("jobs.test.timeout_minutes"));
+    assert_eq!(err.location(), Some((6, 5)));
+    assert_eq!(
+        err.to_string(),
+        "unknown key `jobs.test.timeout_minutes` at line 6 column 5"
+    );
+
+    let Err(err) = Workflow::from_str_strict(
+        "
+on:
+  push:
+    branches: [main]
+    brnaches-ignore: [dev]
+jobs: {}
+",
+    ) else {
+        panic!("expected stray push key to fail");
+    };
+    assert_eq!(err.path(), Some("on.push.brnaches-ignore"));
+    assert_eq!(err.location(), Some((5, 5)));
+
+    let Err(err) = Workflow::from_str_strict(
+        "
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - run: make
+        working_dir: src
+",
+    ) else {
+        panic!("expected stray step key to fail");
+    };
+    assert_eq!(err.path(), Some("jobs.test.steps[0].working_dir"));
+    assert_eq!(err.location(), Some((8, 9)));
+
+    let Err(err) = Workflow::from_str_strict(
+        "
+on: push
+jobs:
+  call:
+    uses: ./.github/workflows/reusable.yml
+    secret: inherit
+",
+    ) else {
+        panic!("expected stray reusable job key to fail");
+    };
+    assert_eq!(err.path(), Some("jobs.call.secret"));
+
+    let Err(err) = Workflow::from_str_strict("on:\n  psuh:\njobs: {}") else {
+        panic!("expected unknown event to fail");
+    };
+    assert_eq!(err.to_string(), "unknown key `on.psuh` at line 2 column 3");
+
+    // Ordinary errors take precedence.
+    let Err(err) = Workflow::from_str_strict("on: push\nfoo: bar") else {
+        panic!("expected missing jobs to fail");
+    };
+    assert!(err.to_string().contains("missing field `jobs`"));
+}
+
+#[test]
+fn test_unknown_keys() {
+    let sample_workflows = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sample-workflows");
+    for sample_workflow in std::fs::read_dir(sample_workflows).unwrap() {
+        let sample_workflow = sample_workflow.unwrap().path();
+        let workflow = Workflow::from_path(&sample_workflow).unwrap();
+        let unknown = workflow.unknown_keys();
+        assert!(
+            unknown.is_empty(),
+            "unexpected unknown keys in {sample_workflow:?}: {unknown:?}"
+        );
+    }
+
+    let contents = "
+on:
+  push:
+    branches: [main]
+    brnaches-ignore: [dev]
+  workflow_dispatch:
+    input: {}
+  workflow_call:
+    inputs:
+      name:
+        type: string
+        defualt: foo
+  schedule:
+    - cron: '0 0 * * *'
+      timezone: UTC
+  psuh:
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    time-out: 10
+    steps:
+      - uses: actions/checkout@v4
+        with:
+          persist-credentials: false
+        env:
+          FOO: bar
+      - run: make
+        shell: bash
+        working_dir: src
+  call:
+    uses: ./.github/workflows/reusable.yml
+    secret: inherit
+concurrency: ci
+envs:
+  FOO: bar
+";
+    let workflow: Workflow = contents.parse().unwrap();
+
+    let paths = workflow
+        .unknown_keys()
+        .into_iter()
+        .map(|unknown| unknown.path)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            "envs",
+            "on.psuh",
+            "on.push.brnaches-ignore",
+            "on.workflow_dispatch.input",
+            "on.schedule[0].timezone",
+            "on.workflow_call.inputs.name.defualt",
+            "jobs.test.time-out",
+            "jobs.test.steps[1].working_dir",
+            "jobs.call.secret",
+        ]
+    );
+
+    let unknown = workflow.unknown_keys();
+    assert_eq!(unknown[6].key, "time-out");
+    assert_eq!(unknown[6].value.as_u64(), Some(10));
+    assert_eq!(unknown[6].location(contents), Some((20, 5)));
+    assert_eq!(unknown[7].location(contents), Some((29, 9)));
+    assert_eq!(unknown[3].location(contents), Some((7, 5)));
+
+    let job = workflow.get_normal_job("test").unwrap();
+    assert!(job.steps[0].extra.is_empty());
+    assert!(job.steps[0]
+        .with()
+        .unwrap()
+        .contains_key("persist-credentials"));
+    assert!(job.extra.get("runs-on").is_none());
+}